@@ -69,7 +69,7 @@ pub async fn inspect_account(
     }
 }
 
-async fn fetch_account_data(account_id: nssa::AccountId) -> Vec<u8> {
+pub(crate) async fn fetch_account_data(account_id: nssa::AccountId) -> Vec<u8> {
     let wallet_core = wallet::WalletCore::from_env().unwrap_or_else(|e| {
         eprintln!("Failed to initialize wallet: {:?}", e);
         eprintln!("Set NSSA_WALLET_HOME_DIR or use --data <hex>");
@@ -87,9 +87,28 @@ async fn fetch_account_data(account_id: nssa::AccountId) -> Vec<u8> {
     account.data.to_vec()
 }
 
+/// Borsh-decode `data` as IDL type `type_name` and return its field `field`.
+pub(crate) fn decode_field(
+    data: &[u8],
+    idl: &LezIdl,
+    type_name: &str,
+    field: &str,
+) -> Result<Value, String> {
+    let type_def = find_type_def(idl, type_name)
+        .ok_or_else(|| format!("Type '{}' not found in IDL", type_name))?;
+    let mut cursor: &[u8] = data;
+    match decode_type_def(&mut cursor, type_def, idl)? {
+        Value::Object(mut map) => map
+            .remove(field)
+            .ok_or_else(|| format!("Type '{}' has no field '{}'", type_name, field)),
+        _ => Err(format!("Type '{}' is not a struct", type_name)),
+    }
+}
+
 fn find_type_def<'a>(idl: &'a LezIdl, name: &str) -> Option<&'a IdlTypeDef> {
     idl.accounts
         .iter()
+        .chain(&idl.types)
         .find(|a| a.name == name)
         .map(|a| &a.type_)
 }
@@ -198,6 +217,13 @@ fn decode_borsh_value(
             Some(def) => decode_type_def(cursor, def, idl),
             None => Err(format!("Undefined type: {}", name)),
         },
+        IdlType::Tuple { tuple } => {
+            let mut arr = Vec::with_capacity(tuple.len());
+            for elem in tuple {
+                arr.push(decode_borsh_value(cursor, elem, idl)?);
+            }
+            Ok(json!(arr))
+        }
     }
 }
 
@@ -240,6 +266,11 @@ fn decode_primitive(cursor: &mut &[u8], name: &str) -> Result<Value, String> {
             read_exact(cursor, &mut buf)?;
             Ok(json!(hex_encode(&buf)))
         }
+        "account_id" => {
+            let mut buf = [0u8; 32];
+            read_exact(cursor, &mut buf)?;
+            Ok(json!(nssa::AccountId::new(buf).to_string()))
+        }
         other => Err(format!("Unknown primitive type: {}", other)),
     }
 }
@@ -1,11 +1,14 @@
 //! CLI helpers: help text, argument parsing, string utilities.
 
 use std::collections::HashMap;
-use lez_framework_core::idl::{IdlType, IdlInstruction, LezIdl};
+use lez_framework_core::idl::{IdlPdaProgram, IdlType, IdlInstruction, LezIdl};
 
 /// Print help for all commands derived from the IDL.
 pub fn print_help(idl: &LezIdl, binary_name: &str) {
     println!("🔧 {} v{} — IDL-driven CLI", idl.name, idl.version);
+    for line in &idl.docs {
+        println!("   {}", line);
+    }
     println!();
     println!("USAGE:");
     println!("  {} [OPTIONS] <COMMAND> [ARGS]", binary_name);
@@ -31,6 +34,9 @@ pub fn print_help(idl: &LezIdl, binary_name: &str) {
             .collect();
         let all_args: Vec<String> = args_desc.into_iter().chain(acct_desc).collect();
         println!("  {:<20} {}", cmd, all_args.join(" "));
+        if let Some(summary) = ix.docs.first() {
+            println!("  {:<20} {}", "", summary);
+        }
     }
     println!();
     println!("TYPE FORMATS:");
@@ -38,6 +44,13 @@ pub fn print_help(idl: &LezIdl, binary_name: &str) {
     println!("  [u8; N]               Hex string (2*N hex chars) or UTF-8 string (≤N chars, right-padded)");
     println!("  [u32; 8] / program_id Comma-separated u32s: \"0,0,0,0,0,0,0,0\"");
     println!("  Vec<[u8; 32]>         Comma-separated hex strings: \"aabb...00,ccdd...00\"");
+    if !idl.errors.is_empty() {
+        println!();
+        println!("ERRORS:");
+        for err in &idl.errors {
+            println!("  {:<6} {:<20} {}", err.code, err.name, err.msg.as_deref().unwrap_or(""));
+        }
+    }
     println!();
     println!("Auto-generated from IDL. Accounts marked as PDA are computed automatically.");
 }
@@ -45,6 +58,9 @@ pub fn print_help(idl: &LezIdl, binary_name: &str) {
 /// Print detailed help for a single instruction.
 pub fn print_instruction_help(ix: &IdlInstruction) {
     println!("📋 {} — {} account(s), {} arg(s)", ix.name, ix.accounts.len(), ix.args.len());
+    for line in &ix.docs {
+        println!("   {}", line);
+    }
     println!();
     println!("ACCOUNTS:");
     for acc in &ix.accounts {
@@ -52,23 +68,67 @@ pub fn print_instruction_help(ix: &IdlInstruction) {
         if acc.writable { flags.push("mut"); }
         if acc.signer { flags.push("signer"); }
         if acc.init { flags.push("init"); }
+        if acc.init_if_needed { flags.push("init_if_needed"); }
+        let space_flag = match (acc.space, acc.max_space) {
+            (Some(space), _) => Some(format!("space = {}", space)),
+            (None, Some(max)) => Some(format!("max_space = {}", max)),
+            (None, None) => None,
+        };
+        if let Some(flag) = &space_flag { flags.push(flag.as_str()); }
+        let count_flag = match (acc.min_accounts, acc.max_accounts) {
+            (Some(min), Some(max)) => Some(format!("{}..={} accounts", min, max)),
+            (Some(min), None) => Some(format!("≥{} accounts", min)),
+            (None, Some(max)) => Some(format!("≤{} accounts", max)),
+            (None, None) => None,
+        };
+        if let Some(flag) = &count_flag { flags.push(flag.as_str()); }
+        let close_flag = acc.close.as_ref().map(|recipient| format!("close → {}", recipient));
+        if let Some(flag) = &close_flag { flags.push(flag.as_str()); }
         let flags_str = if flags.is_empty() { String::new() } else { format!(" [{}]", flags.join(", ")) };
-        let pda_note = if acc.pda.is_some() { " (PDA — auto-computed)" } else { "" };
-        println!("  {}{}{}", acc.name, flags_str, pda_note);
+        let pda_note = match acc.pda.as_ref().map(|pda| &pda.program) {
+            None => String::new(),
+            Some(None) => " (PDA — auto-computed)".to_string(),
+            Some(Some(IdlPdaProgram::Arg { path })) => format!(" (PDA of program --{} — auto-computed)", snake_to_kebab(path)),
+            Some(Some(IdlPdaProgram::Const { value })) => format!(" (PDA of program {} — auto-computed)", value),
+        };
+        println!("  {}{}{}{}", acc.name, flags_str, pda_note, doc_suffix(&acc.docs));
+        for field in &acc.has_one {
+            println!("      has_one {} (--{}-account defaults to {}.{})", field, snake_to_kebab(field), acc.name, field);
+        }
+        for constraint in &acc.constraints {
+            println!("      requires {}", constraint);
+        }
+    }
+    if let Some(threshold) = &ix.threshold {
+        let members = threshold.members.as_ref()
+            .map(|m| format!(" of {} (--{}-account defaults to members held by the wallet)", m, snake_to_kebab(&threshold.signers)))
+            .unwrap_or_default();
+        println!("  threshold: {} signature(s) from {}{}", threshold.min, threshold.signers, members);
     }
     println!();
     println!("ARGS:");
     for arg in &ix.args {
-        println!("  --{:<25} {} ({}) — format: {}",
-            snake_to_kebab(&arg.name), arg.name, idl_type_display(&arg.type_), idl_type_hint(&arg.type_));
+        println!("  --{:<25} {} ({}) — format: {}{}",
+            snake_to_kebab(&arg.name), arg.name, idl_type_display(&arg.type_), idl_type_hint(&arg.type_),
+            doc_suffix(&arg.docs));
     }
     for acc in &ix.accounts {
-        if acc.pda.is_none() {
-            println!("  --{}-account    Account ID for '{}' (64 hex chars)", snake_to_kebab(&acc.name), acc.name);
+        match acc.pda.as_ref().map(|pda| &pda.program) {
+            None => println!("  --{}-account    Account ID for '{}' (64 hex chars)", snake_to_kebab(&acc.name), acc.name),
+            Some(Some(IdlPdaProgram::Const { value })) => println!(
+                "  --{}-program-id    Program '{}' is derived under, if {} is not a literal",
+                snake_to_kebab(&acc.name), acc.name, value),
+            Some(_) => {}
         }
     }
 }
 
+/// ` — <docs>` on one line, or nothing for undocumented items.
+fn doc_suffix(docs: &[String]) -> String {
+    let text = docs.iter().map(|d| d.trim()).filter(|d| !d.is_empty()).collect::<Vec<_>>().join(" ");
+    if text.is_empty() { String::new() } else { format!(" — {}", text) }
+}
+
 /// Parse CLI args for an instruction into a key-value map.
 pub fn parse_instruction_args(args: &[String], ix: &IdlInstruction) -> HashMap<String, String> {
     let mut map = HashMap::new();
@@ -121,6 +181,10 @@ pub fn idl_type_display(ty: &IdlType) -> String {
         IdlType::Option { option } => format!("Option<{}>", idl_type_display(option)),
         IdlType::Defined { defined } => defined.clone(),
         IdlType::Array { array } => format!("[{}; {}]", idl_type_display(&array.0), array.1),
+        IdlType::Tuple { tuple } => {
+            let elems: Vec<String> = tuple.iter().map(idl_type_display).collect();
+            format!("({})", elems.join(", "))
+        }
     }
 }
 
@@ -145,5 +209,9 @@ pub fn idl_type_hint(ty: &IdlType) -> String {
             IdlType::Primitive(p) if p == "u8" => format!("HEX{}|STR≤{}", array.1 * 2, array.1),
             _ => format!("[_; {}]", array.1),
         },
+        IdlType::Tuple { tuple } => {
+            let elems: Vec<String> = tuple.iter().map(idl_type_hint).collect();
+            elems.join(";")
+        }
     }
 }
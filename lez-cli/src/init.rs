@@ -55,13 +55,13 @@ methods/guest/target/
     write_file(root, "Makefile", &format!(r#"# {name} — LEZ Program
 #
 # Quick start:
-#   make build idl deploy setup
+#   make build deploy setup
 #   make cli ARGS="<command> --arg1 value1"
 
 
 SHELL := /bin/bash
 STATE_FILE := .{snake_name}-state
-IDL_FILE := {name}-idl.json
+IDL_FILE := $(or $(CARGO_TARGET_DIR),target)/idl/{snake_name}.idl.json
 PROGRAMS_DIR := methods/guest/target/riscv32im-risc0-zkvm-elf/docker
 PROGRAM_BIN := $(PROGRAMS_DIR)/{snake_name}.bin
 
@@ -74,13 +74,12 @@ define save_var
 	@mv $(STATE_FILE).tmp $(STATE_FILE)
 endef
 
-.PHONY: help build idl cli deploy setup inspect status clean
+.PHONY: help build cli deploy setup inspect status clean
 
 help: ## Show this help
 	@echo "{name} — LEZ Program"
 	@echo ""
-	@echo "  make build       Build the guest binary (needs risc0 toolchain)"
-	@echo "  make idl         Generate IDL from program source"
+	@echo "  make build       Build the guest binary and its IDL (needs risc0 toolchain)"
 	@echo "  make cli ARGS=   Run the IDL-driven CLI (pass args via ARGS=)"
 	@echo "  make deploy      Deploy program to sequencer"
 	@echo "  make setup       Create accounts needed for the program"
@@ -89,18 +88,16 @@ help: ## Show this help
 	@echo "  make clean       Remove saved state"
 	@echo ""
 	@echo "Example:"
-	@echo "  make build idl deploy"
+	@echo "  make build deploy"
 	@echo "  make cli ARGS=\"--help\""
 	@echo "  make cli ARGS=\"-p $(PROGRAM_BIN) <command> --arg1 value1\""
 
-build: ## Build the guest binary
+build: ## Build the guest binary (#[lez_program] writes the IDL while compiling it)
 	cargo risczero build --manifest-path methods/guest/Cargo.toml
+	cargo build -p {snake_name}-methods
 	@echo ""
 	@echo "✅ Guest binary built: $(PROGRAM_BIN)"
 	@ls -la $(PROGRAM_BIN) 2>/dev/null || true
-
-idl: ## Generate IDL JSON from program source
-	cargo run --bin generate_idl > $(IDL_FILE)
 	@echo "✅ IDL written to $(IDL_FILE)"
 
 cli: ## Run the IDL-driven CLI (ARGS="...")
@@ -131,7 +128,7 @@ status: ## Show saved state and binary info
 	@ls -la $(PROGRAM_BIN) 2>/dev/null || echo "  {snake_name}.bin: NOT BUILT (run 'make build')"
 	@echo ""
 	@echo "IDL:"
-	@ls -la $(IDL_FILE) 2>/dev/null || echo "  $(IDL_FILE): NOT GENERATED (run 'make idl')"
+	@ls -la $(IDL_FILE) 2>/dev/null || echo "  $(IDL_FILE): NOT GENERATED (run 'make build')"
 
 clean: ## Remove saved state
 	rm -f $(STATE_FILE) $(STATE_FILE).tmp
@@ -152,19 +149,16 @@ A LEZ program built with [lez-framework](https://github.com/logos-co/spel).
 ## Quick Start
 
 ```bash
-# 1. Build the guest binary
+# 1. Build the guest binary (also writes the IDL to target/idl/)
 make build
 
-# 2. Generate the IDL (auto-extracts from #[lez_program] annotations)
-make idl
-
-# 3. Deploy to sequencer
+# 2. Deploy to sequencer
 make deploy
 
-# 4. See available commands (auto-generated from your program)
+# 3. See available commands (auto-generated from your program)
 make cli ARGS="--help"
 
-# 5. Run an instruction
+# 4. Run an instruction
 make cli ARGS="-p methods/guest/target/riscv32im-risc0-zkvm-elf/docker/{snake_name}.bin \\
   <command> --arg1 value1 --arg2 value2"
 
@@ -177,8 +171,7 @@ make cli ARGS="--dry-run -p methods/guest/target/riscv32im-risc0-zkvm-elf/docker
 
 | Target | Description |
 |--------|-------------|
-| `make build` | Build the guest binary (risc0) |
-| `make idl` | Generate IDL JSON from program source |
+| `make build` | Build the guest binary (risc0) and its IDL |
 | `make cli ARGS="..."` | Run the IDL-driven CLI |
 | `make deploy` | Deploy program to sequencer |
 | `make inspect` | Show ProgramId for built binary |
@@ -197,10 +190,9 @@ make cli ARGS="--dry-run -p methods/guest/target/riscv32im-risc0-zkvm-elf/docker
 │       └── src/bin/{snake_name}.rs
 ├── examples/             # CLI tools
 │   └── src/bin/
-│       ├── generate_idl.rs    # One-liner IDL generator
 │       └── {snake_name}_cli.rs # Three-line CLI wrapper
 ├── Makefile
-└── {name}-idl.json       # Auto-generated IDL
+└── target/idl/{snake_name}.idl.json  # IDL, written when the guest is compiled
 ```
 
 ## How It Works
@@ -296,6 +288,11 @@ mod {snake_name} {{
     use super::*;
 
     /// Initialize the program state.
+    ///
+    /// `state` is a PDA of this program. The program's ID is its image ID,
+    /// which the guest cannot know when it is built, so the generated code
+    /// checks the PDA against the program that owns it in the runtime's
+    /// pre-state, once it is claimed here.
     #[instruction]
     pub fn initialize(
         #[account(init, pda = literal("state"))]
@@ -334,10 +331,6 @@ name = "{snake_name}-examples"
 version = "0.1.0"
 edition = "2021"
 
-[[bin]]
-name = "generate_idl"
-path = "src/bin/generate_idl.rs"
-
 [[bin]]
 name = "{snake_name}_cli"
 path = "src/bin/{snake_name}_cli.rs"
@@ -347,17 +340,7 @@ lez-framework = {{ git = "https://github.com/jimmy-claw/lez-framework.git" }}
 nssa_core = {{ git = "https://github.com/logos-blockchain/lssa.git", rev = "767b5afd388c7981bcdf6f5b5c80159607e07e5b" }}
 lez-cli = {{ git = "https://github.com/jimmy-claw/lez-framework.git" }}
 {snake_name}_core = {{ path = "../{snake_name}_core" }}
-serde_json = "1.0"
 tokio = {{ version = "1.28.2", features = ["net", "rt-multi-thread", "sync", "macros"] }}
-"#));
-
-    // generate_idl.rs
-    write_file(root, "examples/src/bin/generate_idl.rs", &format!(r#"/// Generate IDL JSON for the {name} program.
-///
-/// Usage:
-///   cargo run --bin generate_idl > {name}-idl.json
-
-lez_framework::generate_idl!("../methods/guest/src/bin/{snake_name}.rs");
 "#));
 
     // CLI wrapper
@@ -400,7 +383,7 @@ async fn main() {
     println!("  cd {}", name);
     println!("  # Edit methods/guest/src/bin/{}.rs with your program logic", snake_name);
     println!("  # Edit {}_core/src/lib.rs with your types", snake_name);
-    println!("  make idl        # Generate the IDL");
+    println!("  make build      # Build the guest and its IDL");
     println!("  make cli ARGS=\"--help\"  # See available commands");
 }
 
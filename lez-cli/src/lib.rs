@@ -23,7 +23,7 @@ use cli::{print_help, parse_instruction_args, snake_to_kebab};
 use init::init_project;
 use inspect::inspect_binaries;
 use tx::execute_instruction;
-use pda::compute_pda_from_seeds;
+use pda::{compute_pda_from_seeds, pda_program_id};
 use lez_framework_core::idl::{LezIdl, IdlSeed};
 use parse::ParsedValue;
 use std::collections::HashMap;
@@ -251,11 +251,11 @@ fn compute_pda_command(idl: &LezIdl, program_path: &str, program_id_hex: Option<
 
     // Find account definition with PDA seeds
     let pda_def = idl.instructions.iter()
-        .flat_map(|ix| &ix.accounts)
-        .find(|acc| acc.name == account_name || snake_to_kebab(&acc.name) == account_name)
-        .and_then(|acc| acc.pda.as_ref());
+        .flat_map(|ix| ix.accounts.iter().map(move |acc| (ix, acc)))
+        .find(|(_, acc)| acc.name == account_name || snake_to_kebab(&acc.name) == account_name)
+        .and_then(|(ix, acc)| Some((ix, acc, acc.pda.as_ref()?)));
 
-    let pda_def = match pda_def {
+    let (ix, acc, pda_def) = match pda_def {
         Some(p) => p,
         None => {
             eprintln!("❌ No PDA account named '{}' found in IDL", account_name);
@@ -294,6 +294,19 @@ fn compute_pda_command(idl: &LezIdl, program_path: &str, program_id_hex: Option<
         }
     }
 
+    // Parse arg seeds as their type, so they get the same encoding as in the guest
+    for seed in &pda_def.seeds {
+        let IdlSeed::Arg { path, .. } = seed else { continue };
+        let (Some(ty), Some(ParsedValue::Str(raw))) = (ix.seed_arg_type(seed), seed_args.get(path).cloned()) else { continue };
+        match parse::parse_value(&raw, ty) {
+            Ok(value) => { seed_args.insert(path.clone(), value); }
+            Err(e) => {
+                eprintln!("❌ --{}: {}", snake_to_kebab(path), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Get program_id: from global --program-id flag, or by loading the binary
     use nssa::program::Program;
     use crate::hex::decode_bytes_32;
@@ -324,6 +337,17 @@ fn compute_pda_command(idl: &LezIdl, program_path: &str, program_id_hex: Option<
         std::process::exit(1);
     };
 
+    // A cross-program PDA is derived under the program named in the IDL
+    let program_flag = format!("{}_program_id", acc.name);
+    let program_override = match seed_args.get(&program_flag) {
+        Some(ParsedValue::Str(raw)) => Some(raw.as_str()),
+        _ => None,
+    };
+    let program_id = pda_program_id(pda_def, &program_id, &seed_args, program_override).unwrap_or_else(|e| {
+        eprintln!("❌ {} — provide --{}", e, snake_to_kebab(&program_flag));
+        std::process::exit(1);
+    });
+
     // Compute PDA
     match compute_pda_from_seeds(&pda_def.seeds, &program_id, &HashMap::new(), &seed_args) {
         Ok(account_id) => {
@@ -336,7 +360,10 @@ fn compute_pda_command(idl: &LezIdl, program_path: &str, program_id_hex: Option<
             for seed in &pda_def.seeds {
                 match seed {
                     IdlSeed::Const { value } => eprintln!("  const: {:?}", value),
-                    IdlSeed::Arg { path } => eprintln!("  arg: --{}", path.replace('_', "-")),
+                    IdlSeed::Arg { path, .. } => match ix.seed_arg_type(seed) {
+                        Some(ty) => eprintln!("  arg: --{} ({})", path.replace('_', "-"), cli::idl_type_display(ty)),
+                        None => eprintln!("  arg: --{}", path.replace('_', "-")),
+                    },
                     IdlSeed::Account { path } => eprintln!("  account: {}", path),
                 }
             }
@@ -1,23 +1,31 @@
 //! IDL type-aware value parsing from CLI strings.
 
 use lez_framework_core::idl::IdlType;
-use crate::hex::{hex_decode, hex_encode};
+use base58::ToBase58;
+use crate::hex::{decode_bytes_32, hex_decode, hex_encode};
 
 /// A parsed CLI value with type information preserved.
 #[derive(Debug, Clone)]
 pub enum ParsedValue {
     Bool(bool),
     U8(u8),
+    U16(u16),
     U32(u32),
     U64(u64),
     U128(u128),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
     Str(String),
+    AccountId([u8; 32]),
     ByteArray(Vec<u8>),         // [u8; N]
     U32Array(Vec<u32>),         // [u32; N] / ProgramId
     ByteArrayVec(Vec<Vec<u8>>), // Vec<[u8; 32]>
     None,                       // Option::None
     Some(Box<ParsedValue>),     // Option::Some
-    Raw(String),                // fallback
+    Tuple(Vec<ParsedValue>),    // (A, B, ...)
 }
 
 impl std::fmt::Display for ParsedValue {
@@ -25,10 +33,17 @@ impl std::fmt::Display for ParsedValue {
         match self {
             ParsedValue::Bool(v) => write!(f, "{}", v),
             ParsedValue::U8(v) => write!(f, "{}", v),
+            ParsedValue::U16(v) => write!(f, "{}", v),
             ParsedValue::U32(v) => write!(f, "{}", v),
             ParsedValue::U64(v) => write!(f, "{}", v),
             ParsedValue::U128(v) => write!(f, "{}", v),
+            ParsedValue::I8(v) => write!(f, "{}", v),
+            ParsedValue::I16(v) => write!(f, "{}", v),
+            ParsedValue::I32(v) => write!(f, "{}", v),
+            ParsedValue::I64(v) => write!(f, "{}", v),
+            ParsedValue::I128(v) => write!(f, "{}", v),
             ParsedValue::Str(s) => write!(f, "\"{}\"", s),
+            ParsedValue::AccountId(bytes) => write!(f, "{}", bytes.to_base58()),
             ParsedValue::ByteArray(bytes) => {
                 if let Ok(s) = std::str::from_utf8(bytes) {
                     if s.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
@@ -48,7 +63,10 @@ impl std::fmt::Display for ParsedValue {
             }
             ParsedValue::None => write!(f, "None"),
             ParsedValue::Some(inner) => write!(f, "Some({})", inner),
-            ParsedValue::Raw(s) => write!(f, "{}", s),
+            ParsedValue::Tuple(elems) => {
+                let strs: Vec<String> = elems.iter().map(|v| v.to_string()).collect();
+                write!(f, "({})", strs.join(", "))
+            }
         }
     }
 }
@@ -66,24 +84,60 @@ pub fn parse_value(raw: &str, ty: &IdlType) -> Result<ParsedValue, String> {
                 Ok(ParsedValue::Some(Box::new(parse_value(raw, option)?)))
             }
         }
-        IdlType::Defined { defined } => Ok(ParsedValue::Raw(format!("{}({})", defined, raw))),
+        IdlType::Defined { defined } => {
+            Err(format!("'{}' arguments cannot be passed on the command line", defined))
+        }
+        IdlType::Tuple { tuple } => {
+            // Elements are separated by ';' so they may contain comma lists
+            let parts: Vec<&str> = raw.split(';').map(|s| s.trim()).collect();
+            if parts.len() != tuple.len() {
+                return Err(format!(
+                    "Expected {} ';'-separated tuple elements, got {}",
+                    tuple.len(),
+                    parts.len()
+                ));
+            }
+            let elems = parts
+                .iter()
+                .zip(tuple)
+                .map(|(part, ty)| parse_value(part, ty))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(ParsedValue::Tuple(elems))
+        }
     }
 }
 
 fn parse_primitive(raw: &str, prim: &str) -> Result<ParsedValue, String> {
+    fn int<T>(raw: &str, prim: &str, wrap: fn(T) -> ParsedValue) -> Result<ParsedValue, String>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        raw.parse::<T>().map(wrap).map_err(|e| format!("Invalid {} '{}': {}", prim, raw, e))
+    }
+
     match prim {
-        "u8" => raw.parse::<u8>().map(ParsedValue::U8).map_err(|e| format!("Invalid u8 '{}': {}", raw, e)),
-        "u32" => raw.parse::<u32>().map(ParsedValue::U32).map_err(|e| format!("Invalid u32 '{}': {}", raw, e)),
-        "u64" => raw.parse::<u64>().map(ParsedValue::U64).map_err(|e| format!("Invalid u64 '{}': {}", raw, e)),
-        "u128" => raw.parse::<u128>().map(ParsedValue::U128).map_err(|e| format!("Invalid u128 '{}': {}", raw, e)),
+        "u8" => int(raw, prim, ParsedValue::U8),
+        "u16" => int(raw, prim, ParsedValue::U16),
+        "u32" => int(raw, prim, ParsedValue::U32),
+        "u64" => int(raw, prim, ParsedValue::U64),
+        "u128" => int(raw, prim, ParsedValue::U128),
+        "i8" => int(raw, prim, ParsedValue::I8),
+        "i16" => int(raw, prim, ParsedValue::I16),
+        "i32" => int(raw, prim, ParsedValue::I32),
+        "i64" => int(raw, prim, ParsedValue::I64),
+        "i128" => int(raw, prim, ParsedValue::I128),
         "program_id" => parse_program_id(raw),
+        "account_id" => decode_bytes_32(raw)
+            .map(ParsedValue::AccountId)
+            .map_err(|e| format!("Invalid account_id '{}': {}", raw, e)),
         "bool" => match raw {
             "true" | "1" | "yes" => Ok(ParsedValue::Bool(true)),
             "false" | "0" | "no" => Ok(ParsedValue::Bool(false)),
             _ => Err(format!("Invalid bool '{}': expected true/false", raw)),
         },
         "string" | "String" => Ok(ParsedValue::Str(raw.to_string())),
-        other => Ok(ParsedValue::Raw(format!("{}({})", other, raw))),
+        other => Err(format!("Unsupported type '{}'", other)),
     }
 }
 
@@ -152,7 +206,7 @@ fn parse_array(raw: &str, elem_type: &IdlType, size: usize) -> Result<ParsedValu
             }
             Ok(ParsedValue::U32Array(vals))
         }
-        _ => Ok(ParsedValue::Raw(raw.to_string())),
+        other => Err(unsupported_element("array", other)),
     }
 }
 
@@ -182,28 +236,30 @@ fn parse_vec(raw: &str, elem_type: &IdlType) -> Result<ParsedValue, String> {
                 }
                 Ok(ParsedValue::ByteArrayVec(result))
             }
-            _ => Ok(ParsedValue::Raw(raw.to_string())),
+            _ => Err(unsupported_element("Vec", elem_type)),
         },
         // Vec<u8> — comma-separated decimal values
         IdlType::Primitive(p) if p == "u8" => {
-            let bytes: Result<Vec<u8>, _> = raw.split(',')
-                .map(|s| s.trim().parse::<u8>())
-                .collect();
-            match bytes {
-                Ok(b) => Ok(ParsedValue::ByteArray(b)),
-                Err(_) => Ok(ParsedValue::Raw(raw.to_string())),
-            }
+            raw.split(',')
+                .map(|s| s.trim().parse::<u8>().map_err(|e| format!("Invalid u8 '{}': {}", s.trim(), e)))
+                .collect::<Result<Vec<u8>, _>>()
+                .map(ParsedValue::ByteArray)
         }
         // Vec<u32> — comma-separated decimal values
         IdlType::Primitive(p) if p == "u32" => {
-            let vals: Result<Vec<u32>, _> = raw.split(',')
-                .map(|s| s.trim().parse::<u32>())
-                .collect();
-            match vals {
-                Ok(v) => Ok(ParsedValue::U32Array(v)),
-                Err(_) => Ok(ParsedValue::Raw(raw.to_string())),
-            }
+            raw.split(',')
+                .map(|s| s.trim().parse::<u32>().map_err(|e| format!("Invalid u32 '{}': {}", s.trim(), e)))
+                .collect::<Result<Vec<u32>, _>>()
+                .map(ParsedValue::U32Array)
         }
-        _ => Ok(ParsedValue::Raw(raw.to_string())),
+        _ => Err(unsupported_element("Vec", elem_type)),
     }
 }
+
+fn unsupported_element(container: &str, elem_type: &IdlType) -> String {
+    format!(
+        "Unsupported {} element type {}",
+        container,
+        serde_json::to_string(elem_type).unwrap_or_else(|_| format!("{:?}", elem_type))
+    )
+}
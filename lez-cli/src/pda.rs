@@ -3,8 +3,9 @@
 use std::collections::HashMap;
 use nssa::AccountId;
 use nssa_core::program::{PdaSeed, ProgramId};
-use lez_framework_core::idl::IdlSeed;
-use crate::parse::ParsedValue;
+use lez_framework_core::idl::{IdlPda, IdlPdaProgram, IdlSeed, IdlType};
+use lez_framework_core::pda::PdaSeedArg;
+use crate::parse::{parse_value, ParsedValue};
 
 /// Resolve a single seed to 32 bytes.
 fn resolve_seed(
@@ -34,7 +35,7 @@ fn resolve_seed(
                 })?;
             Ok(*account_id.value())
         }
-        IdlSeed::Arg { path } => {
+        IdlSeed::Arg { path, .. } => {
             let val = parsed_args
                 .get(path)
                 .ok_or_else(|| {
@@ -43,41 +44,70 @@ fn resolve_seed(
                         path
                     )
                 })?;
-            // Convert ParsedValue to 32 bytes
-            match val {
-                ParsedValue::ByteArray(b) => {
-                    if b.len() != 32 {
-                        return Err(format!("Arg '{}' is {} bytes, expected 32", path, b.len()));
-                    }
-                    let mut bytes = [0u8; 32];
-                    bytes.copy_from_slice(b);
-                    Ok(bytes)
-                }
-                ParsedValue::U64(n) => {
-                    let mut bytes = [0u8; 32];
-                    bytes[24..32].copy_from_slice(&n.to_be_bytes());
-                    Ok(bytes)
-                }
-                ParsedValue::U128(n) => {
-                    let mut bytes = [0u8; 32];
-                    bytes[16..32].copy_from_slice(&n.to_be_bytes());
-                    Ok(bytes)
-                }
-                ParsedValue::Str(s) => {
-                    let mut bytes = [0u8; 32];
-                    let src = s.as_bytes();
-                    if src.len() > 32 {
-                        return Err(format!("String arg '{}' exceeds 32 bytes", path));
-                    }
-                    bytes[..src.len()].copy_from_slice(src);
-                    Ok(bytes)
-                }
-                _ => Err(format!(
-                    "Arg '{}' has unsupported type for PDA seed. Expected bytes32, u64, u128, or string.",
-                    path
-                )),
-            }
+            arg_seed(val).ok_or_else(|| format!(
+                "Arg '{}' has unsupported type for PDA seed. Expected an integer, bool, string, byte array, account ID or program ID.",
+                path
+            ))
+        }
+    }
+}
+
+/// The canonical seed of a parsed argument, as encoded in the guest by
+/// `lez_framework_core::pda::PdaSeedArg`.
+fn arg_seed(val: &ParsedValue) -> Option<[u8; 32]> {
+    Some(match val {
+        ParsedValue::Bool(b) => b.to_seed_bytes(),
+        ParsedValue::U8(n) => n.to_seed_bytes(),
+        ParsedValue::U16(n) => n.to_seed_bytes(),
+        ParsedValue::U32(n) => n.to_seed_bytes(),
+        ParsedValue::U64(n) => n.to_seed_bytes(),
+        ParsedValue::U128(n) => n.to_seed_bytes(),
+        ParsedValue::I8(n) => n.to_seed_bytes(),
+        ParsedValue::I16(n) => n.to_seed_bytes(),
+        ParsedValue::I32(n) => n.to_seed_bytes(),
+        ParsedValue::I64(n) => n.to_seed_bytes(),
+        ParsedValue::I128(n) => n.to_seed_bytes(),
+        ParsedValue::Str(s) => s.to_seed_bytes(),
+        ParsedValue::AccountId(bytes) => AccountId::new(*bytes).to_seed_bytes(),
+        ParsedValue::ByteArray(bytes) => bytes.to_seed_bytes(),
+        ParsedValue::U32Array(words) => <[u32; 8]>::try_from(words.as_slice()).ok()?.to_seed_bytes(),
+        _ => return None,
+    })
+}
+
+/// The program a PDA is derived under: its IDL `program`, or `own` when
+/// absent. A `const` that is not a literal program ID is taken from
+/// `program_override`.
+pub fn pda_program_id(
+    pda: &IdlPda,
+    own: &ProgramId,
+    parsed_args: &HashMap<String, ParsedValue>,
+    program_override: Option<&str>,
+) -> Result<ProgramId, String> {
+    match &pda.program {
+        None => Ok(*own),
+        Some(IdlPdaProgram::Arg { path }) => {
+            let val = parsed_args
+                .get(path)
+                .ok_or_else(|| format!("PDA program references arg '{}' which wasn't provided", path))?;
+            program_id_of(val).ok_or_else(|| format!("Arg '{}' is not a program ID", path))
+        }
+        Some(IdlPdaProgram::Const { value }) => {
+            let raw = program_override.unwrap_or(value);
+            program_id_of(&ParsedValue::Str(raw.to_string()))
+                .ok_or_else(|| format!("PDA program '{}' is not a literal program ID", value))
+        }
+    }
+}
+
+fn program_id_of(val: &ParsedValue) -> Option<ProgramId> {
+    match val {
+        ParsedValue::U32Array(words) => words.as_slice().try_into().ok(),
+        ParsedValue::Str(raw) => {
+            let parsed = parse_value(raw, &IdlType::Primitive("program_id".to_string())).ok()?;
+            program_id_of(&parsed)
         }
+        _ => None,
     }
 }
 
@@ -148,7 +178,7 @@ mod tests {
     fn test_arg_seed_bytes32() {
         let seeds = vec![
             IdlSeed::Const { value: "multisig_state__".to_string() },
-            IdlSeed::Arg { path: "create_key".to_string() },
+            IdlSeed::Arg { path: "create_key".to_string(), type_: None },
         ];
         let program_id: ProgramId = [1u32; 8];
         let mut args = HashMap::new();
@@ -161,7 +191,7 @@ mod tests {
     fn test_arg_seed_u64() {
         let seeds = vec![
             IdlSeed::Const { value: "proposal".to_string() },
-            IdlSeed::Arg { path: "index".to_string() },
+            IdlSeed::Arg { path: "index".to_string(), type_: None },
         ];
         let program_id: ProgramId = [1u32; 8];
         let mut args = HashMap::new();
@@ -170,9 +200,79 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_arg_seeds_match_guest_encoding() {
+        assert_eq!(arg_seed(&ParsedValue::U64(5)), Some(5u64.to_seed_bytes()));
+        assert_eq!(arg_seed(&ParsedValue::U128(5)), Some(5u128.to_seed_bytes()));
+        let long = "x".repeat(40);
+        assert_eq!(arg_seed(&ParsedValue::Str(long.clone())), Some(long.to_seed_bytes()));
+        assert_eq!(arg_seed(&ParsedValue::ByteArray(vec![9u8; 32])), Some([9u8; 32]));
+        let words = vec![1u32, 2, 3, 4, 5, 6, 7, 8];
+        let program_id: ProgramId = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(arg_seed(&ParsedValue::U32Array(words)), Some(program_id.to_seed_bytes()));
+        assert_eq!(arg_seed(&ParsedValue::None), None);
+    }
+
+    /// `raw` parsed as `ty` seeds the PDA exactly like `expected` in the guest.
+    fn assert_cli_seed(ty: &str, raw: &str, expected: impl PdaSeedArg) {
+        let val = parse_value(raw, &IdlType::Primitive(ty.to_string())).unwrap();
+        assert_eq!(arg_seed(&val), Some(expected.to_seed_bytes()), "{} {}", ty, raw);
+    }
+
+    #[test]
+    fn test_u16_seed_matches_core() {
+        assert_cli_seed("u16", "513", 513u16);
+    }
+
+    #[test]
+    fn test_i8_seed_matches_core() {
+        assert_cli_seed("i8", "-2", -2i8);
+    }
+
+    #[test]
+    fn test_i16_seed_matches_core() {
+        assert_cli_seed("i16", "-300", -300i16);
+    }
+
+    #[test]
+    fn test_i32_seed_matches_core() {
+        assert_cli_seed("i32", "-70000", -70000i32);
+    }
+
+    #[test]
+    fn test_i64_seed_matches_core() {
+        assert_cli_seed("i64", "-5", -5i64);
+    }
+
+    #[test]
+    fn test_i128_seed_matches_core() {
+        assert_cli_seed("i128", "-170141183460469231731687303715884105728", i128::MIN);
+    }
+
+    #[test]
+    fn test_account_id_seed_matches_core() {
+        assert_cli_seed("account_id", &"0a".repeat(32), AccountId::new([10u8; 32]));
+    }
+
+    #[test]
+    fn test_pda_program_id() {
+        let own: ProgramId = [1u32; 8];
+        let mut pda = IdlPda { seeds: vec![], program: None };
+        assert_eq!(pda_program_id(&pda, &own, &HashMap::new(), None).unwrap(), own);
+
+        pda.program = Some(IdlPdaProgram::Arg { path: "token_program".to_string() });
+        let mut args = HashMap::new();
+        args.insert("token_program".to_string(), ParsedValue::U32Array(vec![2u32; 8]));
+        assert_eq!(pda_program_id(&pda, &own, &args, None).unwrap(), [2u32; 8]);
+
+        pda.program = Some(IdlPdaProgram::Const { value: "TOKEN_PROGRAM_ID".to_string() });
+        assert!(pda_program_id(&pda, &own, &args, None).is_err());
+        assert_eq!(pda_program_id(&pda, &own, &args, Some("3,3,3,3,3,3,3,3")).unwrap(), [3u32; 8]);
+    }
+
     #[test]
     fn test_missing_arg_errors() {
-        let seeds = vec![IdlSeed::Arg { path: "missing".to_string() }];
+        let seeds = vec![IdlSeed::Arg { path: "missing".to_string(), type_: None }];
         let program_id: ProgramId = [1u32; 8];
         let result = compute_pda_from_seeds(&seeds, &program_id, &HashMap::new(), &HashMap::new());
         assert!(result.is_err());
@@ -202,7 +302,7 @@ mod tests {
     fn test_multi_seed_differs_from_single() {
         let seeds_multi = vec![
             IdlSeed::Const { value: "test".to_string() },
-            IdlSeed::Arg { path: "key".to_string() },
+            IdlSeed::Arg { path: "key".to_string(), type_: None },
         ];
         let seeds_single = vec![
             IdlSeed::Const { value: "test".to_string() },
@@ -1,95 +1,136 @@
 //! risc0-compatible serialization for IDL instruction data.
 
-use lez_framework_core::idl::IdlType;
+use lez_framework_core::idl::{IdlDispatch, IdlType, LezIdl};
 use crate::parse::ParsedValue;
 
+/// The leading words that select instruction `name`: its variant index, or
+/// with `dispatch = "discriminator"` its 8-byte discriminator as a
+/// little-endian `u64` (two words).
+///
+/// Fails if the IDL has no instruction `name` or its discriminator is not
+/// 8 bytes long.
+pub fn instruction_tag_risc0(idl: &LezIdl, name: &str) -> Result<Vec<u32>, String> {
+    let unknown = || format!("unknown instruction '{}'", name);
+    match idl.dispatch {
+        IdlDispatch::Index => Ok(vec![idl.instruction_index(name).ok_or_else(unknown)?]),
+        IdlDispatch::Discriminator => {
+            let disc = idl.instruction_discriminator(name).ok_or_else(unknown)?;
+            let bytes: [u8; 8] = disc.as_slice().try_into().map_err(|_| {
+                format!("discriminator of '{}' is {} bytes, expected 8", name, disc.len())
+            })?;
+            let disc = u64::from_le_bytes(bytes);
+            Ok(vec![disc as u32, (disc >> 32) as u32])
+        }
+    }
+}
+
 /// Serialize an instruction to risc0 serde format (Vec<u32>).
 ///
-/// Produces: variant_index (u32), then each field serialized in order.
-/// Matches `risc0_zkvm::serde::to_vec` for an enum struct variant.
+/// Produces: the instruction tag (see [`instruction_tag_risc0`]), then each
+/// field serialized in order. Matches `risc0_zkvm::serde::to_vec` for the
+/// program's `Instruction` enum.
+///
+/// Fails if a value does not match its IDL type, or the type has no
+/// command-line form.
 pub fn serialize_to_risc0(
-    variant_index: u32,
+    tag: Vec<u32>,
     parsed_args: &[(&IdlType, &ParsedValue)],
-) -> Vec<u32> {
-    let mut out = vec![variant_index];
+) -> Result<Vec<u32>, String> {
+    let mut out = tag;
     for (ty, val) in parsed_args {
-        serialize_value_risc0(&mut out, ty, val);
+        serialize_value_risc0(&mut out, ty, val)?;
     }
-    out
+    Ok(out)
 }
 
-fn serialize_value_risc0(out: &mut Vec<u32>, ty: &IdlType, val: &ParsedValue) {
+fn serialize_value_risc0(out: &mut Vec<u32>, ty: &IdlType, val: &ParsedValue) -> Result<(), String> {
     match (ty, val) {
         (IdlType::Primitive(p), _) => serialize_primitive_risc0(out, p.as_str(), val),
         (IdlType::Array { array }, _) => serialize_array_risc0(out, &array.0, array.1, val),
         (IdlType::Vec { vec }, _) => serialize_vec_risc0(out, vec, val),
         (IdlType::Option { option: _ }, ParsedValue::None) => {
             out.push(0);
+            Ok(())
         }
         (IdlType::Option { option }, ParsedValue::Some(inner)) => {
             out.push(1);
-            serialize_value_risc0(out, option, inner);
+            serialize_value_risc0(out, option, inner)
         }
         (IdlType::Option { option }, _) => {
             out.push(1);
-            serialize_value_risc0(out, option, val);
+            serialize_value_risc0(out, option, val)
         }
-        _ => {
-            eprintln!("⚠️  Cannot serialize Defined/Raw type in risc0 format: {:?}", val);
+        (IdlType::Tuple { tuple }, ParsedValue::Tuple(elems)) if tuple.len() == elems.len() => {
+            for (ty, val) in tuple.iter().zip(elems) {
+                serialize_value_risc0(out, ty, val)?;
+            }
+            Ok(())
+        }
+        (IdlType::Defined { defined }, _) => {
+            Err(format!("Cannot serialize '{}' in risc0 format", defined))
         }
+        _ => Err(format!("Value {} does not match its type", val)),
     }
 }
 
-fn serialize_primitive_risc0(out: &mut Vec<u32>, prim: &str, val: &ParsedValue) {
+fn serialize_primitive_risc0(out: &mut Vec<u32>, prim: &str, val: &ParsedValue) -> Result<(), String> {
     match (prim, val) {
         ("bool", ParsedValue::Bool(b)) => out.push(if *b { 1 } else { 0 }),
+        // Integers narrower than a word are widened to one, signed ones sign-extended
         ("u8", ParsedValue::U8(v)) => out.push(*v as u32),
+        ("u16", ParsedValue::U16(v)) => out.push(*v as u32),
         ("u32", ParsedValue::U32(v)) => out.push(*v),
-        ("u64", ParsedValue::U64(v)) => {
-            out.push(*v as u32);
-            out.push((*v >> 32) as u32);
-        }
-        ("u128", ParsedValue::U128(v)) => {
-            let bytes = v.to_le_bytes();
-            for chunk in bytes.chunks(4) {
-                out.push(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
-            }
-        }
-        ("program_id", ParsedValue::U32Array(vals)) => {
+        ("i8", ParsedValue::I8(v)) => out.push(*v as i32 as u32),
+        ("i16", ParsedValue::I16(v)) => out.push(*v as i32 as u32),
+        ("i32", ParsedValue::I32(v)) => out.push(*v as u32),
+        ("u64", ParsedValue::U64(v)) => serialize_u64(out, *v),
+        ("i64", ParsedValue::I64(v)) => serialize_u64(out, *v as u64),
+        ("u128", ParsedValue::U128(v)) => serialize_bytes_padded(out, &v.to_le_bytes()),
+        ("i128", ParsedValue::I128(v)) => serialize_bytes_padded(out, &v.to_le_bytes()),
+        ("program_id", ParsedValue::U32Array(vals)) if vals.len() == 8 => {
             for v in vals {
                 out.push(*v);
             }
         }
+        // `AccountId { value: [u8; 32] }`: one word per byte
+        ("account_id", ParsedValue::AccountId(bytes)) => {
+            for b in bytes {
+                out.push(*b as u32);
+            }
+        }
         ("string" | "String", ParsedValue::Str(s)) => {
             let bytes = s.as_bytes();
             out.push(bytes.len() as u32);
             serialize_bytes_padded(out, bytes);
         }
-        _ => {
-            eprintln!("⚠️  Type mismatch in risc0 serialization: prim={}, val={:?}", prim, val);
-        }
+        _ => return Err(format!("Value {} is not a valid {}", val, prim)),
     }
+    Ok(())
+}
+
+fn serialize_u64(out: &mut Vec<u32>, v: u64) {
+    out.push(v as u32);
+    out.push((v >> 32) as u32);
 }
 
-fn serialize_array_risc0(out: &mut Vec<u32>, elem_type: &IdlType, _size: usize, val: &ParsedValue) {
+fn serialize_array_risc0(out: &mut Vec<u32>, elem_type: &IdlType, size: usize, val: &ParsedValue) -> Result<(), String> {
     match (elem_type, val) {
-        (IdlType::Primitive(p), ParsedValue::ByteArray(bytes)) if p == "u8" => {
+        (IdlType::Primitive(p), ParsedValue::ByteArray(bytes)) if p == "u8" && bytes.len() == size => {
             for b in bytes {
                 out.push(*b as u32);
             }
         }
-        (IdlType::Primitive(p), ParsedValue::U32Array(vals)) if p == "u32" => {
+        (IdlType::Primitive(p), ParsedValue::U32Array(vals)) if p == "u32" && vals.len() == size => {
             for v in vals {
                 out.push(*v);
             }
         }
-        _ => {
-            eprintln!("⚠️  Cannot serialize array type in risc0 format: {:?}", val);
-        }
+        _ => return Err(format!("Value {} is not a valid array of {} elements", val, size)),
     }
+    Ok(())
 }
 
-fn serialize_vec_risc0(out: &mut Vec<u32>, elem_type: &IdlType, val: &ParsedValue) {
+fn serialize_vec_risc0(out: &mut Vec<u32>, elem_type: &IdlType, val: &ParsedValue) -> Result<(), String> {
     match (elem_type, val) {
         // Vec<u32> — comma-separated decimal values
         (IdlType::Primitive(p), ParsedValue::U32Array(vals)) if p == "u32" => {
@@ -105,35 +146,21 @@ fn serialize_vec_risc0(out: &mut Vec<u32>, elem_type: &IdlType, val: &ParsedValu
                 out.push(*b as u32);
             }
         }
-        // Vec<u32> — passed as Raw CSV string (e.g. "0,200,0,0,0")
-        (IdlType::Primitive(p), ParsedValue::Raw(s)) if p == "u32" => {
-            let vals: Vec<u32> = s.split(',')
-                .filter_map(|x| x.trim().parse::<u32>().ok())
-                .collect();
-            out.push(vals.len() as u32);
-            for v in vals {
-                out.push(v);
-            }
-        }
-        (IdlType::Array { array }, ParsedValue::ByteArrayVec(vecs)) => {
+        // Vec<[u8; N]>
+        (IdlType::Array { array }, ParsedValue::ByteArrayVec(vecs))
+            if matches!(&*array.0, IdlType::Primitive(p) if p == "u8")
+                && vecs.iter().all(|v| v.len() == array.1) =>
+        {
             out.push(vecs.len() as u32);
-            match &*array.0 {
-                IdlType::Primitive(p) if p == "u8" => {
-                    for v in vecs {
-                        for b in v {
-                            out.push(*b as u32);
-                        }
-                    }
-                }
-                _ => {
-                    eprintln!("⚠️  Cannot serialize Vec element type in risc0 format");
+            for v in vecs {
+                for b in v {
+                    out.push(*b as u32);
                 }
             }
         }
-        _ => {
-            eprintln!("⚠️  Cannot serialize Vec type in risc0 format: {:?}", val);
-        }
+        _ => return Err(format!("Value {} is not a valid Vec", val)),
     }
+    Ok(())
 }
 
 fn serialize_bytes_padded(out: &mut Vec<u32>, bytes: &[u8]) {
@@ -147,3 +174,90 @@ fn serialize_bytes_padded(out: &mut Vec<u32>, bytes: &[u8]) {
         i += 4;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_tag_risc0() {
+        let mut idl: LezIdl = serde_json::from_str(r#"{
+            "version": "0.1.0",
+            "name": "treasury",
+            "instructions": [
+                {"name": "initialize", "accounts": [], "args": []},
+                {"name": "transfer", "index": 5, "accounts": [], "args": []}
+            ]
+        }"#).unwrap();
+        assert_eq!(instruction_tag_risc0(&idl, "initialize").unwrap(), vec![0]);
+        assert_eq!(instruction_tag_risc0(&idl, "transfer").unwrap(), vec![5]);
+        assert!(instruction_tag_risc0(&idl, "missing").is_err());
+
+        // The discriminator bytes, in order, as two little-endian words
+        idl.dispatch = IdlDispatch::Discriminator;
+        let disc = lez_framework_core::idl::compute_discriminator("transfer");
+        let words = instruction_tag_risc0(&idl, "transfer").unwrap();
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        assert_eq!(bytes, disc);
+        assert!(instruction_tag_risc0(&idl, "missing").is_err());
+
+        idl.instructions[1].discriminator = Some(vec![1, 2, 3]);
+        assert!(instruction_tag_risc0(&idl, "transfer").is_err());
+    }
+
+    #[test]
+    fn test_serialize_tuple_and_option() {
+        use crate::parse::parse_value;
+        let ty: IdlType = serde_json::from_str(r#"{"tuple": ["u64", {"option": "u8"}]}"#).unwrap();
+        let val = parse_value("7; 3", &ty).unwrap();
+        assert_eq!(val.to_string(), "(7, Some(3))");
+        assert_eq!(serialize_to_risc0(vec![2], &[(&ty, &val)]).unwrap(), vec![2, 7, 0, 1, 3]);
+
+        let val = parse_value("8;none", &ty).unwrap();
+        assert_eq!(serialize_to_risc0(vec![], &[(&ty, &val)]).unwrap(), vec![8, 0, 0]);
+        assert!(parse_value("8", &ty).is_err());
+    }
+
+    #[test]
+    fn test_serialize_signed_and_account_id() {
+        use crate::parse::parse_value;
+        let prim = |p: &str| IdlType::Primitive(p.to_string());
+        let cases: [(&str, &str, Vec<u32>); 6] = [
+            ("u16", "65535", vec![0xffff]),
+            ("i8", "-1", vec![u32::MAX]),
+            ("i16", "-2", vec![u32::MAX - 1]),
+            ("i32", "-3", vec![u32::MAX - 2]),
+            ("i64", "-1", vec![u32::MAX, u32::MAX]),
+            ("i128", "-1", vec![u32::MAX; 4]),
+        ];
+        for (ty, raw, words) in cases {
+            let ty = prim(ty);
+            let val = parse_value(raw, &ty).unwrap();
+            assert_eq!(serialize_to_risc0(vec![], &[(&ty, &val)]).unwrap(), words, "{}", raw);
+        }
+
+        let ty = prim("account_id");
+        let val = parse_value(&"07".repeat(32), &ty).unwrap();
+        assert_eq!(serialize_to_risc0(vec![], &[(&ty, &val)]).unwrap(), vec![7; 32]);
+        assert!(parse_value("07", &ty).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_or_mismatched_values_fail() {
+        use crate::parse::parse_value;
+        let u64_ty = IdlType::Primitive("u64".to_string());
+        assert!(serialize_to_risc0(vec![], &[(&u64_ty, &ParsedValue::U8(1))]).is_err());
+
+        let defined: IdlType = serde_json::from_str(r#"{"defined": "Status"}"#).unwrap();
+        assert!(parse_value("Open", &defined).is_err());
+        assert!(serialize_to_risc0(vec![], &[(&defined, &ParsedValue::U8(1))]).is_err());
+
+        for ty in [r#"{"vec": "u64"}"#, r#"{"array": ["u16", 2]}"#, r#"{"vec": {"array": ["u32", 2]}}"#] {
+            let ty: IdlType = serde_json::from_str(ty).unwrap();
+            assert!(parse_value("1,2", &ty).is_err(), "{:?}", ty);
+        }
+        let bytes: IdlType = serde_json::from_str(r#"{"vec": "u8"}"#).unwrap();
+        assert!(parse_value("1,x", &bytes).is_err());
+        assert!(parse_value("i9", &IdlType::Primitive("f32".to_string())).is_err());
+    }
+}
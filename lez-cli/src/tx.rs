@@ -1,17 +1,19 @@
 //! Transaction building and submission.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::process;
 use nssa::program::Program;
 use nssa::public_transaction::{Message, WitnessSet};
 use nssa::{AccountId, PublicTransaction};
 use nssa_core::program::ProgramId;
-use lez_framework_core::idl::{IdlSeed, LezIdl, IdlInstruction};
-use crate::hex::{hex_encode, decode_bytes_32};
+use lez_framework_core::failure::LezFailure;
+use lez_framework_core::idl::{IdlAccountItem, IdlDispatch, IdlSeed, LezIdl, IdlInstruction};
+use crate::account_inspect::{decode_field, fetch_account_data};
+use crate::hex::{hex_decode, hex_encode, decode_bytes_32};
 use crate::parse::{parse_value, ParsedValue};
-use crate::serialize::serialize_to_risc0;
-use crate::pda::compute_pda_from_seeds;
+use crate::serialize::{instruction_tag_risc0, serialize_to_risc0};
+use crate::pda::{compute_pda_from_seeds, pda_program_id};
 use crate::cli::{snake_to_kebab, to_pascal_case};
 use wallet::WalletCore;
 
@@ -54,8 +56,16 @@ pub async fn execute_instruction(
         }
     }
     for acc in &ix.accounts {
-        // rest accounts are variadic (0 or more) — never required
-        if acc.pda.is_none() && !acc.rest {
+        // rest accounts are variadic — required only with a `min`, unless
+        // they are threshold signers gathered from the stored members;
+        // `has_one` accounts can be read from the account holding them
+        let required = if acc.rest {
+            acc.min_accounts.unwrap_or(0) > 0
+                && !ix.threshold.as_ref().is_some_and(|t| t.signers == acc.name && t.members.is_some())
+        } else {
+            relation_holder(ix, &acc.name).is_none()
+        };
+        if acc.pda.is_none() && required {
             let key = format!("{}-account", snake_to_kebab(&acc.name));
             if !args.contains_key(&key) {
                 missing.push(format!("--{}", key));
@@ -85,8 +95,8 @@ pub async fn execute_instruction(
     let mut rest_accounts: Vec<(&str, Vec<Vec<u8>>)> = Vec::new();
     for acc in &ix.accounts {
         if acc.pda.is_some() { continue; }
-        if acc.rest { let key = format!("{}-account", snake_to_kebab(&acc.name)); if !args.contains_key(&key) { continue; } }
         let key = format!("{}-account", snake_to_kebab(&acc.name));
+        if (acc.rest || relation_holder(ix, &acc.name).is_some()) && !args.contains_key(&key) { continue; }
         if acc.rest {
             // variadic: optional, comma-separated list of account IDs (0 entries is valid)
             let entries: Vec<Vec<u8>> = if let Some(raw) = args.get(&key) {
@@ -103,6 +113,10 @@ pub async fn execute_instruction(
             } else {
                 vec![] // rest accounts are optional — 0 is valid
             };
+            if let Err(e) = check_rest_count(acc, entries.len()) {
+                eprintln!("❌ --{}: {}", key, e);
+                has_errors = true;
+            }
             rest_accounts.push((&acc.name, entries));
         } else {
             let raw = args.get(&key).unwrap();
@@ -115,9 +129,15 @@ pub async fn execute_instruction(
     if has_errors { process::exit(1); }
 
     // Build risc0 serialized data
-    let ix_index = idl.instructions.iter().position(|i| i.name == ix.name).unwrap_or(0);
+    let ix_tag = match instruction_tag_risc0(idl, &ix.name) {
+        Ok(tag) => tag,
+        Err(e) => { eprintln!("❌ {}", e); process::exit(1); }
+    };
     let risc0_args: Vec<_> = parsed_args.iter().map(|(_, ty, val)| (*ty, val)).collect();
-    let instruction_data = serialize_to_risc0(ix_index as u32, &risc0_args);
+    let instruction_data = match serialize_to_risc0(ix_tag.clone(), &risc0_args) {
+        Ok(data) => data,
+        Err(e) => { eprintln!("❌ {}", e); process::exit(1); }
+    };
 
     // Display
     println!("Accounts:");
@@ -134,9 +154,10 @@ pub async fn execute_instruction(
                     }
                 }
             }
-        } else {
-            let account_bytes = parsed_accounts.iter().find(|(n, _)| *n == acc.name).unwrap();
-            println!("  📦 {} → 0x{}", acc.name, hex_encode(&account_bytes.1));
+        } else if let Some((_, bytes)) = parsed_accounts.iter().find(|(n, _)| *n == acc.name) {
+            println!("  📦 {} → 0x{}", acc.name, hex_encode(bytes));
+        } else if let Some(holder) = relation_holder(ix, &acc.name) {
+            println!("  📦 {} → auto-filled from {}.{}", acc.name, holder.name, acc.name);
         }
     }
     println!();
@@ -151,7 +172,13 @@ pub async fn execute_instruction(
     } else {
         println!("  program: {}", program_path);
     }
-    println!("  instruction index: {}", ix_index);
+    match idl.dispatch {
+        IdlDispatch::Index => println!("  instruction index: {}", ix_tag[0]),
+        IdlDispatch::Discriminator => println!(
+            "  instruction discriminator: 0x{}",
+            hex_encode(&ix_tag.iter().flat_map(|w| w.to_le_bytes()).collect::<Vec<u8>>())
+        ),
+    }
     println!("  instruction: {} {{", to_pascal_case(&ix.name));
     for (name, _, val) in &parsed_args {
         println!("    {}: {},", name, val);
@@ -212,50 +239,106 @@ pub async fn execute_instruction(
         }
     }
 
-    // Resolve external account references needed by PDA seeds
-    for acc in &ix.accounts {
-        if let Some(pda) = &acc.pda {
-            for seed in &pda.seeds {
-                if let IdlSeed::Account { path } = seed {
-                    if !account_map.contains_key(path) {
-                        let key = format!("{}-account", snake_to_kebab(path));
-                        if let Some(raw) = args.get(&key) {
-                            match decode_bytes_32(raw) {
-                                Ok(bytes) => {
-                                    println!("  ℹ️  Using --{} for PDA seed '{}'", key, path);
-                                    account_map.insert(path.clone(), AccountId::new(bytes));
-                                }
-                                Err(e) => { eprintln!("❌ --{}: {}", key, e); process::exit(1); }
-                            }
-                        } else {
-                            eprintln!("❌ PDA '{}' requires account '{}' — provide --{}", acc.name, path, key);
-                            process::exit(1);
-                        }
+    let mut parsed_arg_map: HashMap<String, ParsedValue> = HashMap::new();
+    for (name, _, val) in &parsed_args {
+        parsed_arg_map.insert(name.to_string(), val.clone());
+    }
+
+    // Derive the remaining accounts, each after the accounts it depends on:
+    // a PDA after its seed accounts, a `has_one` account after its holder
+    let known: HashSet<&str> = account_map.keys().map(String::as_str).collect();
+    let steps = resolution_order(ix, &known).unwrap_or_else(|e| {
+        eprintln!("❌ {}", e);
+        process::exit(1);
+    });
+    for step in steps {
+        match step {
+            Resolve::Pda(acc) => {
+                let pda = acc.pda.as_ref().unwrap();
+                let program_flag = format!("{}-program-id", snake_to_kebab(&acc.name));
+                let pda_program = pda_program_id(pda, &program_id, &parsed_arg_map, args.get(&program_flag).map(String::as_str))
+                    .unwrap_or_else(|e| {
+                        eprintln!("❌ PDA '{}': {} — provide --{}", acc.name, e, program_flag);
+                        process::exit(1);
+                    });
+                match compute_pda_from_seeds(&pda.seeds, &pda_program, &account_map, &parsed_arg_map) {
+                    Ok(id) => {
+                        println!("  PDA {} → {}", acc.name, id);
+                        account_map.insert(acc.name.clone(), id);
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Failed to compute PDA for '{}': {}", acc.name, e);
+                        process::exit(1);
                     }
                 }
             }
+            // Filled from the on-chain data of the account holding it
+            Resolve::Relation { account: acc, holder } => {
+                let path = format!("{}.{}", holder.name, acc.name);
+                let id = read_account_field(idl, ix, &account_map, &path).await
+                    .and_then(|value| json_account_id(&value))
+                    .unwrap_or_else(|e| {
+                        eprintln!("❌ Failed to read '{}': {} — provide --{}-account", path, e, snake_to_kebab(&acc.name));
+                        process::exit(1);
+                    });
+                let id = AccountId::new(id);
+                println!("  ℹ️  {} → {} (from {})", acc.name, id, path);
+                account_map.insert(acc.name.clone(), id);
+            }
         }
     }
 
-    let mut parsed_arg_map: HashMap<String, ParsedValue> = HashMap::new();
-    for (name, _, val) in &parsed_args {
-        parsed_arg_map.insert(name.to_string(), val.clone());
-    }
+    let wallet_core = WalletCore::from_env().unwrap_or_else(|e| {
+        eprintln!("❌ Failed to initialize wallet: {:?}", e);
+        eprintln!("   Set NSSA_WALLET_HOME_DIR environment variable");
+        process::exit(1);
+    });
 
-    // Resolve PDA accounts
-    for acc in &ix.accounts {
-        if let Some(pda) = &acc.pda {
-            match compute_pda_from_seeds(&pda.seeds, &program_id, &account_map, &parsed_arg_map) {
-                Ok(id) => {
-                    println!("  PDA {} → {}", acc.name, id);
-                    account_map.insert(acc.name.clone(), id);
-                }
-                Err(e) => {
-                    eprintln!("❌ Failed to compute PDA for '{}': {}", acc.name, e);
+    // Gather the signers of a `threshold` instruction: the given list, or
+    // the stored members this wallet holds keys for
+    let mut threshold_signers: Vec<AccountId> = Vec::new();
+    if let Some(threshold) = &ix.threshold {
+        let required = match threshold.min.parse::<usize>() {
+            Ok(n) => Ok(n),
+            Err(_) => read_account_field(idl, ix, &account_map, &threshold.min).await
+                .and_then(|value| json_usize(&value)),
+        }.unwrap_or_else(|e| {
+            eprintln!("❌ Failed to read the threshold '{}': {}", threshold.min, e);
+            process::exit(1);
+        });
+        if !rest_accounts.iter().any(|(n, _)| *n == threshold.signers) {
+            let key = format!("{}-account", snake_to_kebab(&threshold.signers));
+            let Some(members) = &threshold.members else {
+                eprintln!("❌ Provide --{} with at least {} signer(s)", key, required);
+                process::exit(1);
+            };
+            let members = read_account_field(idl, ix, &account_map, members).await
+                .and_then(|value| match value.as_array() {
+                    Some(list) => list.iter().map(json_account_id).collect::<Result<Vec<_>, _>>(),
+                    None => Err("member list is not an array".to_string()),
+                })
+                .unwrap_or_else(|e| {
+                    eprintln!("❌ Failed to read the members '{}': {} — provide --{}", members, e, key);
                     process::exit(1);
-                }
-            }
+                });
+            let gathered: Vec<Vec<u8>> = members.into_iter()
+                .filter(|id| wallet_core.storage().user_data.get_pub_account_signing_key(AccountId::new(*id)).is_some())
+                .take(required)
+                .map(|id| id.to_vec())
+                .collect();
+            println!("  ℹ️  Gathered {} signer(s) for '{}' from the wallet", gathered.len(), threshold.signers);
+            rest_accounts.push((&threshold.signers, gathered));
         }
+        let (_, entries) = rest_accounts.iter().find(|(n, _)| *n == threshold.signers).unwrap();
+        if entries.len() < required {
+            eprintln!("❌ '{}' needs at least {} signer(s), got {}", threshold.signers, required, entries.len());
+            process::exit(1);
+        }
+        threshold_signers = entries.iter().map(|bytes| {
+            let mut arr = [0u8; 32];
+            arr.copy_from_slice(bytes);
+            AccountId::new(arr)
+        }).collect();
     }
 
     let mut account_ids: Vec<AccountId> = Vec::new();
@@ -278,16 +361,26 @@ pub async fn execute_instruction(
         }
     }
 
-    let wallet_core = WalletCore::from_env().unwrap_or_else(|e| {
-        eprintln!("❌ Failed to initialize wallet: {:?}", e);
-        eprintln!("   Set NSSA_WALLET_HOME_DIR environment variable");
-        process::exit(1);
-    });
-
-    let signer_accounts: Vec<AccountId> = ix.accounts.iter()
-        .filter(|a| a.signer)
-        .map(|a| *account_map.get(&a.name).unwrap())
-        .collect();
+    // Every element of a `signer` rest account signs, as do threshold signers
+    let mut signer_accounts: Vec<AccountId> = Vec::new();
+    for acc in ix.accounts.iter().filter(|a| a.signer) {
+        if acc.rest {
+            if let Some((_, entries)) = rest_accounts.iter().find(|(n, _)| *n == acc.name) {
+                for bytes in entries {
+                    let mut arr = [0u8; 32];
+                    arr.copy_from_slice(bytes);
+                    signer_accounts.push(AccountId::new(arr));
+                }
+            }
+        } else {
+            signer_accounts.push(*account_map.get(&acc.name).unwrap());
+        }
+    }
+    for id in threshold_signers {
+        if !signer_accounts.contains(&id) {
+            signer_accounts.push(id);
+        }
+    }
 
     let nonces = if signer_accounts.is_empty() {
         vec![]
@@ -310,7 +403,9 @@ pub async fn execute_instruction(
     let tx = PublicTransaction::new(message, witness_set);
 
     let response = wallet_core.sequencer_client.send_tx_public(tx).await.unwrap_or_else(|e| {
-        eprintln!("❌ Failed to submit transaction: {:?}", e);
+        let message = format!("{:?}", e);
+        eprintln!("❌ Failed to submit transaction: {}", message);
+        explain_program_error(idl, &message);
         process::exit(1);
     });
 
@@ -326,8 +421,287 @@ pub async fn execute_instruction(
     match poller.poll_tx(response.tx_hash).await {
         Ok(_) => println!("✅ Transaction confirmed — included in a block."),
         Err(e) => {
-            eprintln!("❌ Transaction NOT confirmed: {e:#}");
+            let message = format!("{e:#}");
+            eprintln!("❌ Transaction NOT confirmed: {}", message);
+            explain_program_error(idl, &message);
             process::exit(1);
         }
     }
 }
+
+/// Check the number of accounts given for a rest account against its
+/// `min_accounts` / `max_accounts`.
+fn check_rest_count(acc: &IdlAccountItem, count: usize) -> Result<(), String> {
+    let min = acc.min_accounts.unwrap_or(0);
+    if count < min {
+        return Err(format!("expected at least {} account(s), got {}", min, count));
+    }
+    if let Some(max) = acc.max_accounts.filter(|max| count > *max) {
+        return Err(format!("expected at most {} account(s), got {}", max, count));
+    }
+    Ok(())
+}
+
+/// Read `<account>.<field>` from the on-chain data of one of the
+/// instruction's accounts, decoded with its IDL `account_type`.
+async fn read_account_field(
+    idl: &LezIdl,
+    ix: &IdlInstruction,
+    account_map: &HashMap<String, AccountId>,
+    path: &str,
+) -> Result<serde_json::Value, String> {
+    let (account, field) = path.split_once('.')
+        .ok_or_else(|| format!("expected <account>.<field>, got '{}'", path))?;
+    let acc = ix.accounts.iter().find(|a| a.name == account)
+        .ok_or_else(|| format!("no account '{}'", account))?;
+    let type_name = acc.account_type.as_deref()
+        .ok_or_else(|| format!("IDL does not record the data type of '{}'", account))?;
+    let id = account_map.get(account)
+        .ok_or_else(|| format!("account '{}' is not resolved", account))?;
+    let data = fetch_account_data(*id).await;
+    decode_field(&data, idl, type_name, field)
+}
+
+/// An account ID decoded by `decode_field`: an `account_id` (base58) or a
+/// `[u8; 32]` (hex).
+fn json_account_id(value: &serde_json::Value) -> Result<[u8; 32], String> {
+    let s = value.as_str().ok_or_else(|| format!("{} is not an account ID", value))?;
+    if s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit()) {
+        let mut arr = [0u8; 32];
+        arr.copy_from_slice(&hex_decode(s)?);
+        return Ok(arr);
+    }
+    decode_bytes_32(s)
+}
+
+/// An unsigned integer decoded by `decode_field` (`u64` and wider are strings).
+fn json_usize(value: &serde_json::Value) -> Result<usize, String> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().and_then(|n| usize::try_from(n).ok()),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("{} is not an unsigned integer", value))
+}
+
+/// The account whose data stores the ID of account `name`, via
+/// `#[account(has_one = name)]`.
+fn relation_holder<'a>(ix: &'a IdlInstruction, name: &str) -> Option<&'a IdlAccountItem> {
+    ix.accounts.iter().find(|a| a.has_one.iter().any(|field| field == name))
+}
+
+/// One account the CLI derives rather than takes from the command line.
+enum Resolve<'a> {
+    /// A PDA, computed from its seeds.
+    Pda(&'a IdlAccountItem),
+    /// A `has_one` account, read from the account holding it.
+    Relation { account: &'a IdlAccountItem, holder: &'a IdlAccountItem },
+}
+
+impl<'a> Resolve<'a> {
+    fn account(&self) -> &'a IdlAccountItem {
+        match *self {
+            Resolve::Pda(account) | Resolve::Relation { account, .. } => account,
+        }
+    }
+
+    /// The first account this step needs that is not `resolved` yet.
+    fn missing(&self, resolved: &HashSet<&str>) -> Option<&'a str> {
+        match *self {
+            Resolve::Pda(acc) => acc.pda.as_ref().unwrap().seeds.iter().find_map(|seed| match seed {
+                IdlSeed::Account { path } if !resolved.contains(path.as_str()) => Some(path.as_str()),
+                _ => None,
+            }),
+            Resolve::Relation { holder, .. } => {
+                Some(holder.name.as_str()).filter(|name| !resolved.contains(name))
+            }
+        }
+    }
+}
+
+/// The order in which to derive the accounts missing from `known`: a PDA
+/// after the accounts its seeds name, a `has_one` account after its holder.
+/// Fails naming an account that nothing provides.
+fn resolution_order<'a>(ix: &'a IdlInstruction, known: &HashSet<&str>) -> Result<Vec<Resolve<'a>>, String> {
+    let mut pending: Vec<Resolve<'a>> = ix.accounts.iter()
+        .filter(|a| !known.contains(a.name.as_str()))
+        .filter_map(|a| match (&a.pda, relation_holder(ix, &a.name)) {
+            (Some(_), _) => Some(Resolve::Pda(a)),
+            (None, Some(holder)) => Some(Resolve::Relation { account: a, holder }),
+            (None, None) => None,
+        })
+        .collect();
+    let mut resolved: HashSet<&str> = known.clone();
+    let mut ordered = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let Some(i) = pending.iter().position(|step| step.missing(&resolved).is_none()) else {
+            let needed = pending[0].missing(&resolved).unwrap();
+            return Err(format!(
+                "'{}' requires account '{}' — provide --{}-account",
+                pending[0].account().name, needed, snake_to_kebab(needed)
+            ));
+        };
+        let step = pending.remove(i);
+        resolved.insert(step.account().name.as_str());
+        ordered.push(step);
+    }
+    Ok(ordered)
+}
+
+/// If `message` carries an encoded `LezFailure` from the guest, print which
+/// instruction failed, the offending account and the named error from the IDL.
+fn explain_program_error(idl: &LezIdl, message: &str) {
+    let Some(failure) = LezFailure::decode(message) else { return };
+    for line in describe_failure(idl, &failure) {
+        eprintln!("   {}", line);
+    }
+}
+
+fn describe_failure(idl: &LezIdl, failure: &LezFailure) -> Vec<String> {
+    let mut lines = vec![format!("Instruction: {}", failure.instruction)];
+
+    if let Some(idx) = failure.account_index {
+        let name = idl.instructions.iter()
+            .find(|ix| ix.name == failure.instruction)
+            .and_then(|ix| ix.accounts.get(idx).or_else(|| ix.accounts.last().filter(|a| a.rest)))
+            .map(|a| a.name.as_str());
+        match name {
+            Some(name) => lines.push(format!("Account: {} (#{})", name, idx)),
+            None => lines.push(format!("Account: #{}", idx)),
+        }
+    }
+
+    match idl.find_error(failure.error_code) {
+        Some(err) => {
+            lines.push(format!("Error {}: {}", failure.error_code, err.name));
+            if let Some(msg) = &err.msg {
+                lines.push(msg.clone());
+            }
+        }
+        None => lines.push(format!("Error {}: {}", failure.error_code, failure.error)),
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lez_framework_core::error::LezError;
+
+    fn idl() -> LezIdl {
+        serde_json::from_str(r#"{
+            "version": "0.1.0",
+            "name": "treasury",
+            "instructions": [{
+                "name": "transfer",
+                "accounts": [
+                    {"name": "from", "writable": true},
+                    {"name": "signers", "rest": true}
+                ],
+                "args": []
+            }],
+            "errors": [{"code": 6010, "name": "Frozen", "msg": "Treasury is frozen"}]
+        }"#).unwrap()
+    }
+
+    #[test]
+    fn test_describe_failure() {
+        let failure = LezFailure::new("transfer", LezError::custom(10, "Treasury is frozen"));
+        let message = format!("guest panicked: {}", failure.encode());
+        let decoded = LezFailure::decode(&message).unwrap();
+        assert_eq!(describe_failure(&idl(), &decoded), vec![
+            "Instruction: transfer",
+            "Error 6010: Frozen",
+            "Treasury is frozen",
+        ]);
+    }
+
+    #[test]
+    fn test_check_rest_count() {
+        let acc: IdlAccountItem = serde_json::from_str(
+            r#"{"name": "members", "rest": true, "min_accounts": 1, "max_accounts": 2}"#,
+        ).unwrap();
+        assert!(check_rest_count(&acc, 0).is_err());
+        assert!(check_rest_count(&acc, 1).is_ok());
+        assert!(check_rest_count(&acc, 2).is_ok());
+        assert_eq!(check_rest_count(&acc, 3).unwrap_err(), "expected at most 2 account(s), got 3");
+    }
+
+    #[test]
+    fn test_json_values() {
+        let hex = "01".repeat(32);
+        assert_eq!(json_account_id(&serde_json::json!(hex)).unwrap(), [1u8; 32]);
+        assert!(json_account_id(&serde_json::json!(5)).is_err());
+        assert_eq!(json_usize(&serde_json::json!(3)).unwrap(), 3);
+        assert_eq!(json_usize(&serde_json::json!("7")).unwrap(), 7);
+        assert!(json_usize(&serde_json::json!(-1)).is_err());
+    }
+
+    #[test]
+    fn test_relation_holder() {
+        let idl: LezIdl = serde_json::from_str(r#"{
+            "version": "0.1.0",
+            "name": "treasury",
+            "instructions": [{
+                "name": "withdraw",
+                "accounts": [
+                    {"name": "state", "account_type": "TreasuryState", "has_one": ["authority"]},
+                    {"name": "authority", "signer": true}
+                ],
+                "args": []
+            }]
+        }"#).unwrap();
+        let ix = &idl.instructions[0];
+        assert_eq!(relation_holder(ix, "authority").map(|a| a.name.as_str()), Some("state"));
+        assert!(relation_holder(ix, "state").is_none());
+    }
+
+    #[test]
+    fn test_has_one_account_resolved_before_pda_it_seeds() {
+        let idl: LezIdl = serde_json::from_str(r#"{
+            "version": "0.1.0",
+            "name": "treasury",
+            "instructions": [{
+                "name": "withdraw",
+                "accounts": [
+                    {"name": "vault", "writable": true, "pda": {"seeds": [
+                        {"kind": "const", "value": "vault"},
+                        {"kind": "account", "path": "authority"}
+                    ]}},
+                    {"name": "state", "account_type": "TreasuryState", "has_one": ["authority"]},
+                    {"name": "authority", "signer": true}
+                ],
+                "args": []
+            }]
+        }"#).unwrap();
+        let ix = &idl.instructions[0];
+        let names = |steps: Vec<Resolve>| -> Vec<String> {
+            steps.iter().map(|step| match step {
+                Resolve::Pda(acc) => format!("pda {}", acc.name),
+                Resolve::Relation { account, holder } => format!("{}.{}", holder.name, account.name),
+            }).collect()
+        };
+
+        let order = resolution_order(ix, &HashSet::from(["state"])).unwrap();
+        assert_eq!(names(order), vec!["state.authority", "pda vault"]);
+
+        let order = resolution_order(ix, &HashSet::from(["state", "authority"])).unwrap();
+        assert_eq!(names(order), vec!["pda vault"]);
+
+        let err = resolution_order(ix, &HashSet::new()).err().unwrap();
+        assert_eq!(err, "'vault' requires account 'authority' — provide --authority-account");
+    }
+
+    #[test]
+    fn test_describe_failure_names_account() {
+        let failure = LezFailure::new("transfer", LezError::PdaMismatch { account_index: 0 });
+        assert_eq!(describe_failure(&idl(), &failure), vec![
+            "Instruction: transfer",
+            "Account: from (#0)",
+            "Error 1009: PDA mismatch for account 0",
+        ]);
+
+        let failure = LezFailure::new("transfer", LezError::AccountNotInitialized { account_index: 3 });
+        assert_eq!(describe_failure(&idl(), &failure)[1], "Account: signers (#3)");
+    }
+}
@@ -8,11 +8,16 @@ use crate::util::*;
 pub fn generate_client(idl: &LezIdl) -> Result<String, String> {
     let mut out = String::new();
     let program_pascal = pascal_case(&idl.name);
+    let custom_serialize = needs_custom_serialize(idl);
 
     // Header
     writeln!(out, "//! Auto-generated client for the {} program.", idl.name).unwrap();
     writeln!(out, "//! Generated by lez-client-gen from IDL v{}.", idl.version).unwrap();
     writeln!(out, "//! DO NOT EDIT — regenerate from IDL instead.").unwrap();
+    if !idl.docs.is_empty() {
+        writeln!(out, "//!").unwrap();
+        write_docs(&mut out, "", "//!", &idl.docs);
+    }
     writeln!(out).unwrap();
 
     // Imports
@@ -20,24 +25,27 @@ pub fn generate_client(idl: &LezIdl) -> Result<String, String> {
     writeln!(out, "    AccountId, ProgramId, PublicTransaction,").unwrap();
     writeln!(out, "    public_transaction::{{Message, WitnessSet}},").unwrap();
     writeln!(out, "}};").unwrap();
-    writeln!(out, "use serde::{{Deserialize, Serialize}};").unwrap();
+    if custom_serialize {
+        writeln!(out, "use serde::Serialize;").unwrap();
+    } else {
+        writeln!(out, "use serde::{{Deserialize, Serialize}};").unwrap();
+    }
     writeln!(out, "use wallet::WalletCore;").unwrap();
     writeln!(out).unwrap();
 
     // PDA helper — SHA-256(seed1 || seed2 || ...) matching on-chain derivation
-    writeln!(out, "/// Compute a PDA by SHA-256 hashing concatenated seeds.").unwrap();
+    writeln!(out, "/// Compute a PDA of `program_id`: a single seed is used directly,").unwrap();
+    writeln!(out, "/// several are combined by SHA-256 hashing them concatenated.").unwrap();
     writeln!(out, "/// Matches the on-chain nssa PDA derivation (not XOR).").unwrap();
-    writeln!(out, "fn compute_pda(seeds: &[&[u8]]) -> AccountId {{").unwrap();
-    writeln!(out, "    use sha2::{{Sha256, Digest}};").unwrap();
-    writeln!(out, "    let mut hasher = Sha256::new();").unwrap();
-    writeln!(out, "    for seed in seeds {{").unwrap();
+    writeln!(out, "fn compute_pda(program_id: &ProgramId, seeds: &[&[u8]]) -> AccountId {{").unwrap();
+    writeln!(out, "    let padded: Vec<[u8; 32]> = seeds.iter().map(|seed| {{").unwrap();
     writeln!(out, "        let mut padded = [0u8; 32];").unwrap();
     writeln!(out, "        let len = seed.len().min(32);").unwrap();
     writeln!(out, "        padded[..len].copy_from_slice(&seed[..len]);").unwrap();
-    writeln!(out, "        hasher.update(&padded);").unwrap();
-    writeln!(out, "    }}").unwrap();
-    writeln!(out, "    let hash: [u8; 32] = hasher.finalize().into();").unwrap();
-    writeln!(out, "    AccountId::from(hash)").unwrap();
+    writeln!(out, "        padded").unwrap();
+    writeln!(out, "    }}).collect();").unwrap();
+    writeln!(out, "    let refs: Vec<&[u8; 32]> = padded.iter().collect();").unwrap();
+    writeln!(out, "    lez_framework_core::pda::compute_pda(program_id, &refs)").unwrap();
     writeln!(out, "}}").unwrap();
     writeln!(out).unwrap();
 
@@ -58,15 +66,21 @@ pub fn generate_client(idl: &LezIdl) -> Result<String, String> {
     writeln!(out).unwrap();
 
     // Instruction enum
-    writeln!(out, "#[derive(Clone, Debug, Serialize, Deserialize)]").unwrap();
+    if custom_serialize {
+        writeln!(out, "#[derive(Clone, Debug)]").unwrap();
+    } else {
+        writeln!(out, "#[derive(Clone, Debug, Serialize, Deserialize)]").unwrap();
+    }
     writeln!(out, "pub enum {}Instruction {{", program_pascal).unwrap();
     for ix in &idl.instructions {
         let variant = pascal_case(&ix.name);
+        write_docs(&mut out, "    ", "///", &ix.docs);
         if ix.args.is_empty() {
             writeln!(out, "    {},", variant).unwrap();
         } else {
             writeln!(out, "    {} {{", variant).unwrap();
             for arg in &ix.args {
+                write_docs(&mut out, "        ", "///", &arg.docs);
                 writeln!(out, "        {}: {},", rust_ident(&arg.name), idl_type_to_rust(&arg.type_)).unwrap();
             }
             writeln!(out, "    }},").unwrap();
@@ -74,12 +88,18 @@ pub fn generate_client(idl: &LezIdl) -> Result<String, String> {
     }
     writeln!(out, "}}").unwrap();
     writeln!(out).unwrap();
+    if custom_serialize {
+        write_instruction_serialize(&mut out, idl, &format!("{}Instruction", program_pascal));
+        writeln!(out).unwrap();
+    }
 
     // Per-instruction account structs
     for ix in &idl.instructions {
         let accounts_name = format!("{}Accounts", pascal_case(&ix.name));
+        writeln!(out, "/// Accounts for `{}`.", ix.name).unwrap();
         writeln!(out, "pub struct {} {{", accounts_name).unwrap();
         for acc in &ix.accounts {
+            write_docs(&mut out, "    ", "///", &acc.docs);
             if acc.rest {
                 writeln!(out, "    pub {}: Vec<AccountId>,", rust_ident(&acc.name)).unwrap();
             } else {
@@ -108,6 +128,7 @@ pub fn generate_client(idl: &LezIdl) -> Result<String, String> {
         let variant = pascal_case(&ix.name);
 
         writeln!(out).unwrap();
+        write_docs(&mut out, "    ", "///", &ix.docs);
         write!(out, "    pub async fn {}(\n        &self,\n        accounts: {}", method, accounts_name).unwrap();
         for arg in &ix.args {
             write!(out, ",\n        {}: {}", rust_ident(&arg.name), idl_type_to_rust(&arg.type_)).unwrap();
@@ -167,13 +188,19 @@ pub fn generate_client(idl: &LezIdl) -> Result<String, String> {
             if let Some(pda) = &acc.pda {
                 writeln!(out).unwrap();
                 let method_name = format!("compute_{}_pda", snake_case(&acc.name));
-                let mut pda_args: Vec<(String, String)> = Vec::new();
+                // Derived under the program named by an arg, or else the one passed in
+                let program = match &pda.program {
+                    Some(IdlPdaProgram::Arg { path }) => snake_case(path),
+                    _ => "program_id".to_string(),
+                };
+                let mut pda_args: Vec<(String, String)> = vec![(program.clone(), "ProgramId".to_string())];
                 for seed in &pda.seeds {
                     match seed {
                         IdlSeed::Account { path } => pda_args.push((snake_case(path), "AccountId".to_string())),
-                        IdlSeed::Arg { path } => {
-                            let ty = ix.args.iter().find(|a| a.name == *path)
-                                .map(|a| idl_type_to_rust(&a.type_))
+                        IdlSeed::Arg { path, .. } if snake_case(path) == program => {}
+                        IdlSeed::Arg { path, .. } => {
+                            let ty = ix.seed_arg_type(seed)
+                                .map(idl_type_to_rust)
                                 .unwrap_or_else(|| "String".to_string());
                             pda_args.push((snake_case(path), ty));
                         }
@@ -186,12 +213,12 @@ pub fn generate_client(idl: &LezIdl) -> Result<String, String> {
                     write!(out, "{}: &{}", name, ty).unwrap();
                 }
                 writeln!(out, ") -> AccountId {{").unwrap();
-                writeln!(out, "        compute_pda(&[").unwrap();
+                writeln!(out, "        compute_pda({}, &[", program).unwrap();
                 for seed in &pda.seeds {
                     match seed {
                         IdlSeed::Const { value } => writeln!(out, "            b\"{}\",", value).unwrap(),
                         IdlSeed::Account { path } => writeln!(out, "            {}.as_ref(),", snake_case(path)).unwrap(),
-                        IdlSeed::Arg { path } => writeln!(out, "            {}.to_string().as_bytes(),", snake_case(path)).unwrap(),
+                        IdlSeed::Arg { path, .. } => writeln!(out, "            &lez_framework_core::pda::PdaSeedArg::to_seed_bytes({}),", snake_case(path)).unwrap(),
                     }
                 }
                 writeln!(out, "        ])").unwrap();
@@ -36,6 +36,9 @@ pub fn generate_ffi(idl: &LezIdl) -> Result<String, String> {
     writeln!(out, "//!   - `wallet_path`: path to NSSA wallet directory").unwrap();
     writeln!(out, "//!   - `sequencer_url`: e.g. \"http://127.0.0.1:3040\"").unwrap();
     writeln!(out, "//!   - `program_id_hex`: 64-char hex string identifying the program").unwrap();
+    writeln!(out, "//!").unwrap();
+    writeln!(out, "//! A PDA of a `const` program other than this one also needs").unwrap();
+    writeln!(out, "//! `<account>_program_id_hex`.").unwrap();
     writeln!(out).unwrap();
 
     // Imports
@@ -52,12 +55,20 @@ pub fn generate_ffi(idl: &LezIdl) -> Result<String, String> {
         writeln!(out, "use {} as ProgramInstruction;", itype).unwrap();
     } else {
         // Generate local instruction enum
-        writeln!(out, "use serde::{{Serialize, Deserialize}};").unwrap();
-        writeln!(out).unwrap();
-        writeln!(out, "#[derive(Debug, Clone, Serialize, Deserialize)]").unwrap();
+        let custom_serialize = needs_custom_serialize(idl);
+        if custom_serialize {
+            writeln!(out, "use serde::Serialize;").unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "#[derive(Debug, Clone)]").unwrap();
+        } else {
+            writeln!(out, "use serde::{{Serialize, Deserialize}};").unwrap();
+            writeln!(out).unwrap();
+            writeln!(out, "#[derive(Debug, Clone, Serialize, Deserialize)]").unwrap();
+        }
         writeln!(out, "pub enum {local_enum} {{").unwrap();
         for ix in &idl.instructions {
             let variant = pascal_case(&ix.name);
+            write_docs(&mut out, "    ", "///", &ix.docs);
             if ix.args.is_empty() {
                 writeln!(out, "    {variant},").unwrap();
             } else {
@@ -65,12 +76,17 @@ pub fn generate_ffi(idl: &LezIdl) -> Result<String, String> {
                 for arg in &ix.args {
                     let name = rust_ident(&arg.name);
                     let ty = idl_type_to_rust(&arg.type_);
+                    write_docs(&mut out, "        ", "///", &arg.docs);
                     writeln!(out, "        {name}: {ty},").unwrap();
                 }
                 writeln!(out, "    }},").unwrap();
             }
         }
         writeln!(out, "}}").unwrap();
+        if custom_serialize {
+            writeln!(out).unwrap();
+            write_instruction_serialize(&mut out, idl, &local_enum);
+        }
     }
     writeln!(out).unwrap();
 
@@ -94,16 +110,21 @@ pub fn generate_ffi(idl: &LezIdl) -> Result<String, String> {
     writeln!(out).unwrap();
 
     // PDA helper
-    writeln!(out, "fn compute_pda(seeds: &[&[u8]]) -> AccountId {{").unwrap();
-    writeln!(out, "    let mut hasher = Sha256::new();").unwrap();
-    writeln!(out, "    for seed in seeds {{").unwrap();
+    writeln!(out, "fn compute_pda(program_id: &ProgramId, seeds: &[&[u8]]) -> AccountId {{").unwrap();
+    writeln!(out, "    let padded: Vec<[u8; 32]> = seeds.iter().map(|seed| {{").unwrap();
     writeln!(out, "        let mut padded = [0u8; 32];").unwrap();
     writeln!(out, "        let len = seed.len().min(32);").unwrap();
     writeln!(out, "        padded[..len].copy_from_slice(&seed[..len]);").unwrap();
-    writeln!(out, "        hasher.update(&padded);").unwrap();
-    writeln!(out, "    }}").unwrap();
-    writeln!(out, "    let hash: [u8; 32] = hasher.finalize().into();").unwrap();
-    writeln!(out, "    AccountId::new(hash)").unwrap();
+    writeln!(out, "        padded").unwrap();
+    writeln!(out, "    }}).collect();").unwrap();
+    writeln!(out, "    let combined = if padded.len() == 1 {{").unwrap();
+    writeln!(out, "        padded[0]").unwrap();
+    writeln!(out, "    }} else {{").unwrap();
+    writeln!(out, "        let mut hasher = Sha256::new();").unwrap();
+    writeln!(out, "        for seed in &padded {{ hasher.update(seed); }}").unwrap();
+    writeln!(out, "        hasher.finalize().into()").unwrap();
+    writeln!(out, "    }};").unwrap();
+    writeln!(out, "    AccountId::from((program_id, &nssa_core::program::PdaSeed::new(combined)))").unwrap();
     writeln!(out, "}}").unwrap();
     writeln!(out).unwrap();
 
@@ -155,6 +176,10 @@ pub fn generate_ffi(idl: &LezIdl) -> Result<String, String> {
         let signer_accounts: Vec<&IdlAccountItem> = ix.accounts.iter().filter(|a| a.signer).collect();
 
         writeln!(out, "/// FFI: {} instruction.", ix.name).unwrap();
+        if !ix.docs.is_empty() {
+            writeln!(out, "///").unwrap();
+            write_docs(&mut out, "", "///", &ix.docs);
+        }
         writeln!(out, "#[no_mangle]").unwrap();
         writeln!(out, "pub extern \"C\" fn {fn_name}(args_json: *const c_char) -> *mut c_char {{").unwrap();
         writeln!(out, "    let args = match cstr_to_str(args_json) {{").unwrap();
@@ -180,42 +205,43 @@ pub fn generate_ffi(idl: &LezIdl) -> Result<String, String> {
         }
         writeln!(out).unwrap();
 
-        // Build a type map for PDA seed arg type checks (same pattern as generate_pda_helpers)
-        let param_type_map: std::collections::HashMap<String, String> = ix.args.iter()
-            .map(|a| (rust_ident(&a.name), idl_type_to_rust(&a.type_)))
-            .collect();
-
-        // Resolve accounts
-        for acc in &ix.accounts {
+        // Resolve accounts: given ones first, then PDAs in seed order
+        for acc in ix.accounts.iter().filter(|a| a.pda.is_none()) {
             let name = rust_ident(&acc.name);
             if acc.rest {
                 // rest accounts parsed from JSON array
                 writeln!(out, "    let {name}: Vec<AccountId> = v[\"{}\"].as_array()", acc.name).unwrap();
                 writeln!(out, "        .ok_or(\"missing {}\")?", acc.name).unwrap();
                 writeln!(out, "        .iter().map(|a| parse_account_id(a.as_str().ok_or(\"expected string\")?)).collect::<Result<Vec<_>,_>>()?;").unwrap();
-            } else if let Some(pda) = &acc.pda {
-                writeln!(out, "    let {name} = compute_pda(&[").unwrap();
-                for seed in &pda.seeds {
-                    match seed {
-                        IdlSeed::Const { value } => writeln!(out, "        b\"{value}\",").unwrap(),
-                        IdlSeed::Account { path } => writeln!(out, "        {}.as_ref(),", rust_ident(path)).unwrap(),
-                        IdlSeed::Arg { path } => {
-                            let pname = rust_ident(path);
-                            let arg_ty = param_type_map.get(&pname).map(|s| s.as_str()).unwrap_or("");
-                            if arg_ty == "u64" {
-                                writeln!(out, "        &{pname}.to_le_bytes(),").unwrap();
-                            } else {
-                                writeln!(out, "        &{pname} as &[u8],").unwrap();
-                            }
-                        }
-                    }
-                }
-                writeln!(out, "    ]);").unwrap();
             } else {
                 writeln!(out, "    let {name} = parse_account_id(v[\"{}\"].as_str().ok_or(\"missing {}\")?)?;",
                     acc.name, acc.name).unwrap();
             }
         }
+        for acc in ix.pda_accounts_in_seed_order() {
+            let name = rust_ident(&acc.name);
+            let pda = acc.pda.as_ref().unwrap();
+            let program = match &pda.program {
+                None => "&program_id".to_string(),
+                Some(IdlPdaProgram::Arg { path }) => format!("&{}", rust_ident(path)),
+                Some(IdlPdaProgram::Const { .. }) => {
+                    let key = format!("{}_program_id_hex", acc.name);
+                    writeln!(out, "    let {name}_program_id = parse_program_id_hex(v[\"{key}\"].as_str().ok_or(\"missing {key}\")?)?;").unwrap();
+                    format!("&{name}_program_id")
+                }
+            };
+            writeln!(out, "    let {name} = compute_pda({program}, &[").unwrap();
+            for seed in &pda.seeds {
+                match seed {
+                    IdlSeed::Const { value } => writeln!(out, "        b\"{value}\",").unwrap(),
+                    IdlSeed::Account { path } => writeln!(out, "        {}.as_ref(),", rust_ident(path)).unwrap(),
+                    IdlSeed::Arg { path, .. } => {
+                        writeln!(out, "        &lez_framework_core::pda::PdaSeedArg::to_seed_bytes(&{}),", rust_ident(path)).unwrap();
+                    }
+                }
+            }
+            writeln!(out, "    ]);").unwrap();
+        }
         writeln!(out).unwrap();
 
         // Build account_ids vec (non-rest accounts first, then rest)
@@ -312,12 +338,17 @@ pub fn generate_pda_helpers(idl: &LezIdl) -> String {
 
                 // Collect function parameters from arg seeds.
                 // Const seeds are inlined; account seeds get a TODO comment.
+                let program = match &pda.program {
+                    Some(IdlPdaProgram::Arg { path }) => rust_ident(path),
+                    _ => "program_id".to_string(),
+                };
                 let mut params: Vec<(String, String)> = Vec::new();
                 for seed in &pda.seeds {
                     match seed {
-                        IdlSeed::Arg { path } => {
-                            let ty = ix.args.iter().find(|a| a.name == *path)
-                                .map(|a| idl_type_to_rust(&a.type_))
+                        IdlSeed::Arg { path, .. } if rust_ident(path) == program => {}
+                        IdlSeed::Arg { path, .. } => {
+                            let ty = ix.seed_arg_type(seed)
+                                .map(idl_type_to_rust)
                                 .unwrap_or_else(|| "[u8; 32]".to_string());
                             // Normalise aliases to raw array types for cleaner FFI signatures
                             let param_ty = match ty.as_str() {
@@ -338,19 +369,20 @@ pub fn generate_pda_helpers(idl: &LezIdl) -> String {
                 writeln!(out).unwrap();
                 let seed_desc: Vec<String> = pda.seeds.iter().map(|s| match s {
                     IdlSeed::Const { value } => format!("const(\"{}\")", value),
-                    IdlSeed::Arg { path } => format!("arg({})", path),
+                    IdlSeed::Arg { path, .. } => format!("arg({})", path),
                     IdlSeed::Account { path } => format!("account({})", path),
                 }).collect();
                 writeln!(out, "/// Compute PDA for `{}` account.", acc.name).unwrap();
                 writeln!(out, "/// Seeds: [{}]", seed_desc.join(", ")).unwrap();
-
-                // Build a type map for seed loops to look up arg types
-                let param_type_map: std::collections::HashMap<String, String> =
-                    params.iter().cloned().collect();
+                match &pda.program {
+                    Some(IdlPdaProgram::Arg { path }) => writeln!(out, "/// Derived under the program `{}`.", path).unwrap(),
+                    Some(IdlPdaProgram::Const { value }) => writeln!(out, "/// Derived under `{}`: pass it as `program_id`.", value).unwrap(),
+                    None => {}
+                }
 
                 // Function signature
                 write!(out, "pub fn compute_{}_pda(", acc_name).unwrap();
-                write!(out, "program_id: &ProgramId").unwrap();
+                write!(out, "{}: &ProgramId", program).unwrap();
                 for (name, ty) in &params {
                     // Primitive scalars (u64, u32, etc.) are passed by value
                     let is_scalar = matches!(ty.as_str(), "u64" | "u32" | "u16" | "u8" | "i64" | "i32" | "i16" | "i8" | "u128" | "i128");
@@ -371,16 +403,9 @@ pub fn generate_pda_helpers(idl: &LezIdl) -> String {
                             writeln!(out, "    let src = b\"{}\";", value).unwrap();
                             writeln!(out, "    seed_bytes[..src.len()].copy_from_slice(src);").unwrap();
                         }
-                        IdlSeed::Arg { path } => {
-                            let pname = rust_ident(path);
-                            let arg_ty = param_type_map.get(&pname).map(|s| s.as_str()).unwrap_or("");
-                            if arg_ty == "u64" {
-                                // u64 single seed: pad little-endian bytes into [u8; 32]
-                                writeln!(out, "    let mut seed_bytes = [0u8; 32];").unwrap();
-                                writeln!(out, "    seed_bytes[..8].copy_from_slice(&{}.to_le_bytes());", pname).unwrap();
-                            } else {
-                                writeln!(out, "    let seed_bytes: [u8; 32] = *{};", pname).unwrap();
-                            }
+                        IdlSeed::Arg { path, .. } => {
+                            // Canonical encoding of the arg's type, as in the guest
+                            writeln!(out, "    let seed_bytes = lez_framework_core::pda::PdaSeedArg::to_seed_bytes(&{});", rust_ident(path)).unwrap();
                         }
                         IdlSeed::Account { path } => {
                             let pname = rust_ident(path);
@@ -389,7 +414,7 @@ pub fn generate_pda_helpers(idl: &LezIdl) -> String {
                         }
                     }
                     writeln!(out, "    let pda_seed = nssa_core::program::PdaSeed::new(seed_bytes);").unwrap();
-                    writeln!(out, "    AccountId::from((program_id, &pda_seed))").unwrap();
+                    writeln!(out, "    AccountId::from(({}, &pda_seed))", program).unwrap();
                 } else {
                     // Multi-seed: SHA-256(seed1 || seed2 || ...) — matches lez-cli/src/pda.rs
                     writeln!(out, "    use sha2::{{Sha256, Digest}};").unwrap();
@@ -404,15 +429,8 @@ pub fn generate_pda_helpers(idl: &LezIdl) -> String {
                                 writeln!(out, "        hasher.update(&padded);").unwrap();
                                 writeln!(out, "    }}").unwrap();
                             }
-                            IdlSeed::Arg { path } => {
-                                let pname = rust_ident(path);
-                                let arg_ty = param_type_map.get(&pname).map(|s| s.as_str()).unwrap_or("");
-                                if arg_ty == "u64" {
-                                    // u64 seed: hash the little-endian bytes directly
-                                    writeln!(out, "    hasher.update(&{}.to_le_bytes());", pname).unwrap();
-                                } else {
-                                    writeln!(out, "    hasher.update({} as &[u8]);", pname).unwrap();
-                                }
+                            IdlSeed::Arg { path, .. } => {
+                                writeln!(out, "    hasher.update(lez_framework_core::pda::PdaSeedArg::to_seed_bytes(&{}));", rust_ident(path)).unwrap();
                             }
                             IdlSeed::Account { path } => {
                                 let pname = rust_ident(path);
@@ -423,7 +441,7 @@ pub fn generate_pda_helpers(idl: &LezIdl) -> String {
                     }
                     writeln!(out, "    let combined: [u8; 32] = hasher.finalize().into();").unwrap();
                     writeln!(out, "    let pda_seed = nssa_core::program::PdaSeed::new(combined);").unwrap();
-                    writeln!(out, "    AccountId::from((program_id, &pda_seed))").unwrap();
+                    writeln!(out, "    AccountId::from(({}, &pda_seed))", program).unwrap();
                 }
                 writeln!(out, "}}").unwrap();
             }
@@ -450,7 +468,20 @@ pub fn generate_header(idl: &LezIdl) -> Result<String, String> {
 
     for ix in &idl.instructions {
         let fn_name = format!("{}_{}", prefix, snake_case(&ix.name));
-        writeln!(out, "/* {} instruction */", ix.name).unwrap();
+        if ix.docs.is_empty() {
+            writeln!(out, "/* {} instruction */", ix.name).unwrap();
+        } else {
+            writeln!(out, "/* {} instruction", ix.name).unwrap();
+            for line in &ix.docs {
+                let line = line.replace("*/", "* /");
+                if line.is_empty() {
+                    writeln!(out, " *").unwrap();
+                } else {
+                    writeln!(out, " * {line}").unwrap();
+                }
+            }
+            writeln!(out, " */").unwrap();
+        }
         writeln!(out, "char* {fn_name}(const char* args_json);").unwrap();
         writeln!(out).unwrap();
     }
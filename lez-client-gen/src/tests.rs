@@ -200,6 +200,94 @@ fn test_rest_accounts() {
     assert!(output.ffi_code.contains("signers"));
 }
 
+#[test]
+fn test_explicit_instruction_indices() {
+    let idl = r#"{
+        "version": "0.1.0",
+        "name": "test_prog",
+        "instructions": [
+            {"name": "close", "index": 4, "accounts": [], "args": []},
+            {"name": "deposit", "index": 1, "accounts": [], "args": [{"name": "amount", "type": "u128"}]}
+        ]
+    }"#;
+    let output = generate_from_idl_json(idl).expect("should handle explicit indices");
+    for code in [&output.client_code, &output.ffi_code] {
+        assert!(code.contains("impl Serialize for TestProgInstruction"));
+        assert!(code.contains("serialize_unit_variant(\"TestProgInstruction\", 4, \"Close\")"));
+        assert!(code.contains("serialize_struct_variant(\"TestProgInstruction\", 1, \"Deposit\", 1)"));
+        assert!(!code.contains("Serialize, Deserialize)]\npub enum TestProgInstruction"));
+    }
+
+    // Positional indices keep the derived impls
+    let output = generate_from_idl_json(SAMPLE_IDL).unwrap();
+    assert!(!output.client_code.contains("impl Serialize for"));
+}
+
+#[test]
+fn test_discriminator_dispatch() {
+    let idl = r#"{
+        "version": "0.1.0",
+        "name": "test_prog",
+        "dispatch": "discriminator",
+        "instructions": [
+            {"name": "deposit", "accounts": [], "args": [{"name": "amount", "type": "u128"}]}
+        ]
+    }"#;
+    let disc = lez_framework_core::idl::compute_discriminator("deposit");
+    let disc = u64::from_le_bytes(disc.try_into().unwrap());
+    let output = generate_from_idl_json(idl).expect("should handle discriminator dispatch");
+    for code in [&output.client_code, &output.ffi_code] {
+        assert!(code.contains("impl Serialize for TestProgInstruction"));
+        assert!(code.contains("serializer.serialize_tuple(2)"));
+        assert!(code.contains(&format!("tuple.serialize_element(&{:#018x}u64)", disc)));
+        assert!(code.contains("tuple.serialize_element(amount)"));
+    }
+}
+
+#[test]
+fn test_composite_arg_types() {
+    let idl = r#"{
+        "version": "0.1.0",
+        "name": "test_prog",
+        "instructions": [{
+            "name": "configure",
+            "accounts": [],
+            "args": [
+                {"name": "label", "type": "string"},
+                {"name": "limits", "type": {"tuple": ["u64", {"option": "i32"}]}},
+                {"name": "keys", "type": {"vec": {"option": {"array": ["u8", 32]}}}}
+            ]
+        }]
+    }"#;
+    let output = generate_from_idl_json(idl).expect("should handle composite types");
+    assert!(output.client_code.contains("label: String,"));
+    assert!(output.client_code.contains("limits: (u64, Option<i32>),"));
+    assert!(output.client_code.contains("keys: Vec<Option<[u8; 32]>>,"));
+}
+
+#[test]
+fn test_docs_are_copied() {
+    let idl = r#"{
+        "version": "0.1.0",
+        "name": "test_prog",
+        "docs": ["A test program."],
+        "instructions": [{
+            "name": "deposit",
+            "docs": ["Deposit funds.", "", "Fails if the vault is frozen."],
+            "accounts": [{"name": "vault", "docs": ["The vault to credit."], "writable": true}],
+            "args": [{"name": "amount", "docs": ["Amount in base units."], "type": "u128"}]
+        }]
+    }"#;
+    let output = generate_from_idl_json(idl).expect("should handle docs");
+    assert!(output.client_code.contains("//! A test program."));
+    assert!(output.client_code.contains("    /// Deposit funds.\n    ///\n    /// Fails if the vault is frozen.\n    Deposit {"));
+    assert!(output.client_code.contains("        /// Amount in base units.\n        amount: u128,"));
+    assert!(output.client_code.contains("    /// The vault to credit.\n    pub vault: AccountId,"));
+    assert!(output.client_code.contains("    /// Fails if the vault is frozen.\n    pub async fn deposit("));
+    assert!(output.ffi_code.contains("/// FFI: deposit instruction.\n///\n/// Deposit funds."));
+    assert!(output.header.contains("/* deposit instruction\n * Deposit funds.\n *\n * Fails if the vault is frozen.\n */"));
+}
+
 #[test]
 fn test_pda_helpers_single_arg_seed() {
     use lez_framework_core::idl::*;
@@ -208,28 +296,44 @@ fn test_pda_helpers_single_arg_seed() {
     let idl = LezIdl {
         version: "0.1.0".to_string(),
         name: "test_program".to_string(),
+        docs: vec![],
         instructions: vec![IdlInstruction {
             name: "create".to_string(),
+            index: None,
+            docs: vec![],
             accounts: vec![IdlAccountItem {
                 name: "multisig_state".to_string(),
+                docs: vec![],
                 writable: true,
                 signer: false,
                 init: true,
+                init_if_needed: false,
+                space: None,
+                max_space: None,
                 owner: None,
                 pda: Some(IdlPda {
-                    seeds: vec![IdlSeed::Arg { path: "create_key".to_string() }],
+                    seeds: vec![IdlSeed::Arg { path: "create_key".to_string(), type_: None }],
+                    program: None,
                 }),
+                account_type: None,
+                has_one: vec![],
+                close: None,
+                constraints: vec![],
                 rest: false,
+                min_accounts: None,
+                max_accounts: None,
                 visibility: vec![],
             }],
             args: vec![IdlArg {
                 name: "create_key".to_string(),
+                docs: vec![],
                 type_: IdlType::Primitive("[u8; 32]".to_string()),
 
             }],
             discriminator: None,
             execution: None,
             variant: None,
+            threshold: None,
         }],
         accounts: vec![],
         types: vec![],
@@ -237,6 +341,7 @@ fn test_pda_helpers_single_arg_seed() {
         spec: None,
         metadata: None,
         instruction_type: None,
+        dispatch: IdlDispatch::Index,
     };
 
     let output = generate_pda_helpers(&idl);
@@ -263,31 +368,47 @@ fn test_pda_helpers_multi_seed() {
     let idl = LezIdl {
         version: "0.1.0".to_string(),
         name: "test_program".to_string(),
+        docs: vec![],
         instructions: vec![IdlInstruction {
             name: "create".to_string(),
+            index: None,
+            docs: vec![],
             accounts: vec![IdlAccountItem {
                 name: "multisig_state".to_string(),
+                docs: vec![],
                 writable: true,
                 signer: false,
                 init: true,
+                init_if_needed: false,
+                space: None,
+                max_space: None,
                 owner: None,
                 pda: Some(IdlPda {
                     seeds: vec![
                         IdlSeed::Const { value: "multisig_state__".to_string() },
-                        IdlSeed::Arg { path: "create_key".to_string() },
+                        IdlSeed::Arg { path: "create_key".to_string(), type_: None },
                     ],
+                    program: None,
                 }),
+                account_type: None,
+                has_one: vec![],
+                close: None,
+                constraints: vec![],
                 rest: false,
+                min_accounts: None,
+                max_accounts: None,
                 visibility: vec![],
             }],
             args: vec![IdlArg {
                 name: "create_key".to_string(),
+                docs: vec![],
                 type_: IdlType::Primitive("[u8; 32]".to_string()),
 
             }],
             discriminator: None,
             execution: None,
             variant: None,
+            threshold: None,
         }],
         accounts: vec![],
         types: vec![],
@@ -295,6 +416,7 @@ fn test_pda_helpers_multi_seed() {
         spec: None,
         metadata: None,
         instruction_type: None,
+        dispatch: IdlDispatch::Index,
     };
 
     let output = generate_pda_helpers(&idl);
@@ -321,30 +443,46 @@ fn test_pda_helpers_deduplication() {
     // Same account name appears in two instructions — should only generate one helper
     let make_ix = |name: &str| IdlInstruction {
         name: name.to_string(),
+        index: None,
+        docs: vec![],
         accounts: vec![IdlAccountItem {
             name: "shared_state".to_string(),
+            docs: vec![],
             writable: true,
             signer: false,
             init: false,
+            init_if_needed: false,
+            space: None,
+            max_space: None,
             owner: None,
             pda: Some(IdlPda {
-                seeds: vec![IdlSeed::Arg { path: "my_key".to_string() }],
+                seeds: vec![IdlSeed::Arg { path: "my_key".to_string(), type_: None }],
+                program: None,
             }),
+            account_type: None,
+            has_one: vec![],
+            close: None,
+            constraints: vec![],
             rest: false,
+            min_accounts: None,
+            max_accounts: None,
             visibility: vec![],
         }],
         args: vec![IdlArg {
             name: "my_key".to_string(),
+            docs: vec![],
             type_: IdlType::Primitive("[u8; 32]".to_string()),
         }],
         discriminator: None,
         execution: None,
         variant: None,
+        threshold: None,
     };
 
     let idl = LezIdl {
         version: "0.1.0".to_string(),
         name: "test_program".to_string(),
+        docs: vec![],
         instructions: vec![make_ix("create"), make_ix("update")],
         accounts: vec![],
         types: vec![],
@@ -352,6 +490,7 @@ fn test_pda_helpers_deduplication() {
         spec: None,
         metadata: None,
         instruction_type: None,
+        dispatch: IdlDispatch::Index,
     };
 
     let output = generate_pda_helpers(&idl);
@@ -390,27 +529,43 @@ fn test_pda_helpers_u64_single_seed() {
     let idl = LezIdl {
         version: "0.1.0".to_string(),
         name: "test_program".to_string(),
+        docs: vec![],
         instructions: vec![IdlInstruction {
             name: "create_proposal".to_string(),
+            index: None,
+            docs: vec![],
             accounts: vec![IdlAccountItem {
                 name: "proposal".to_string(),
+                docs: vec![],
                 writable: true,
                 signer: false,
                 init: true,
+                init_if_needed: false,
+                space: None,
+                max_space: None,
                 owner: None,
                 pda: Some(IdlPda {
-                    seeds: vec![IdlSeed::Arg { path: "proposal_index".to_string() }],
+                    seeds: vec![IdlSeed::Arg { path: "proposal_index".to_string(), type_: None }],
+                    program: None,
                 }),
+                account_type: None,
+                has_one: vec![],
+                close: None,
+                constraints: vec![],
                 rest: false,
+                min_accounts: None,
+                max_accounts: None,
                 visibility: vec![],
             }],
             args: vec![IdlArg {
                 name: "proposal_index".to_string(),
+                docs: vec![],
                 type_: IdlType::Primitive("u64".to_string()),
             }],
             discriminator: None,
             execution: None,
             variant: None,
+            threshold: None,
         }],
         accounts: vec![],
         types: vec![],
@@ -418,6 +573,7 @@ fn test_pda_helpers_u64_single_seed() {
         spec: None,
         metadata: None,
         instruction_type: None,
+        dispatch: IdlDispatch::Index,
     };
 
     let output = generate_pda_helpers(&idl);
@@ -428,9 +584,8 @@ fn test_pda_helpers_u64_single_seed() {
     assert!(!output.contains("proposal_index: &u64"), "u64 param must not be by reference: {}", output);
     assert!(output.contains("-> AccountId"), "missing return type: {}", output);
 
-    // Single u64 seed: uses to_le_bytes() padded into [u8; 32]
-    assert!(output.contains("to_le_bytes()"), "u64 seed must use to_le_bytes: {}", output);
-    assert!(output.contains("seed_bytes[..8].copy_from_slice"), "must copy 8 bytes of u64: {}", output);
+    // Single u64 seed: canonical little-endian seed encoding
+    assert!(output.contains("PdaSeedArg::to_seed_bytes(&proposal_index)"), "u64 seed must use PdaSeedArg: {}", output);
     assert!(output.contains("PdaSeed::new(seed_bytes)"), "must create PdaSeed: {}", output);
 }
 
@@ -443,36 +598,53 @@ fn test_pda_helpers_u64_multi_seed() {
     let idl = LezIdl {
         version: "0.1.0".to_string(),
         name: "test_program".to_string(),
+        docs: vec![],
         instructions: vec![IdlInstruction {
             name: "create_proposal".to_string(),
+            index: None,
+            docs: vec![],
             accounts: vec![IdlAccountItem {
                 name: "proposal".to_string(),
+                docs: vec![],
                 writable: true,
                 signer: false,
                 init: true,
+                init_if_needed: false,
+                space: None,
+                max_space: None,
                 owner: None,
                 pda: Some(IdlPda {
                     seeds: vec![
-                        IdlSeed::Arg { path: "create_key".to_string() },
-                        IdlSeed::Arg { path: "proposal_index".to_string() },
+                        IdlSeed::Arg { path: "create_key".to_string(), type_: None },
+                        IdlSeed::Arg { path: "proposal_index".to_string(), type_: None },
                     ],
+                    program: None,
                 }),
+                account_type: None,
+                has_one: vec![],
+                close: None,
+                constraints: vec![],
                 rest: false,
+                min_accounts: None,
+                max_accounts: None,
                 visibility: vec![],
             }],
             args: vec![
                 IdlArg {
                     name: "create_key".to_string(),
+                    docs: vec![],
                     type_: IdlType::Primitive("[u8; 32]".to_string()),
                 },
                 IdlArg {
                     name: "proposal_index".to_string(),
+                    docs: vec![],
                     type_: IdlType::Primitive("u64".to_string()),
                 },
             ],
             discriminator: None,
             execution: None,
             variant: None,
+            threshold: None,
         }],
         accounts: vec![],
         types: vec![],
@@ -480,6 +652,7 @@ fn test_pda_helpers_u64_multi_seed() {
         spec: None,
         metadata: None,
         instruction_type: None,
+        dispatch: IdlDispatch::Index,
     };
 
     let output = generate_pda_helpers(&idl);
@@ -494,10 +667,73 @@ fn test_pda_helpers_u64_multi_seed() {
     assert!(output.contains("Sha256"), "multi-seed must use SHA256: {}", output);
     assert!(output.contains("hasher.update"), "must call hasher.update: {}", output);
 
-    // u64 seed uses to_le_bytes, not as &[u8]
-    assert!(output.contains("proposal_index.to_le_bytes()"), "u64 seed must use to_le_bytes: {}", output);
+    // Every arg seed uses the canonical encoding of its type
+    assert!(output.contains("PdaSeedArg::to_seed_bytes(&proposal_index)"), "u64 seed must use PdaSeedArg: {}", output);
     assert!(!output.contains("proposal_index as &[u8]"), "u64 must not use as &[u8]: {}", output);
 
-    // [u8;32] seed uses as &[u8]
-    assert!(output.contains("create_key as &[u8]"), "byte array seed must use as &[u8]: {}", output);
+    assert!(output.contains("PdaSeedArg::to_seed_bytes(&create_key)"), "byte array seed must use PdaSeedArg: {}", output);
+}
+
+#[test]
+fn test_pda_helpers_cross_program() {
+    use lez_framework_core::idl::*;
+    use crate::ffi_codegen::generate_pda_helpers;
+
+    // A PDA of the program passed as `token_program_id`
+    let idl = LezIdl {
+        version: "0.1.0".to_string(),
+        name: "test_program".to_string(),
+        docs: vec![],
+        instructions: vec![IdlInstruction {
+            name: "deposit".to_string(),
+            index: None,
+            docs: vec![],
+            accounts: vec![IdlAccountItem {
+                name: "token_holding".to_string(),
+                docs: vec![],
+                writable: true,
+                signer: false,
+                init: false,
+                init_if_needed: false,
+                space: None,
+                max_space: None,
+                owner: None,
+                pda: Some(IdlPda {
+                    seeds: vec![IdlSeed::Const { value: "holding".to_string() }],
+                    program: Some(IdlPdaProgram::Arg { path: "token_program_id".to_string() }),
+                }),
+                account_type: None,
+                has_one: vec![],
+                close: None,
+                constraints: vec![],
+                rest: false,
+                min_accounts: None,
+                max_accounts: None,
+                visibility: vec![],
+            }],
+            args: vec![IdlArg {
+                name: "token_program_id".to_string(),
+                docs: vec![],
+                type_: IdlType::Primitive("program_id".to_string()),
+            }],
+            discriminator: None,
+            execution: None,
+            variant: None,
+            threshold: None,
+        }],
+        accounts: vec![],
+        types: vec![],
+        errors: vec![],
+        spec: None,
+        metadata: None,
+        instruction_type: None,
+        dispatch: IdlDispatch::Index,
+    };
+
+    let output = generate_pda_helpers(&idl);
+    assert!(output.contains("pub fn compute_token_holding_pda(token_program_id: &ProgramId)"), "missing fn signature: {}", output);
+    assert!(output.contains("AccountId::from((token_program_id, &pda_seed))"), "must derive under token_program_id: {}", output);
+
+    let ffi = crate::ffi_codegen::generate_ffi(&idl).unwrap();
+    assert!(ffi.contains("compute_pda(&token_program_id, &["), "FFI must derive under token_program_id: {}", ffi);
 }
@@ -57,6 +57,7 @@ pub fn idl_type_to_rust(ty: &lez_framework_core::idl::IdlType) -> String {
         IdlType::Primitive(p) => match p.as_str() {
             "account_id" | "AccountId" | "[u8; 32]" | "[u8;32]" => "AccountId".to_string(),
             "ProgramId" | "[u32; 8]" | "[u32;8]" => "ProgramId".to_string(),
+            "string" => "String".to_string(),
             s => s.to_string(),
         },
         IdlType::Vec { vec } => format!("Vec<{}>", idl_type_to_rust(vec)),
@@ -65,6 +66,10 @@ pub fn idl_type_to_rust(ty: &lez_framework_core::idl::IdlType) -> String {
         IdlType::Array { array: (elem, size) } => {
             format!("[{}; {}]", idl_type_to_rust(elem), size)
         }
+        IdlType::Tuple { tuple } => {
+            let elems: Vec<String> = tuple.iter().map(idl_type_to_rust).collect();
+            format!("({})", elems.join(", "))
+        }
     }
 }
 
@@ -80,7 +85,7 @@ pub fn idl_type_to_json_parse(ty: &lez_framework_core::idl::IdlType, var: &str)
             "ProgramId" | "[u32; 8]" | "[u32;8]" => {
                 format!("parse_program_id({var}.as_str().ok_or(\"expected string for ProgramId\")?)?")
             }
-            "String" => format!("{var}.as_str().ok_or(\"expected string\")?.to_string()"),
+            "String" | "string" => format!("{var}.as_str().ok_or(\"expected string\")?.to_string()"),
             "bool" => format!("{var}.as_bool().ok_or(\"expected bool\")?"),
             "u8" | "u16" | "u32" | "u64" | "u128" => {
                 format!("{var}.as_u64().ok_or(\"expected number\")? as {p}")
@@ -100,6 +105,85 @@ pub fn idl_type_to_json_parse(ty: &lez_framework_core::idl::IdlType, var: &str)
     }
 }
 
+/// Write IDL `docs` as Rust doc comment lines, e.g. with `marker` `///`.
+pub fn write_docs(out: &mut String, indent: &str, marker: &str, docs: &[String]) {
+    use std::fmt::Write;
+    for line in docs {
+        if line.is_empty() {
+            writeln!(out, "{indent}{marker}").unwrap();
+        } else {
+            writeln!(out, "{indent}{marker} {line}").unwrap();
+        }
+    }
+}
+
+/// True if the instruction enum needs a hand-written `Serialize`: either the
+/// program dispatches on discriminators, or some instruction's `index`
+/// differs from its position so a derived impl would send the wrong one.
+pub fn needs_custom_serialize(idl: &lez_framework_core::idl::LezIdl) -> bool {
+    idl.dispatch == lez_framework_core::idl::IdlDispatch::Discriminator
+        || idl
+            .instructions
+            .iter()
+            .enumerate()
+            .any(|(i, ix)| ix.index.is_some_and(|index| index as usize != i))
+}
+
+/// Write `impl Serialize` for the generated instruction enum, tagging each
+/// variant the way the program expects: with its `index`, or under
+/// `dispatch = "discriminator"` with a tuple led by the discriminator as a
+/// little-endian `u64`.
+pub fn write_instruction_serialize(out: &mut String, idl: &lez_framework_core::idl::LezIdl, enum_name: &str) {
+    use lez_framework_core::idl::IdlDispatch;
+    use std::fmt::Write;
+    writeln!(out, "impl Serialize for {enum_name} {{").unwrap();
+    writeln!(out, "    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{").unwrap();
+    writeln!(out, "        #[allow(unused_imports)]").unwrap();
+    writeln!(out, "        use serde::ser::{{SerializeStructVariant, SerializeTuple}};").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for ix in &idl.instructions {
+        let variant = pascal_case(&ix.name);
+        let fields: Vec<String> = ix.args.iter().map(|a| rust_ident(&a.name)).collect();
+        if idl.dispatch == IdlDispatch::Discriminator {
+            let disc = idl.instruction_discriminator(&ix.name).unwrap_or_default();
+            let mut bytes = [0u8; 8];
+            let len = disc.len().min(8);
+            bytes[..len].copy_from_slice(&disc[..len]);
+            writeln!(out, "            {enum_name}::{variant} {{ {} }} => {{", fields.join(", ")).unwrap();
+            writeln!(out, "                let mut tuple = serializer.serialize_tuple({})?;", 1 + fields.len()).unwrap();
+            writeln!(out, "                tuple.serialize_element(&{:#018x}u64)?;", u64::from_le_bytes(bytes)).unwrap();
+            for field in &fields {
+                writeln!(out, "                tuple.serialize_element({field})?;").unwrap();
+            }
+            writeln!(out, "                tuple.end()").unwrap();
+            writeln!(out, "            }}").unwrap();
+            continue;
+        }
+        let index = idl.instruction_index(&ix.name).unwrap_or(0);
+        if ix.args.is_empty() {
+            writeln!(
+                out,
+                "            {enum_name}::{variant} => serializer.serialize_unit_variant(\"{enum_name}\", {index}, \"{variant}\"),"
+            ).unwrap();
+        } else {
+            writeln!(out, "            {enum_name}::{variant} {{ {} }} => {{", fields.join(", ")).unwrap();
+            writeln!(
+                out,
+                "                let mut state = serializer.serialize_struct_variant(\"{enum_name}\", {index}, \"{variant}\", {})?;",
+                fields.len()
+            ).unwrap();
+            for (arg, field) in ix.args.iter().zip(&fields) {
+                writeln!(out, "                state.serialize_field(\"{}\", {field})?;", arg.name).unwrap();
+            }
+            writeln!(out, "                state.end()").unwrap();
+            writeln!(out, "            }}").unwrap();
+        }
+    }
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
 fn collapse_underscores(s: &str) -> String {
     let mut out = String::new();
     let mut prev_underscore = false;
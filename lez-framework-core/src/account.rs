@@ -0,0 +1,119 @@
+//! Typed account wrapper with borsh-encoded data.
+//!
+//! Declaring a handler parameter as `LezAccount<T>` instead of
+//! `AccountWithMetadata` makes `#[lez_program]` decode the account data
+//! into `T` before dispatch:
+//!
+//! ```rust,ignore
+//! #[instruction]
+//! pub fn deposit(
+//!     #[account(mut)] mut state: LezAccount<TreasuryState>,
+//!     amount: u128,
+//! ) -> LezResult {
+//!     state.total += amount;
+//!     Ok(LezOutput::states_only(vec![state.into_post_state()?]))
+//! }
+//! ```
+
+use std::ops::{Deref, DerefMut};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::account::{Account, AccountId, AccountWithMetadata};
+use nssa_core::program::AccountPostState;
+
+use crate::error::LezError;
+
+/// An account whose data is borsh-decoded into `T`.
+///
+/// Derefs to `T`; the original account is available via [`LezAccount::meta`].
+#[derive(Debug, Clone)]
+pub struct LezAccount<T> {
+    meta: AccountWithMetadata,
+    data: T,
+}
+
+impl<T: BorshSerialize + BorshDeserialize> LezAccount<T> {
+    /// Decode the data of the account at `account_index`.
+    pub fn load(meta: AccountWithMetadata, account_index: usize) -> Result<Self, LezError> {
+        let data = T::try_from_slice(&meta.account.data).map_err(|e| {
+            LezError::DeserializationError {
+                account_index,
+                message: e.to_string(),
+            }
+        })?;
+        Ok(Self { meta, data })
+    }
+
+    /// Wrap an uninitialized account, starting from `T::default()`.
+    /// Used for `#[account(init)]` parameters.
+    pub fn init(meta: AccountWithMetadata) -> Self
+    where
+        T: Default,
+    {
+        Self { meta, data: T::default() }
+    }
+
+    /// Decode the data of the account at `account_index`, or start from
+    /// `T::default()` if the account is uninitialized.
+    /// Used for `#[account(init_if_needed)]` parameters.
+    pub fn load_or_init(meta: AccountWithMetadata, account_index: usize) -> Result<Self, LezError>
+    where
+        T: Default,
+    {
+        if meta.account == Account::default() {
+            Ok(Self::init(meta))
+        } else {
+            Self::load(meta, account_index)
+        }
+    }
+
+    /// The underlying account, as passed to the program.
+    pub fn meta(&self) -> &AccountWithMetadata {
+        &self.meta
+    }
+
+    pub fn account_id(&self) -> &AccountId {
+        &self.meta.account_id
+    }
+
+    pub fn is_authorized(&self) -> bool {
+        self.meta.is_authorized
+    }
+
+    /// Re-encode the data and return the updated account.
+    pub fn into_account(self) -> Result<Account, LezError> {
+        let bytes = borsh::to_vec(&self.data).map_err(|e| LezError::SerializationError {
+            message: e.to_string(),
+        })?;
+        let mut account = self.meta.account;
+        account.data = bytes.try_into().map_err(|_| LezError::SerializationError {
+            message: "account data exceeds the maximum size".to_string(),
+        })?;
+        Ok(account)
+    }
+
+    /// Re-encode the data into a post-state for the handler's output.
+    pub fn into_post_state(self) -> Result<AccountPostState, LezError> {
+        Ok(AccountPostState::new(self.into_account()?))
+    }
+
+    /// Like [`LezAccount::into_post_state`], but claims the account for this
+    /// program. Use for `#[account(init)]` accounts.
+    pub fn into_post_state_claimed(self) -> Result<AccountPostState, LezError> {
+        Ok(AccountPostState::new_claimed(self.into_account()?))
+    }
+}
+
+impl<T> Deref for LezAccount<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T> DerefMut for LezAccount<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+}
@@ -0,0 +1,82 @@
+//! Support code for the generated `Instruction` enum.
+//!
+//! With `#[instruction(index = N)]` the variant index on the wire no longer
+//! matches the variant's position, so `#[lez_program]` writes the serde impls
+//! itself. [`VariantTag`] is the piece of that which resolves the variant.
+
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, Unexpected, Visitor};
+
+/// Deserializes an enum variant identifier into its explicit index.
+///
+/// Accepts either the index (binary formats such as risc0 serde) or the
+/// variant name (self-describing formats such as JSON).
+#[derive(Debug, Clone, Copy)]
+pub struct VariantTag {
+    pub enum_name: &'static str,
+    /// `(index, name)` for every variant.
+    pub variants: &'static [(u32, &'static str)],
+}
+
+impl<'de> DeserializeSeed<'de> for VariantTag {
+    type Value = u32;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<u32, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for VariantTag {
+    type Value = u32;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a variant of {}", self.enum_name)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<u32, E> {
+        self.variants
+            .iter()
+            .find(|(index, _)| u64::from(*index) == value)
+            .map(|(index, _)| *index)
+            .ok_or_else(|| E::invalid_value(Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<u32, E> {
+        self.variants
+            .iter()
+            .find(|(_, name)| *name == value)
+            .map(|(index, _)| *index)
+            .ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::value::{Error, StrDeserializer, U32Deserializer};
+    use serde::de::IntoDeserializer;
+
+    const TAG: VariantTag = VariantTag {
+        enum_name: "Instruction",
+        variants: &[(0, "Initialize"), (7, "Transfer")],
+    };
+
+    #[test]
+    fn test_resolves_index_and_name() {
+        let by_index: U32Deserializer<Error> = 7u32.into_deserializer();
+        assert_eq!(TAG.deserialize(by_index).unwrap(), 7);
+
+        let by_name: StrDeserializer<Error> = "Initialize".into_deserializer();
+        assert_eq!(TAG.deserialize(by_name).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_rejects_unknown_variant() {
+        let by_index: U32Deserializer<Error> = 1u32.into_deserializer();
+        assert!(TAG.deserialize(by_index).is_err());
+
+        let by_name: StrDeserializer<Error> = "Close".into_deserializer();
+        assert!(TAG.deserialize(by_name).is_err());
+    }
+}
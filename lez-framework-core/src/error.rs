@@ -32,7 +32,7 @@ pub use crate::types::LezOutput;
 ///     Ok(())
 /// }
 /// ```
-#[derive(Error, Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Error, Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub enum LezError {
     /// Wrong number of accounts provided for this instruction
     #[error("Expected {expected} accounts, got {actual}")]
@@ -104,6 +104,46 @@ pub enum LezError {
         code: u32,
         message: String,
     },
+
+    /// Handler changed an account that is not marked `mut`
+    #[error("Read-only account '{account_name}' (index {account_index}) was modified")]
+    ReadOnlyAccountModified {
+        account_index: usize,
+        account_name: String,
+    },
+
+    /// An `#[account(constraint = ...)]` check without `@ error` failed
+    #[error("Constraint `{constraint}` on account {account_index} failed")]
+    ConstraintViolated {
+        account_index: usize,
+        constraint: String,
+    },
+
+    /// Account data size is outside its `space` / `max_space` bound
+    #[error("Account {account_index} data is {actual} bytes, expected {min} to {max}")]
+    InvalidDataSize {
+        account_index: usize,
+        actual: usize,
+        min: usize,
+        max: usize,
+    },
+
+    /// A variable-length account list is shorter than its `min` or longer than its `max`
+    #[error("Account list '{account_name}' has {actual} accounts, expected {}", count_bounds(.min, .max))]
+    AccountListLength {
+        account_name: String,
+        actual: usize,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+fn count_bounds(min: &usize, max: &Option<usize>) -> String {
+    match *max {
+        Some(max) if max == *min => format!("exactly {}", min),
+        Some(max) => format!("{} to {}", min, max),
+        None => format!("at least {}", min),
+    }
 }
 
 impl LezError {
@@ -116,6 +156,8 @@ impl LezError {
     }
 
     /// Get a numeric error code for client-side handling.
+    ///
+    /// `Custom` codes are reported as `6000 + code`, saturating at `u32::MAX`.
     pub fn error_code(&self) -> u32 {
         match self {
             LezError::AccountCountMismatch { .. } => 1000,
@@ -128,7 +170,26 @@ impl LezError {
             LezError::Overflow { .. } => 1007,
             LezError::Unauthorized { .. } => 1008,
             LezError::PdaMismatch { .. } => 1009,
-            LezError::Custom { code, .. } => 6000 + code,
+            LezError::ReadOnlyAccountModified { .. } => 1010,
+            LezError::ConstraintViolated { .. } => 1011,
+            LezError::InvalidDataSize { .. } => 1012,
+            LezError::AccountListLength { .. } => 1013,
+            LezError::Custom { code, .. } => code.saturating_add(6000),
+        }
+    }
+
+    /// Index of the account this error refers to, if any.
+    pub fn account_index(&self) -> Option<usize> {
+        match self {
+            LezError::InvalidAccountOwner { account_index, .. }
+            | LezError::AccountAlreadyInitialized { account_index }
+            | LezError::AccountNotInitialized { account_index }
+            | LezError::DeserializationError { account_index, .. }
+            | LezError::PdaMismatch { account_index }
+            | LezError::ReadOnlyAccountModified { account_index, .. }
+            | LezError::ConstraintViolated { account_index, .. }
+            | LezError::InvalidDataSize { account_index, .. } => Some(*account_index),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,108 @@
+//! Machine-decodable failure reporting.
+//!
+//! A guest can only report failure by panicking, so generated code panics
+//! with a message of the form
+//!
+//! ```text
+//! LEZ_FAILURE:<hex(borsh(LezFailure))> Program error [1008] in 'transfer': Unauthorized: ...
+//! ```
+//!
+//! The hex payload is what tooling decodes with [`LezFailure::decode`]; the
+//! text after it is for humans reading raw logs.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::error::LezError;
+
+/// Marks the start of an encoded [`LezFailure`] in a panic message.
+pub const FAILURE_PREFIX: &str = "LEZ_FAILURE:";
+
+/// A failed instruction, as reported by a guest.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct LezFailure {
+    /// Instruction name in snake_case, as in the IDL.
+    pub instruction: String,
+    /// `error.error_code()`, kept so decoders need not know every variant.
+    pub error_code: u32,
+    /// Index of the offending account, when the error refers to one.
+    pub account_index: Option<usize>,
+    pub error: LezError,
+}
+
+impl LezFailure {
+    pub fn new(instruction: impl Into<String>, error: LezError) -> Self {
+        Self {
+            instruction: instruction.into(),
+            error_code: error.error_code(),
+            account_index: error.account_index(),
+            error,
+        }
+    }
+
+    /// Render as a panic message: prefix, hex payload, then a readable summary.
+    pub fn encode(&self) -> String {
+        let payload = borsh::to_vec(self).expect("LezFailure serialization cannot fail");
+        format!("{}{} {}", FAILURE_PREFIX, crate::hex::encode(&payload), self)
+    }
+
+    /// Find and decode a failure anywhere in `message`, e.g. the error text of
+    /// a rejected transaction. Returns `None` if there is no valid payload.
+    pub fn decode(message: &str) -> Option<Self> {
+        let start = message.find(FAILURE_PREFIX)? + FAILURE_PREFIX.len();
+        let rest = &message[start..];
+        let end = rest.find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(rest.len());
+        let payload = crate::hex::decode(&rest[..end])?;
+        Self::try_from_slice(&payload).ok()
+    }
+}
+
+impl std::fmt::Display for LezFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Program error [{}] in '{}': {}",
+            self.error_code, self.instruction, self.error
+        )
+    }
+}
+
+/// Abort the guest with an encoded [`LezFailure`].
+/// Called by the generated `main()` on every failure path.
+pub fn abort(instruction: &str, error: LezError) -> ! {
+    panic!("{}", LezFailure::new(instruction, error).encode())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let failure = LezFailure::new("transfer", LezError::PdaMismatch { account_index: 2 });
+        assert_eq!(failure.error_code, 1009);
+        assert_eq!(failure.account_index, Some(2));
+
+        let message = failure.encode();
+        assert!(message.starts_with(FAILURE_PREFIX));
+        assert!(message.ends_with("Program error [1009] in 'transfer': PDA mismatch for account 2"));
+        assert_eq!(LezFailure::decode(&message), Some(failure));
+    }
+
+    #[test]
+    fn test_decode_embedded_in_other_text() {
+        let failure = LezFailure::new("initialize", LezError::custom(3, "bad threshold"));
+        let message = format!("Guest panicked: {}\nstack backtrace: ...", failure.encode());
+
+        let decoded = LezFailure::decode(&message).unwrap();
+        assert_eq!(decoded.instruction, "initialize");
+        assert_eq!(decoded.error_code, 6003);
+        assert_eq!(decoded.account_index, None);
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        assert_eq!(LezFailure::decode("Program error [1008]: Unauthorized"), None);
+        assert_eq!(LezFailure::decode("LEZ_FAILURE:zz"), None);
+        assert_eq!(LezFailure::decode("LEZ_FAILURE:0102"), None);
+    }
+}
@@ -18,11 +18,20 @@ use serde::{Deserialize, Serialize};
 pub struct LezIdl {
     pub version: String,
     pub name: String,
+    /// Doc comments of the program module, one entry per line.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
     pub instructions: Vec<IdlInstruction>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub accounts: Vec<IdlAccountType>,
+    /// Named types referenced from `accounts`, instruction arguments and
+    /// other types via `{"defined": ...}`.
+    ///
+    /// Breaking change in 0.2.0: this was `Vec<IdlTypeDef>`, which carried no
+    /// type names. Entries now wrap the same `IdlTypeDef` with its name; read
+    /// the old value from `type_`.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub types: Vec<IdlTypeDef>,
+    pub types: Vec<IdlAccountType>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub errors: Vec<IdlError>,
     /// IDL spec identifier (lssa-lang compat).
@@ -36,6 +45,27 @@ pub struct LezIdl {
     /// Example: "multisig_core::Instruction"
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub instruction_type: Option<String>,
+    /// How instructions are tagged on the wire.
+    #[serde(default, skip_serializing_if = "IdlDispatch::is_index")]
+    pub dispatch: IdlDispatch,
+}
+
+/// How the program tells instructions apart in the instruction data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdlDispatch {
+    /// A `u32` variant index (`IdlInstruction::index`, or the position).
+    #[default]
+    Index,
+    /// The 8-byte `discriminator`, read as a little-endian `u64`
+    /// (lssa-lang compatible).
+    Discriminator,
+}
+
+impl IdlDispatch {
+    fn is_index(&self) -> bool {
+        *self == IdlDispatch::Index
+    }
 }
 
 /// Program metadata (lssa-lang compat).
@@ -60,6 +90,12 @@ pub struct IdlExecution {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdlInstruction {
     pub name: String,
+    /// Variant index of the instruction in the serialized `Instruction` enum.
+    /// Older IDLs omit it; the position in `instructions` is used instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
     pub accounts: Vec<IdlAccountItem>,
     pub args: Vec<IdlArg>,
     /// SHA256("global:{name}")[..8] discriminator (lssa-lang compat).
@@ -71,25 +107,74 @@ pub struct IdlInstruction {
     /// Variant name in PascalCase (lssa-lang compat).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub variant: Option<String>,
+    /// `#[instruction(threshold(...))]` signature requirement.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<IdlThreshold>,
+}
+
+/// At least `min` distinct accounts of the rest account `signers` must sign.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdlThreshold {
+    /// Name of the rest account holding the signers.
+    pub signers: String,
+    /// Required signatures as written: a number, or `<account>.<field>` read
+    /// from the account's data, e.g. `"state.threshold"`.
+    pub min: String,
+    /// Member list the signers must belong to, as written, e.g. `"state.members"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<String>,
 }
 
 /// An account expected by an instruction.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdlAccountItem {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
     #[serde(default)]
     pub writable: bool,
     #[serde(default)]
     pub signer: bool,
     #[serde(default)]
     pub init: bool,
+    /// `#[account(init_if_needed)]`: created if uninitialized, used as is otherwise.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub init_if_needed: bool,
+    /// `#[account(space = N)]`: exact data size in bytes after the instruction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub space: Option<usize>,
+    /// `#[account(max_space = N)]`: maximum data size in bytes after the instruction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_space: Option<usize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub owner: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pda: Option<IdlPda>,
+    /// Name of the `accounts` type the data decodes to, for `LezAccount<T>`
+    /// parameters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<String>,
+    /// `#[account(has_one = ...)]` relations: each named account's ID is
+    /// stored in the field of the same name of this account's data.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub has_one: Vec<String>,
+    /// `#[account(close = ...)]`: the account that receives this account's
+    /// balance when the instruction closes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close: Option<String>,
+    /// `#[account(constraint = ...)]` checks as written, e.g.
+    /// `"vault.account.balance >= *amount @ VaultError::Insufficient"`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<String>,
     /// If true, this account represents a variable-length trailing list.
     #[serde(default, skip_serializing_if = "is_false")]
     pub rest: bool,
+    /// `#[account(min = N)]`: fewest accounts a rest list may hold.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_accounts: Option<usize>,
+    /// `#[account(max = N)]`: most accounts a rest list may hold.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_accounts: Option<usize>,
     /// Visibility tags (lssa-lang compat). e.g. ["public"].
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub visibility: Vec<String>,
@@ -101,6 +186,22 @@ fn is_false(v: &bool) -> bool { !v }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdlPda {
     pub seeds: Vec<IdlSeed>,
+    /// The program the PDA is derived under; the executing program when
+    /// absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program: Option<IdlPdaProgram>,
+}
+
+/// The program a PDA is derived under, from `pda_program = ...`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum IdlPdaProgram {
+    /// A `ProgramId` expression as written, e.g. a constant's name
+    #[serde(rename = "const")]
+    Const { value: String },
+    /// A `ProgramId` instruction argument
+    #[serde(rename = "arg")]
+    Arg { path: String },
 }
 
 /// A seed component for PDA derivation.
@@ -111,14 +212,22 @@ pub enum IdlSeed {
     Const { value: String },
     #[serde(rename = "account")]
     Account { path: String },
+    /// `type` selects the seed encoding (see `pda::PdaSeedArg`); IDLs that
+    /// predate it fall back to the argument's own type.
     #[serde(rename = "arg")]
-    Arg { path: String },
+    Arg {
+        path: String,
+        #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+        type_: Option<IdlType>,
+    },
 }
 
 /// An instruction argument.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdlArg {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<String>,
     #[serde(rename = "type")]
     pub type_: IdlType,
 }
@@ -132,9 +241,13 @@ pub enum IdlType {
     Option { option: Box<IdlType> },
     Defined { defined: String },
     Array { array: (Box<IdlType>, usize) },
+    Tuple { tuple: Vec<IdlType> },
 }
 
-/// Account type definition in the IDL.
+/// Named type definition in the IDL.
+///
+/// Used for both `accounts` (account data layouts) and `types`
+/// (types referenced from them via `{"defined": ...}`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IdlAccountType {
     pub name: String,
@@ -194,6 +307,7 @@ impl LezIdl {
         Self {
             version: "0.1.0".to_string(),
             name: name.into(),
+            docs: vec![],
             instructions: vec![],
             accounts: vec![],
             types: vec![],
@@ -201,11 +315,69 @@ impl LezIdl {
             spec: None,
             metadata: None,
             instruction_type: None,
+            dispatch: IdlDispatch::Index,
         }
     }
 
+    /// The variant index the program expects for instruction `name`: its
+    /// recorded `index`, or its position for IDLs that predate the field.
+    pub fn instruction_index(&self, name: &str) -> Option<u32> {
+        let position = self.instructions.iter().position(|ix| ix.name == name)?;
+        Some(self.instructions[position].index.unwrap_or(position as u32))
+    }
+
+    /// The discriminator of instruction `name`, as recorded or computed from
+    /// the name for IDLs that omit it.
+    pub fn instruction_discriminator(&self, name: &str) -> Option<Vec<u8>> {
+        let ix = self.instructions.iter().find(|ix| ix.name == name)?;
+        Some(ix.discriminator.clone().unwrap_or_else(|| compute_discriminator(name)))
+    }
+
+    /// Look up a program error by the code reported by `LezError::error_code()`.
+    pub fn find_error(&self, code: u32) -> Option<&IdlError> {
+        self.errors.iter().find(|e| e.code == code)
+    }
+
+    /// Parse an IDL from JSON, such as a program's `PROGRAM_IDL_JSON`.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
     /// Serialize the IDL to pretty-printed JSON.
     pub fn to_json_pretty(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
     }
 }
+
+impl IdlInstruction {
+    /// The type that encodes an `arg` seed: recorded on the seed, or for
+    /// IDLs that predate it, the type of the argument of that name.
+    pub fn seed_arg_type<'a>(&'a self, seed: &'a IdlSeed) -> Option<&'a IdlType> {
+        let IdlSeed::Arg { path, type_ } = seed else { return None };
+        type_.as_ref().or_else(|| self.args.iter().find(|a| a.name == *path).map(|a| &a.type_))
+    }
+
+    /// The instruction's PDA accounts, ordered so each comes after the PDA
+    /// accounts its `account` seeds name. Cyclic seeds, which the macro
+    /// rejects, keep their declaration order.
+    pub fn pda_accounts_in_seed_order(&self) -> Vec<&IdlAccountItem> {
+        let is_pda = |name: &str| self.accounts.iter().any(|a| a.name == name && a.pda.is_some());
+        let mut pending: Vec<&IdlAccountItem> = self.accounts.iter().filter(|a| a.pda.is_some()).collect();
+        let mut ordered: Vec<&IdlAccountItem> = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|acc| {
+                acc.pda.as_ref().unwrap().seeds.iter().all(|seed| match seed {
+                    IdlSeed::Account { path } => {
+                        !is_pda(path) || ordered.iter().any(|a| a.name == *path)
+                    }
+                    _ => true,
+                })
+            });
+            match ready {
+                Some(i) => ordered.push(pending.remove(i)),
+                None => ordered.append(&mut pending),
+            }
+        }
+        ordered
+    }
+}
@@ -2,13 +2,19 @@
 //!
 //! Core types and traits for the LEZ program framework.
 
+pub mod account;
+pub mod dispatch;
 pub mod error;
+pub mod failure;
 pub mod types;
 pub mod idl;
 pub mod pda;
 pub mod validation;
 
+mod hex;
+
 pub mod prelude {
+    pub use crate::account::LezAccount;
     pub use crate::error::{LezError, LezResult};
     pub use crate::pda::{compute_pda, seed_from_str};
     pub use crate::types::{LezOutput, AccountConstraint};
@@ -1,9 +1,23 @@
 //! Generic PDA (Program Derived Address) computation utilities.
+//!
+//! `arg("...")` seeds use one canonical 32-byte encoding per argument type,
+//! shared by the guest, `lez-cli` and generated clients through
+//! [`PdaSeedArg`]:
+//!
+//! | IDL type | Seed |
+//! |----------|------|
+//! | `u8` … `u128`, `i8` … `i128` | little-endian, zero-padded |
+//! | `bool` | `0` or `1`, zero-padded |
+//! | `string`, `Vec<u8>`, `[u8; N]` | zero-padded bytes; SHA-256 of them if longer than 32 |
+//! | `account_id` | the 32-byte ID |
+//! | `program_id` | the eight words little-endian |
 
-use nssa_core::account::AccountId;
+use nssa_core::account::{AccountId, AccountWithMetadata};
 use nssa_core::program::{PdaSeed, ProgramId};
 use sha2::{Sha256, Digest};
 
+use crate::error::LezError;
+
 /// Convert a string to a zero-padded 32-byte seed.
 ///
 /// # Panics
@@ -17,6 +31,16 @@ pub fn seed_from_str(s: &str) -> [u8; 32] {
     bytes
 }
 
+/// Zero-pad `bytes` to 32 bytes, or hash them with SHA-256 if they are longer.
+pub fn seed_from_bytes(bytes: &[u8]) -> [u8; 32] {
+    if bytes.len() > 32 {
+        return Sha256::digest(bytes).into();
+    }
+    let mut seed = [0u8; 32];
+    seed[..bytes.len()].copy_from_slice(bytes);
+    seed
+}
+
 /// Compute a PDA `AccountId` from a program ID and one or more 32-byte seeds.
 ///
 /// - Single seed: used directly as the PDA seed.
@@ -43,6 +67,119 @@ pub fn compute_pda(program_id: &ProgramId, seeds: &[&[u8; 32]]) -> AccountId {
     AccountId::from((program_id, &pda_seed))
 }
 
+/// Conversion of an instruction argument into a 32-byte PDA seed,
+/// used for `arg("...")` seeds. See the module docs for the encodings.
+pub trait PdaSeedArg {
+    fn to_seed_bytes(&self) -> [u8; 32];
+}
+
+impl<T: PdaSeedArg + ?Sized> PdaSeedArg for &T {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        (**self).to_seed_bytes()
+    }
+}
+
+macro_rules! impl_int_seed {
+    ($($ty:ty),*) => {$(
+        /// Little-endian, zero-padded to 32 bytes.
+        impl PdaSeedArg for $ty {
+            fn to_seed_bytes(&self) -> [u8; 32] {
+                seed_from_bytes(&self.to_le_bytes())
+            }
+        }
+    )*};
+}
+
+impl_int_seed!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl PdaSeedArg for bool {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        seed_from_bytes(&[u8::from(*self)])
+    }
+}
+
+impl PdaSeedArg for [u8] {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        seed_from_bytes(self)
+    }
+}
+
+impl<const N: usize> PdaSeedArg for [u8; N] {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        seed_from_bytes(self)
+    }
+}
+
+impl PdaSeedArg for Vec<u8> {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        seed_from_bytes(self)
+    }
+}
+
+impl PdaSeedArg for str {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        seed_from_bytes(self.as_bytes())
+    }
+}
+
+impl PdaSeedArg for String {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        seed_from_bytes(self.as_bytes())
+    }
+}
+
+impl PdaSeedArg for AccountId {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        *self.value()
+    }
+}
+
+/// `ProgramId`: each word little-endian.
+impl PdaSeedArg for [u32; 8] {
+    fn to_seed_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(self) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Verify that `account_id` is the PDA derived from `program_id` and `seeds`.
+/// Used for `#[account(pda = ...)]` constraint.
+pub fn verify_pda(
+    account_id: &AccountId,
+    program_id: &ProgramId,
+    seeds: &[&[u8; 32]],
+    account_index: usize,
+) -> Result<(), LezError> {
+    if compute_pda(program_id, seeds) != *account_id {
+        return Err(LezError::PdaMismatch { account_index });
+    }
+    Ok(())
+}
+
+/// Verify a PDA of the executing program.
+///
+/// With `program_id`, this is [`verify_pda`]. A guest cannot embed its own
+/// image ID, so without it the ID is taken from the runtime's pre-state: the
+/// account must be the PDA of the program that owns it. An unclaimed account
+/// has no owner yet and passes; once this program claims it, every later
+/// instruction checks its address.
+pub fn verify_own_pda(
+    account: &AccountWithMetadata,
+    program_id: Option<&ProgramId>,
+    seeds: &[&[u8; 32]],
+    account_index: usize,
+) -> Result<(), LezError> {
+    let program_id = match program_id {
+        Some(program_id) => program_id,
+        None if account.account.program_owner == ProgramId::default() => return Ok(()),
+        None => &account.account.program_owner,
+    };
+    verify_pda(&account.account_id, program_id, seeds, account_index)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,6 +274,71 @@ mod tests {
         assert_ne!(single, multi);
     }
 
+    #[test]
+    fn test_verify_pda() {
+        let program_id: ProgramId = [1u32; 8];
+        let seed = seed_from_str("vault");
+        let pda = compute_pda(&program_id, &[&seed]);
+
+        assert!(verify_pda(&pda, &program_id, &[&seed], 0).is_ok());
+
+        let other = AccountId::new([9u8; 32]);
+        let err = verify_pda(&other, &program_id, &[&seed], 3).unwrap_err();
+        assert!(matches!(err, LezError::PdaMismatch { account_index: 3 }));
+    }
+
+    #[test]
+    fn test_verify_own_pda_uses_owner() {
+        let program_id: ProgramId = [1u32; 8];
+        let seed = seed_from_str("state");
+        let mut account = AccountWithMetadata {
+            account_id: compute_pda(&program_id, &[&seed]),
+            account: Default::default(),
+            is_authorized: false,
+        };
+
+        // Not claimed yet: nothing to check against
+        assert!(verify_own_pda(&account, None, &[&seed], 0).is_ok());
+
+        account.account.program_owner = program_id;
+        assert!(verify_own_pda(&account, None, &[&seed], 0).is_ok());
+        assert!(verify_own_pda(&account, Some(&program_id), &[&seed], 0).is_ok());
+
+        // A PDA of some other program, claimed by this one
+        account.account.program_owner = [2u32; 8];
+        assert!(verify_own_pda(&account, None, &[&seed], 1).is_err());
+        assert!(verify_own_pda(&account, Some(&[2u32; 8]), &[&seed], 1).is_err());
+    }
+
+    #[test]
+    fn test_seed_arg_u64_encoding() {
+        let seed = 5u64.to_seed_bytes();
+        assert_eq!(&seed[..8], &5u64.to_le_bytes());
+        assert_eq!(&seed[8..], &[0u8; 24]);
+        assert_eq!("abc".to_string().to_seed_bytes(), seed_from_str("abc"));
+    }
+
+    #[test]
+    fn test_seed_arg_encodings() {
+        assert_eq!((-1i16).to_seed_bytes()[..3], [0xff, 0xff, 0]);
+        assert_eq!(true.to_seed_bytes(), seed_from_bytes(&[1]));
+        assert_eq!([7u8; 32].to_seed_bytes(), [7u8; 32]);
+        assert_eq!([7u8; 4].to_seed_bytes(), vec![7u8; 4].to_seed_bytes());
+        assert_eq!(AccountId::new([3u8; 32]).to_seed_bytes(), [3u8; 32]);
+        let program_id: ProgramId = [1, 0, 0, 0, 0, 0, 0, 2];
+        let seed = program_id.to_seed_bytes();
+        assert_eq!(seed[0], 1);
+        assert_eq!(seed[28], 2);
+    }
+
+    #[test]
+    fn test_long_seed_is_hashed() {
+        let long = "a".repeat(33);
+        let seed = long.to_seed_bytes();
+        assert_eq!(seed, <[u8; 32]>::from(Sha256::digest(long.as_bytes())));
+        assert_ne!(seed, "a".repeat(32).to_seed_bytes());
+    }
+
     #[test]
     #[should_panic(expected = "at least one seed")]
     fn test_compute_pda_empty_seeds() {
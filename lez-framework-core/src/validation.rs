@@ -3,6 +3,9 @@
 //! These functions are called by the macro-generated code to validate
 //! accounts before passing them to instruction handlers.
 
+use nssa_core::account::{Account, AccountId, AccountWithMetadata};
+use nssa_core::program::{AccountPostState, ProgramId};
+
 use crate::error::LezError;
 use crate::types::AccountConstraint;
 
@@ -60,23 +63,170 @@ pub fn is_default_account(data: &[u8]) -> bool {
 /// Verify that an account's owner matches the expected program.
 /// Used for `#[account(owner = PROGRAM_ID)]` constraint.
 pub fn verify_owner(
-    account_owner: &[u8; 32],
-    expected_owner: &[u8; 32],
+    account_owner: &ProgramId,
+    expected_owner: &ProgramId,
     account_index: usize,
 ) -> Result<(), LezError> {
     if account_owner != expected_owner {
+        let owner_bytes: Vec<u8> = expected_owner.iter().flat_map(|w| w.to_le_bytes()).collect();
         return Err(LezError::InvalidAccountOwner {
             account_index,
-            expected_owner: hex::encode(expected_owner),
+            expected_owner: crate::hex::encode(&owner_bytes),
+        });
+    }
+    Ok(())
+}
+
+/// Verify the length of a variable-length account list.
+/// Used for `#[account(min = N, max = M)]` on `Vec<AccountWithMetadata>` parameters.
+pub fn verify_account_list_len(
+    actual: usize,
+    min: usize,
+    max: Option<usize>,
+    account_name: &str,
+) -> Result<(), LezError> {
+    if actual < min || max.is_some_and(|max| actual > max) {
+        return Err(LezError::AccountListLength {
+            account_name: account_name.to_string(),
+            actual,
+            min,
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// Verify that the handler returned the account at `account_index` unchanged.
+/// Used for accounts without `#[account(mut)]`.
+pub fn verify_read_only(
+    pre_states: &[AccountWithMetadata],
+    post_states: &[AccountPostState],
+    account_index: usize,
+    account_name: &str,
+) -> Result<(), LezError> {
+    let needed = account_index + 1;
+    let actual = pre_states.len().min(post_states.len());
+    if actual < needed {
+        return Err(LezError::AccountCountMismatch { expected: needed, actual });
+    }
+    if *post_states[account_index].account() != pre_states[account_index].account {
+        return Err(LezError::ReadOnlyAccountModified {
+            account_index,
+            account_name: account_name.to_string(),
         });
     }
     Ok(())
 }
 
-// Note: hex is used for error display only. In production, 
-// consider base58 or the chain's preferred encoding.
-mod hex {
-    pub fn encode(bytes: &[u8]) -> String {
-        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+/// Claim the account at `account_index` for this program if it was
+/// uninitialized before the handler ran.
+/// Used for `#[account(init_if_needed)]`.
+pub fn claim_if_new(
+    pre_states: &[AccountWithMetadata],
+    post_states: &mut [AccountPostState],
+    account_index: usize,
+) {
+    let (Some(pre), Some(post)) = (pre_states.get(account_index), post_states.get_mut(account_index)) else {
+        return;
+    };
+    if pre.account == Account::default() {
+        *post = AccountPostState::new_claimed(post.account().clone());
+    }
+}
+
+/// Verify that the data the handler left in the account at `account_index`
+/// is between `min` and `max` bytes long.
+/// Used for `#[account(space = N)]` (`min == max`) and `#[account(max_space = N)]`.
+pub fn verify_data_size(
+    post_states: &[AccountPostState],
+    account_index: usize,
+    min: usize,
+    max: usize,
+) -> Result<(), LezError> {
+    let Some(post) = post_states.get(account_index) else {
+        return Err(LezError::AccountCountMismatch {
+            expected: account_index + 1,
+            actual: post_states.len(),
+        });
+    };
+    let actual = post.account().data.len();
+    if actual < min || actual > max {
+        return Err(LezError::InvalidDataSize { account_index, actual, min, max });
     }
+    Ok(())
+}
+
+/// Close the account at `account_index` after the handler ran: its balance is
+/// moved to the account at `recipient_index` and its data is reset.
+/// Used for `#[account(mut, close = recipient)]`.
+pub fn close_account(
+    post_states: &mut [AccountPostState],
+    account_index: usize,
+    recipient_index: usize,
+) -> Result<(), LezError> {
+    let needed = account_index.max(recipient_index) + 1;
+    if post_states.len() < needed {
+        return Err(LezError::AccountCountMismatch { expected: needed, actual: post_states.len() });
+    }
+    let balance = post_states[account_index].account().balance;
+    let recipient = post_states[recipient_index].account_mut();
+    recipient.balance = recipient.balance.checked_add(balance).ok_or_else(|| LezError::Overflow {
+        operation: format!("closing account {} into account {}", account_index, recipient_index),
+    })?;
+
+    let closed = post_states[account_index].account_mut();
+    closed.balance = 0;
+    closed.data = Default::default();
+    Ok(())
+}
+
+/// An entry of a stored member list, matched against signer account IDs.
+pub trait MemberId {
+    fn matches(&self, account_id: &AccountId) -> bool;
+}
+
+impl MemberId for AccountId {
+    fn matches(&self, account_id: &AccountId) -> bool {
+        self == account_id
+    }
+}
+
+impl MemberId for [u8; 32] {
+    fn matches(&self, account_id: &AccountId) -> bool {
+        self == account_id.value()
+    }
+}
+
+/// Verify that at least `required` distinct accounts of `signers` are
+/// authorized, all of them in `members` if given. `first_index` is the index
+/// of `signers[0]` among the instruction's accounts.
+/// Used for `#[instruction(threshold(signers, required, members))]`.
+pub fn verify_threshold<M: MemberId>(
+    signers: &[AccountWithMetadata],
+    first_index: usize,
+    required: usize,
+    members: Option<&[M]>,
+) -> Result<(), LezError> {
+    let mut counted: Vec<&AccountId> = Vec::new();
+    for (offset, signer) in signers.iter().enumerate().filter(|(_, s)| s.is_authorized) {
+        let index = first_index + offset;
+        let id = crate::hex::encode(signer.account_id.value());
+        if counted.contains(&&signer.account_id) {
+            return Err(LezError::Unauthorized {
+                message: format!("Signer {} (index {}) is listed more than once", id, index),
+            });
+        }
+        if members.is_some_and(|members| !members.iter().any(|m| m.matches(&signer.account_id))) {
+            return Err(LezError::Unauthorized {
+                message: format!("Signer {} (index {}) is not a member", id, index),
+            });
+        }
+        counted.push(&signer.account_id);
+    }
+    if counted.len() < required {
+        return Err(LezError::Unauthorized {
+            message: format!("{} of {} required signatures", counted.len(), required),
+        });
+    }
+    Ok(())
 }
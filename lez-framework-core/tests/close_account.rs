@@ -0,0 +1,39 @@
+//! Test closing an account into a recipient after the handler ran.
+//!
+//! The macro emits a `close_account` call per `#[account(close = ...)]`
+//! account; these tests exercise that helper directly.
+
+use nssa_core::account::Account;
+use nssa_core::program::AccountPostState;
+use lez_framework_core::error::LezError;
+use lez_framework_core::validation::close_account;
+
+fn post_state(balance: u128, data: Vec<u8>) -> AccountPostState {
+    AccountPostState::new(Account { balance, data: data.try_into().unwrap(), ..Account::default() })
+}
+
+#[test]
+fn balance_moves_to_recipient() {
+    let mut post = vec![post_state(70, vec![1, 2, 3]), post_state(30, vec![9])];
+    close_account(&mut post, 0, 1).unwrap();
+
+    assert_eq!(post[0].account().balance, 0);
+    assert!(post[0].account().data.is_empty());
+    assert_eq!(post[1].account().balance, 100);
+    assert_eq!(post[1].account().data.to_vec(), vec![9]);
+}
+
+#[test]
+fn recipient_overflow_fails() {
+    let mut post = vec![post_state(1, vec![]), post_state(u128::MAX, vec![])];
+    let err = close_account(&mut post, 0, 1).unwrap_err();
+    assert!(matches!(err, LezError::Overflow { .. }), "got {:?}", err);
+    assert_eq!(post[0].account().balance, 1);
+}
+
+#[test]
+fn missing_post_state_fails() {
+    let mut post = vec![post_state(1, vec![])];
+    let err = close_account(&mut post, 0, 1).unwrap_err();
+    assert_eq!(err, LezError::AccountCountMismatch { expected: 2, actual: 1 });
+}
@@ -0,0 +1,58 @@
+//! Test the checks the macro runs on post-states for `init_if_needed`,
+//! `space` and `max_space` accounts.
+
+use nssa_core::account::{Account, AccountId, AccountWithMetadata};
+use nssa_core::program::AccountPostState;
+use lez_framework_core::error::LezError;
+use lez_framework_core::validation::{claim_if_new, verify_data_size};
+
+fn make_account(balance: u128) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new([1u8; 32]),
+        account: Account { balance, ..Account::default() },
+        is_authorized: false,
+    }
+}
+
+fn post_state(len: usize) -> AccountPostState {
+    AccountPostState::new(Account { data: vec![7u8; len].try_into().unwrap(), ..Account::default() })
+}
+
+#[test]
+fn fresh_account_is_claimed() {
+    let pre = vec![make_account(0)];
+    let mut post = vec![post_state(4)];
+    claim_if_new(&pre, &mut post, 0);
+    assert!(post[0].requires_claim());
+    assert_eq!(post[0].account().data.len(), 4);
+}
+
+#[test]
+fn existing_account_is_left_alone() {
+    let pre = vec![make_account(5)];
+    let mut post = vec![post_state(4)];
+    claim_if_new(&pre, &mut post, 0);
+    assert!(!post[0].requires_claim());
+}
+
+#[test]
+fn data_size_within_bounds_passes() {
+    let post = vec![post_state(8)];
+    assert!(verify_data_size(&post, 0, 8, 8).is_ok());
+    assert!(verify_data_size(&post, 0, 0, 16).is_ok());
+}
+
+#[test]
+fn data_size_out_of_bounds_fails() {
+    let post = vec![post_state(8)];
+    let err = verify_data_size(&post, 0, 0, 4).unwrap_err();
+    assert_eq!(err, LezError::InvalidDataSize { account_index: 0, actual: 8, min: 0, max: 4 });
+    assert_eq!(err.error_code(), 1012);
+}
+
+#[test]
+fn missing_post_state_fails_data_size_check() {
+    let post = vec![post_state(8)];
+    let err = verify_data_size(&post, 1, 0, 16).unwrap_err();
+    assert_eq!(err, LezError::AccountCountMismatch { expected: 2, actual: 1 });
+}
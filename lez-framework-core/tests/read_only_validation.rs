@@ -0,0 +1,62 @@
+//! Test that accounts without #[account(mut)] must be returned unchanged.
+//!
+//! The macro emits a `verify_read_only` call per non-mut account after the
+//! handler runs; these tests exercise that helper directly.
+
+use nssa_core::account::{Account, AccountId, AccountWithMetadata};
+use nssa_core::program::AccountPostState;
+use lez_framework_core::error::LezError;
+use lez_framework_core::validation::verify_read_only;
+
+fn make_account(id: [u8; 32], balance: u128) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new(id),
+        account: Account { balance, ..Account::default() },
+        is_authorized: false,
+    }
+}
+
+#[test]
+fn unchanged_read_only_account_passes() {
+    let pre = vec![make_account([1u8; 32], 100), make_account([2u8; 32], 50)];
+    let post = vec![
+        AccountPostState::new(pre[0].account.clone()),
+        AccountPostState::new(pre[1].account.clone()),
+    ];
+    assert!(verify_read_only(&pre, &post, 1, "config").is_ok());
+}
+
+#[test]
+fn modified_read_only_account_fails() {
+    let pre = vec![make_account([1u8; 32], 100), make_account([2u8; 32], 50)];
+    let mut changed = pre[1].account.clone();
+    changed.balance = 0;
+    let post = vec![
+        AccountPostState::new(pre[0].account.clone()),
+        AccountPostState::new(changed),
+    ];
+
+    let err = verify_read_only(&pre, &post, 1, "config").unwrap_err();
+    match err {
+        LezError::ReadOnlyAccountModified { account_index, ref account_name } => {
+            assert_eq!(account_index, 1);
+            assert_eq!(account_name, "config");
+        }
+        _ => panic!("Expected ReadOnlyAccountModified, got {:?}", err),
+    }
+    assert_eq!(err.error_code(), 1010);
+}
+
+#[test]
+fn missing_read_only_post_state_fails() {
+    let pre = vec![make_account([1u8; 32], 100), make_account([2u8; 32], 50)];
+    let post = vec![AccountPostState::new(pre[0].account.clone())];
+
+    match verify_read_only(&pre, &post, 1, "config").unwrap_err() {
+        LezError::AccountCountMismatch { expected, actual } => {
+            assert_eq!(expected, 2);
+            assert_eq!(actual, 1);
+        }
+        err => panic!("Expected AccountCountMismatch, got {:?}", err),
+    }
+}
@@ -0,0 +1,70 @@
+//! Test the `LezAccount<T>` wrapper used for typed account parameters.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use nssa_core::account::{Account, AccountId, AccountWithMetadata};
+use lez_framework_core::account::LezAccount;
+use lez_framework_core::error::LezError;
+
+#[derive(Debug, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+struct Counter {
+    count: u64,
+    label: String,
+}
+
+fn make_account(data: Vec<u8>) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new([1u8; 32]),
+        account: Account { data: data.try_into().unwrap(), ..Account::default() },
+        is_authorized: false,
+    }
+}
+
+#[test]
+fn load_decodes_data() {
+    let counter = Counter { count: 7, label: "hits".to_string() };
+    let acc = make_account(borsh::to_vec(&counter).unwrap());
+
+    let loaded = LezAccount::<Counter>::load(acc, 0).unwrap();
+    assert_eq!(*loaded, counter);
+}
+
+#[test]
+fn load_invalid_data_fails() {
+    let acc = make_account(vec![1, 2, 3]);
+    let err = LezAccount::<Counter>::load(acc, 4).unwrap_err();
+    match err {
+        LezError::DeserializationError { account_index, .. } => assert_eq!(account_index, 4),
+        _ => panic!("Expected DeserializationError, got {:?}", err),
+    }
+}
+
+#[test]
+fn modified_value_is_written_back() {
+    let acc = make_account(borsh::to_vec(&Counter::default()).unwrap());
+    let mut loaded = LezAccount::<Counter>::load(acc, 0).unwrap();
+    loaded.count += 1;
+
+    let post = loaded.into_post_state().unwrap();
+    let stored = Counter::try_from_slice(&post.account().data).unwrap();
+    assert_eq!(stored.count, 1);
+}
+
+#[test]
+fn init_starts_from_default() {
+    let state = LezAccount::<Counter>::init(make_account(vec![]));
+    assert_eq!(*state, Counter::default());
+}
+
+#[test]
+fn load_or_init_handles_both_states() {
+    let fresh = AccountWithMetadata {
+        account_id: AccountId::new([1u8; 32]),
+        account: Account::default(),
+        is_authorized: false,
+    };
+    assert_eq!(*LezAccount::<Counter>::load_or_init(fresh, 0).unwrap(), Counter::default());
+
+    let counter = Counter { count: 3, label: "x".to_string() };
+    let existing = make_account(borsh::to_vec(&counter).unwrap());
+    assert_eq!(*LezAccount::<Counter>::load_or_init(existing, 0).unwrap(), counter);
+}
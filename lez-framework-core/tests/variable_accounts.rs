@@ -7,12 +7,22 @@ use lez_framework_core::idl::{IdlAccountItem, IdlPda};
 fn test_rest_account_serializes() {
     let acc = IdlAccountItem {
         name: "members".to_string(),
+        docs: vec![],
         writable: false,
         signer: false,
         init: false,
+        init_if_needed: false,
+        space: None,
+        max_space: None,
         owner: None,
         pda: None,
+        account_type: None,
+        has_one: vec![],
+        close: None,
+        constraints: vec![],
         rest: true,
+        min_accounts: None,
+        max_accounts: None,
         visibility: vec!["public".to_string()],
     };
     let json = serde_json::to_string(&acc).unwrap();
@@ -23,12 +33,22 @@ fn test_rest_account_serializes() {
 fn test_non_rest_account_omits_rest() {
     let acc = IdlAccountItem {
         name: "state".to_string(),
+        docs: vec![],
         writable: true,
         signer: false,
         init: false,
+        init_if_needed: false,
+        space: None,
+        max_space: None,
         owner: None,
         pda: None,
+        account_type: None,
+        has_one: vec![],
+        close: None,
+        constraints: vec![],
         rest: false,
+        min_accounts: None,
+        max_accounts: None,
         visibility: vec![],
     };
     let json = serde_json::to_string(&acc).unwrap();
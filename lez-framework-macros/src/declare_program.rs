@@ -0,0 +1,448 @@
+//! `declare_program!`: typed `ChainedCall` builders from another program's IDL.
+//!
+//! Only the parts of the IDL the builders need are read; unknown fields are
+//! ignored, so IDLs from newer or older framework versions still load.
+
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::quote;
+use serde::Deserialize;
+use syn::Ident;
+
+use crate::idl::compute_discriminator;
+
+#[derive(Deserialize)]
+struct Idl {
+    name: String,
+    #[serde(default)]
+    docs: Vec<String>,
+    instructions: Vec<Instruction>,
+    #[serde(default)]
+    accounts: Vec<TypeDef>,
+    #[serde(default)]
+    types: Vec<TypeDef>,
+    #[serde(default)]
+    dispatch: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Instruction {
+    name: String,
+    #[serde(default)]
+    index: Option<u32>,
+    #[serde(default)]
+    docs: Vec<String>,
+    accounts: Vec<Account>,
+    args: Vec<Arg>,
+    #[serde(default)]
+    discriminator: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    name: String,
+    #[serde(default)]
+    pda: Option<Pda>,
+    #[serde(default)]
+    rest: bool,
+    #[serde(default)]
+    min_accounts: Option<usize>,
+    #[serde(default)]
+    max_accounts: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct Pda {
+    seeds: Vec<Seed>,
+    #[serde(default)]
+    program: Option<PdaProgram>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum PdaProgram {
+    Const {},
+    Arg { path: String },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Seed {
+    Const { value: String },
+    Account { path: String },
+    Arg { path: String },
+}
+
+#[derive(Deserialize)]
+struct Arg {
+    name: String,
+    #[serde(rename = "type")]
+    type_: IdlType,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IdlType {
+    Primitive(String),
+    Vec { vec: Box<IdlType> },
+    Option { option: Box<IdlType> },
+    Defined { defined: String },
+    Array { array: (Box<IdlType>, usize) },
+    Tuple { tuple: Vec<IdlType> },
+}
+
+#[derive(Deserialize)]
+struct TypeDef {
+    name: String,
+    #[serde(rename = "type")]
+    type_: TypeDefBody,
+}
+
+#[derive(Deserialize)]
+struct TypeDefBody {
+    kind: String,
+    #[serde(default)]
+    fields: Vec<Field>,
+    #[serde(default)]
+    variants: Vec<Variant>,
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: String,
+    #[serde(rename = "type")]
+    type_: IdlType,
+}
+
+#[derive(Deserialize)]
+struct Variant {
+    name: String,
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+pub(crate) fn expand_declare_program(lit: &syn::LitStr) -> syn::Result<TokenStream2> {
+    let file_path = lit.value();
+    let resolved_path = match std::env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest_dir) if !std::path::Path::new(&file_path).is_absolute() => {
+            std::path::Path::new(&manifest_dir).join(&file_path)
+        }
+        _ => std::path::PathBuf::from(&file_path),
+    };
+    let content = std::fs::read_to_string(&resolved_path).map_err(|e| {
+        syn::Error::new_spanned(
+            lit,
+            format!("Failed to read '{}' (resolved: '{}'): {}", file_path, resolved_path.display(), e),
+        )
+    })?;
+    let idl: Idl = serde_json::from_str(&content)
+        .map_err(|e| syn::Error::new_spanned(lit, format!("Failed to parse IDL '{}': {}", file_path, e)))?;
+
+    let err = |msg: String| syn::Error::new_spanned(lit, msg);
+    let mod_name = ident(&idl.name).map_err(err)?;
+    let mod_docs = &idl.docs;
+    let discriminator = idl.dispatch.as_deref() == Some("discriminator");
+
+    // The argument types defined by the callee, and the types they use
+    let mut defined = Vec::new();
+    for arg in idl.instructions.iter().flat_map(|ix| &ix.args) {
+        collect_defined(&arg.type_, &idl, &mut defined);
+    }
+    let type_defs = defined
+        .iter()
+        .map(|name| {
+            let def = idl.types.iter().chain(&idl.accounts).find(|def| def.name == *name)
+                .ok_or_else(|| err(format!("type `{}` is not defined in the IDL", name)))?;
+            type_def(def).map_err(err)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let builders = idl
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(position, ix)| {
+            let tag = if discriminator {
+                let disc = ix.discriminator.clone().unwrap_or_else(|| compute_discriminator(&ix.name));
+                let disc = disc
+                    .get(..8)
+                    .and_then(|d| <[u8; 8]>::try_from(d).ok())
+                    .ok_or_else(|| err(format!("instruction `{}` has no 8-byte discriminator", ix.name)))?;
+                Literal::u64_suffixed(u64::from_le_bytes(disc))
+            } else {
+                Literal::u32_suffixed(ix.index.unwrap_or(position as u32))
+            };
+            builder(ix, tag).map_err(err)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let path_str = resolved_path.to_string_lossy().to_string();
+    Ok(quote! {
+        #(#[doc = #mod_docs])*
+        #[allow(dead_code, clippy::too_many_arguments)]
+        pub mod #mod_name {
+            // Help cargo track the IDL file
+            const _: &str = include_str!(#path_str);
+
+            #(#type_defs)*
+
+            #(#builders)*
+        }
+    })
+}
+
+/// One `pub fn <instruction>(program_id, <pda>_program..., accounts..., args...)`
+/// returning the `ChainedCall`. A `<pda>_program` parameter stands in for each
+/// PDA derived under a constant program of the callee's. The instruction is
+/// serialized as a tuple of its tag and arguments, which encodes exactly like
+/// the callee's `Instruction` variant.
+fn builder(ix: &Instruction, tag: Literal) -> Result<TokenStream2, String> {
+    let fn_name = ident(&ix.name)?;
+    let docs = &ix.docs;
+
+    let fixed: Vec<&Account> = ix.accounts.iter().filter(|a| !a.rest).collect();
+    let rest: Vec<&Account> = ix.accounts.iter().filter(|a| a.rest).collect();
+    let const_programs: Vec<&Account> = fixed
+        .iter()
+        .copied()
+        .filter(|acc| matches!(acc.pda.as_ref().and_then(|pda| pda.program.as_ref()), Some(PdaProgram::Const {})))
+        .collect();
+
+    // Every parameter shares one namespace
+    let mut params: Vec<(String, String)> = vec![("program_id".to_string(), "the callee's program ID".to_string())];
+    params.extend(const_programs.iter().map(|acc| (program_param(acc), format!("the program of PDA `{}`", acc.name))));
+    params.extend(ix.accounts.iter().map(|acc| (acc.name.clone(), format!("account `{}`", acc.name))));
+    params.extend(ix.args.iter().map(|arg| (arg.name.clone(), format!("argument `{}`", arg.name))));
+    for (i, (name, what)) in params.iter().enumerate() {
+        if let Some((_, first)) = params[..i].iter().find(|(other, _)| other == name) {
+            return Err(format!(
+                "instruction `{}`: {} and {} would both be the parameter `{}`",
+                ix.name, first, what, name
+            ));
+        }
+    }
+
+    let arg_names = ix.args.iter().map(|a| ident(&a.name)).collect::<Result<Vec<_>, _>>()?;
+    let arg_types = ix.args.iter().map(|a| rust_type(&a.type_)).collect::<Result<Vec<_>, _>>()?;
+
+    let account_params = ix
+        .accounts
+        .iter()
+        .map(|acc| {
+            let name = ident(&acc.name)?;
+            Ok(if acc.rest {
+                quote! { #name: Vec<nssa_core::account::AccountWithMetadata> }
+            } else {
+                quote! { #name: nssa_core::account::AccountWithMetadata }
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let rest_checks = rest
+        .iter()
+        .filter(|acc| acc.min_accounts.unwrap_or(0) > 0 || acc.max_accounts.is_some())
+        .map(|acc| {
+            let name = ident(&acc.name)?;
+            let name_str = &acc.name;
+            let min = acc.min_accounts.unwrap_or(0);
+            let (max, out_of_bounds) = match acc.max_accounts {
+                Some(max) if min == 0 => (quote! { Some(#max) }, quote! { #name.len() > #max }),
+                Some(max) => (quote! { Some(#max) }, quote! { #name.len() < #min || #name.len() > #max }),
+                None => (quote! { None }, quote! { #name.len() < #min }),
+            };
+            Ok(quote! {
+                if #out_of_bounds {
+                    return Err(lez_framework::error::LezError::AccountListLength {
+                        account_name: #name_str.to_string(),
+                        actual: #name.len(),
+                        min: #min,
+                        max: #max,
+                    });
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let pda_checks = fixed
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, acc)| Some((idx, acc, acc.pda.as_ref()?)))
+        .map(|(idx, acc, pda)| {
+            let name = ident(&acc.name)?;
+            let program = match &pda.program {
+                None => quote! { &program_id },
+                Some(PdaProgram::Arg { path }) => {
+                    let arg = ident(path)?;
+                    quote! { &#arg }
+                }
+                // The callee names a constant of its own, so the caller passes it in
+                Some(PdaProgram::Const {}) => {
+                    let param = ident(&program_param(acc))?;
+                    quote! { &#param }
+                }
+            };
+            let seeds = pda
+                .seeds
+                .iter()
+                .map(|seed| match seed {
+                    Seed::Const { value } => Ok(quote! { lez_framework::pda::seed_from_str(#value) }),
+                    Seed::Account { path } if fixed.iter().any(|a| a.name == *path) => {
+                        let account = ident(path)?;
+                        Ok(quote! { *#account.account_id.value() })
+                    }
+                    Seed::Arg { path } if ix.args.iter().any(|a| a.name == *path) => {
+                        let arg = ident(path)?;
+                        Ok(quote! { lez_framework::pda::PdaSeedArg::to_seed_bytes(&#arg) })
+                    }
+                    Seed::Account { path } | Seed::Arg { path } => Err(format!(
+                        "PDA `{}` of instruction `{}` has a seed `{}` that is not in the IDL",
+                        acc.name, ix.name, path
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(quote! {
+                lez_framework::pda::verify_pda(&#name.account_id, #program, &[#(&#seeds),*], #idx)?;
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let program_params = const_programs
+        .iter()
+        .map(|acc| ident(&program_param(acc)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let fixed_names = fixed.iter().map(|a| ident(&a.name)).collect::<Result<Vec<_>, _>>()?;
+    let rest_names = rest.iter().map(|a| ident(&a.name)).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(quote! {
+        #(#[doc = #docs])*
+        pub fn #fn_name(
+            program_id: nssa_core::program::ProgramId,
+            #(#program_params: nssa_core::program::ProgramId,)*
+            #(#account_params,)*
+            #(#arg_names: #arg_types),*
+        ) -> Result<nssa_core::program::ChainedCall, lez_framework::error::LezError> {
+            #(#rest_checks)*
+            #(#pda_checks)*
+            #[allow(unused_mut)]
+            let mut __pre_states = vec![#(#fixed_names),*];
+            #(__pre_states.extend(#rest_names);)*
+            Ok(nssa_core::program::ChainedCall::new(program_id, __pre_states, &(#tag, #(#arg_names,)*)))
+        }
+    })
+}
+
+/// A callee type referenced by instruction arguments, serialized like the original.
+fn type_def(def: &TypeDef) -> Result<TokenStream2, String> {
+    let name = ident(&def.name)?;
+    let fields = |fields: &[Field]| {
+        fields
+            .iter()
+            .map(|f| {
+                let name = ident(&f.name)?;
+                let ty = rust_type(&f.type_)?;
+                Ok(quote! { #name: #ty })
+            })
+            .collect::<Result<Vec<_>, String>>()
+    };
+    match def.type_.kind.as_str() {
+        "struct" => {
+            let fields = fields(&def.type_.fields)?;
+            Ok(quote! {
+                #[derive(Debug, Clone, serde::Serialize)]
+                pub struct #name { #(pub #fields),* }
+            })
+        }
+        "enum" => {
+            let variants = def
+                .type_
+                .variants
+                .iter()
+                .map(|v| {
+                    let name = ident(&v.name)?;
+                    if v.fields.is_empty() {
+                        Ok(quote! { #name })
+                    } else {
+                        let fields = fields(&v.fields)?;
+                        Ok(quote! { #name { #(#fields),* } })
+                    }
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(quote! {
+                #[derive(Debug, Clone, serde::Serialize)]
+                pub enum #name { #(#variants),* }
+            })
+        }
+        other => Err(format!("type `{}` has unsupported kind `{}`", def.name, other)),
+    }
+}
+
+fn collect_defined(ty: &IdlType, idl: &Idl, out: &mut Vec<String>) {
+    match ty {
+        IdlType::Primitive(_) => {}
+        IdlType::Vec { vec: inner } | IdlType::Option { option: inner } | IdlType::Array { array: (inner, _) } => {
+            collect_defined(inner, idl, out)
+        }
+        IdlType::Tuple { tuple } => tuple.iter().for_each(|ty| collect_defined(ty, idl, out)),
+        IdlType::Defined { defined } => {
+            if out.contains(defined) {
+                return;
+            }
+            out.push(defined.clone());
+            let Some(def) = idl.types.iter().chain(&idl.accounts).find(|def| def.name == *defined) else { return };
+            let variant_fields = def.type_.variants.iter().flat_map(|v| &v.fields);
+            for field in def.type_.fields.iter().chain(variant_fields) {
+                collect_defined(&field.type_, idl, out);
+            }
+        }
+    }
+}
+
+fn rust_type(ty: &IdlType) -> Result<TokenStream2, String> {
+    Ok(match ty {
+        IdlType::Primitive(p) => match p.as_str() {
+            "string" | "String" => quote! { String },
+            "program_id" => quote! { nssa_core::program::ProgramId },
+            "account_id" => quote! { nssa_core::account::AccountId },
+            // `u64`, `bool`, ... and older IDLs' `[u8; 32]`
+            other => syn::parse_str::<syn::Type>(other)
+                .map(|ty| quote! { #ty })
+                .map_err(|_| format!("unsupported IDL type `{}`", other))?,
+        },
+        IdlType::Vec { vec } => {
+            let inner = rust_type(vec)?;
+            quote! { Vec<#inner> }
+        }
+        IdlType::Option { option } => {
+            let inner = rust_type(option)?;
+            quote! { Option<#inner> }
+        }
+        IdlType::Defined { defined } => {
+            let name = ident(defined)?;
+            quote! { #name }
+        }
+        IdlType::Array { array: (elem, len) } => {
+            let elem = rust_type(elem)?;
+            quote! { [#elem; #len] }
+        }
+        IdlType::Tuple { tuple } => {
+            let elems = tuple.iter().map(rust_type).collect::<Result<Vec<_>, _>>()?;
+            quote! { (#(#elems,)*) }
+        }
+    })
+}
+
+/// `<account>_program`, the ID of the program a `const` PDA program names.
+fn program_param(acc: &Account) -> String {
+    format!("{}_program", acc.name)
+}
+
+/// `name` as an identifier, raw (`r#type`) if it is a keyword.
+fn ident(name: &str) -> Result<Ident, String> {
+    match syn::parse_str::<Ident>(name) {
+        Ok(ident) => Ok(ident),
+        // `self`, `crate`, ... cannot be raw identifiers either
+        Err(_) if syn::parse_str::<Ident>(&format!("r#{}", name)).is_ok() => {
+            Ok(Ident::new_raw(name, Span::call_site()))
+        }
+        Err(_) => Err(format!("`{}` in the IDL is not a valid Rust identifier", name)),
+    }
+}
@@ -0,0 +1,582 @@
+//! The IDL as the macros build it.
+//!
+//! These types mirror `lez_framework_core::idl` field for field, including the
+//! serde attributes, so serializing them produces exactly the JSON the core
+//! types read back. The model is built once per program and serialized with
+//! `serde_json`; both `PROGRAM_IDL_JSON` and `generate_idl!` use that output.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use syn::Type;
+
+use crate::{
+    owner_to_idl_string, to_pascal_case, tokens_to_idl_string, Dispatch, IdlItems, IdlTypeInfo, IdlTypeKind,
+    InstructionInfo, PdaProgramDef, PdaSeedDef,
+};
+
+#[derive(Serialize)]
+pub(crate) struct LezIdl {
+    version: String,
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    instructions: Vec<IdlInstruction>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    accounts: Vec<IdlAccountType>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    types: Vec<IdlAccountType>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<IdlError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spec: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<IdlMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instruction_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dispatch: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct IdlMetadata {
+    name: String,
+    version: String,
+}
+
+#[derive(Serialize)]
+struct IdlExecution {
+    public: bool,
+    private_owned: bool,
+}
+
+#[derive(Serialize)]
+struct IdlInstruction {
+    name: String,
+    index: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    accounts: Vec<IdlAccountItem>,
+    args: Vec<IdlArg>,
+    discriminator: Vec<u8>,
+    execution: IdlExecution,
+    variant: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threshold: Option<IdlThreshold>,
+}
+
+#[derive(Serialize)]
+struct IdlThreshold {
+    signers: String,
+    min: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    members: Option<String>,
+}
+
+#[derive(Serialize)]
+struct IdlAccountItem {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    writable: bool,
+    signer: bool,
+    init: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    init_if_needed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    space: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_space: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pda: Option<IdlPda>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_type: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    has_one: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    close: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    constraints: Vec<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    rest: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_accounts: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_accounts: Option<usize>,
+    visibility: Vec<String>,
+}
+
+fn is_false(v: &bool) -> bool {
+    !v
+}
+
+#[derive(Serialize)]
+struct IdlPda {
+    seeds: Vec<IdlSeed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    program: Option<IdlPdaProgram>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+enum IdlPdaProgram {
+    #[serde(rename = "const")]
+    Const { value: String },
+    #[serde(rename = "arg")]
+    Arg { path: String },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+enum IdlSeed {
+    #[serde(rename = "const")]
+    Const { value: String },
+    #[serde(rename = "account")]
+    Account { path: String },
+    #[serde(rename = "arg")]
+    Arg {
+        path: String,
+        #[serde(rename = "type")]
+        type_: IdlType,
+    },
+}
+
+#[derive(Serialize)]
+struct IdlArg {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    docs: Vec<String>,
+    #[serde(rename = "type")]
+    type_: IdlType,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+enum IdlType {
+    Primitive(String),
+    Vec { vec: Box<IdlType> },
+    Option { option: Box<IdlType> },
+    Defined { defined: String },
+    Array { array: (Box<IdlType>, usize) },
+    Tuple { tuple: Vec<IdlType> },
+}
+
+#[derive(Serialize)]
+struct IdlAccountType {
+    name: String,
+    #[serde(rename = "type")]
+    type_: IdlTypeDef,
+}
+
+#[derive(Serialize)]
+struct IdlTypeDef {
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<IdlField>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    variants: Vec<IdlEnumVariant>,
+}
+
+#[derive(Serialize)]
+struct IdlField {
+    name: String,
+    #[serde(rename = "type")]
+    type_: IdlType,
+}
+
+#[derive(Serialize)]
+struct IdlEnumVariant {
+    name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<IdlField>,
+}
+
+#[derive(Serialize)]
+struct IdlError {
+    code: u32,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    msg: Option<String>,
+}
+
+impl LezIdl {
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("IDL serialization cannot fail")
+    }
+
+    pub(crate) fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("IDL serialization cannot fail")
+    }
+}
+
+/// `idl/` in the cargo target directory: `CARGO_TARGET_DIR` when set,
+/// otherwise `target/` of the outermost workspace enclosing `manifest_dir`,
+/// so a guest crate excluded from the project workspace still writes next to it.
+pub(crate) fn default_idl_dir(manifest_dir: &Path) -> PathBuf {
+    if let Some(target_dir) = std::env::var_os("CARGO_TARGET_DIR").filter(|dir| !dir.is_empty()) {
+        // Relative paths are relative to where cargo runs rustc
+        let cwd = std::env::current_dir().unwrap_or_else(|_| manifest_dir.to_path_buf());
+        return cwd.join(target_dir).join("idl");
+    }
+    let root = manifest_dir
+        .ancestors()
+        .filter(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .map(|toml| toml.lines().any(|line| line.trim() == "[workspace]"))
+                .unwrap_or(false)
+        })
+        .last()
+        .unwrap_or(manifest_dir);
+    root.join("target").join("idl")
+}
+
+/// Write `<dir>/<program>.idl.json`, leaving the file untouched when it is
+/// already up to date.
+pub(crate) fn write_idl_file(dir: &Path, program_name: &str, json: &str) -> std::io::Result<()> {
+    let path = dir.join(format!("{}.idl.json", program_name));
+    let contents = format!("{}\n", json);
+    if std::fs::read_to_string(&path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    // Several crates (or a crate and its tests) may expand the same program
+    // at once, so write to a private file and rename it into place
+    let tmp = dir.join(format!(".{}.idl.json.{}", program_name, std::process::id()));
+    std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&tmp, contents))
+        .and_then(|()| std::fs::rename(&tmp, &path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+}
+
+/// Compute SHA256("global:{name}")[..8] discriminator at macro expansion time.
+pub(crate) fn compute_discriminator(name: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("global:{}", name).as_bytes());
+    let result = hasher.finalize();
+    result[..8].to_vec()
+}
+
+/// Build the IDL of a `#[lez_program]` module.
+///
+/// Fails if an argument or field has a type the IDL cannot describe.
+pub(crate) fn build_idl(
+    program_name: &str,
+    instructions: &[InstructionInfo],
+    idl_items: &IdlItems,
+    external_instruction: Option<&str>,
+    dispatch: Dispatch,
+    program_docs: &[String],
+) -> syn::Result<LezIdl> {
+    let consts = &idl_items.consts;
+    let instructions = instructions
+        .iter()
+        .map(|ix| {
+            let name = ix.fn_name.to_string();
+            let args = ix
+                .args
+                .iter()
+                .map(|arg| {
+                    Ok(IdlArg {
+                        name: arg.name.to_string().trim_start_matches('_').to_string(),
+                        docs: arg.docs.clone(),
+                        type_: rust_type_to_idl_type(&arg.ty, consts)?,
+                    })
+                })
+                .collect::<syn::Result<Vec<IdlArg>>>()?;
+            let accounts = ix
+                .accounts
+                .iter()
+                .map(|acc| IdlAccountItem {
+                    name: acc.name.to_string().trim_start_matches('_').to_string(),
+                    docs: acc.docs.clone(),
+                    writable: acc.constraints.mutable,
+                    signer: acc.constraints.signer,
+                    init: acc.constraints.init,
+                    init_if_needed: acc.constraints.init_if_needed,
+                    space: acc.constraints.space,
+                    max_space: acc.constraints.max_space,
+                    owner: acc.constraints.owner.as_ref().map(owner_to_idl_string),
+                    pda: (!acc.constraints.pda_seeds.is_empty()).then(|| IdlPda {
+                        seeds: acc.constraints.pda_seeds.iter().map(|seed| idl_seed(seed, &args)).collect(),
+                        program: acc.constraints.pda_program.as_ref().map(|program| match program {
+                            PdaProgramDef::Const(expr) => {
+                                IdlPdaProgram::Const { value: tokens_to_idl_string(quote::quote!(#expr)) }
+                            }
+                            PdaProgramDef::Arg(path) => IdlPdaProgram::Arg { path: path.value() },
+                        }),
+                    }),
+                    account_type: acc.data_type.as_ref().map(type_name),
+                    has_one: acc.constraints.has_one.iter().map(|f| f.to_string()).collect(),
+                    close: acc.constraints.close.as_ref().map(|r| r.to_string()),
+                    constraints: acc.constraints.checks.iter().map(|c| c.to_idl_string()).collect(),
+                    rest: acc.is_rest,
+                    min_accounts: acc.constraints.min,
+                    max_accounts: acc.constraints.max,
+                    visibility: vec!["public".to_string()],
+                })
+                .collect();
+
+            Ok(IdlInstruction {
+                discriminator: compute_discriminator(&name),
+                variant: to_pascal_case(&ix.fn_name).to_string(),
+                name,
+                index: ix.index,
+                docs: ix.docs.clone(),
+                accounts,
+                args,
+                execution: IdlExecution { public: true, private_owned: false },
+                threshold: ix.threshold.as_ref().map(|t| {
+                    let min = &t.min;
+                    IdlThreshold {
+                        signers: t.signers.to_string(),
+                        min: tokens_to_idl_string(quote::quote!(#min)),
+                        members: t.members.as_ref().map(|m| tokens_to_idl_string(quote::quote!(#m))),
+                    }
+                }),
+            })
+        })
+        .collect::<syn::Result<_>>()?;
+
+    let type_defs = |is_account: bool| -> syn::Result<Vec<IdlAccountType>> {
+        idl_items
+            .types
+            .iter()
+            .filter(|t| t.is_account == is_account)
+            .map(|t| idl_type_def(t, consts))
+            .collect()
+    };
+    let errors = idl_items
+        .errors
+        .iter()
+        .map(|e| IdlError { code: e.code.saturating_add(6000), name: e.name.clone(), msg: e.msg.clone() })
+        .collect();
+
+    Ok(LezIdl {
+        version: "0.1.0".to_string(),
+        name: program_name.to_string(),
+        docs: program_docs.to_vec(),
+        instructions,
+        accounts: type_defs(true)?,
+        types: type_defs(false)?,
+        errors,
+        spec: Some("0.1.0".to_string()),
+        metadata: Some(IdlMetadata {
+            name: program_name.to_string(),
+            version: "0.1.0".to_string(),
+        }),
+        instruction_type: external_instruction.map(str::to_string),
+        dispatch: match dispatch {
+            Dispatch::Index => None,
+            Dispatch::Discriminator => Some("discriminator"),
+        },
+    })
+}
+
+/// An IDL seed; `arg` seeds carry the argument's type, which selects their
+/// encoding.
+fn idl_seed(seed: &PdaSeedDef, args: &[IdlArg]) -> IdlSeed {
+    match seed {
+        PdaSeedDef::Const(value) => IdlSeed::Const { value: value.value() },
+        PdaSeedDef::Account(path) => IdlSeed::Account { path: path.value() },
+        PdaSeedDef::Arg(path) => {
+            let name = path.value();
+            let arg = args
+                .iter()
+                .find(|a| a.name == name.trim_start_matches('_'))
+                .expect("seed arguments are checked by check_pda_seeds");
+            IdlSeed::Arg { path: name, type_: arg.type_.clone() }
+        }
+    }
+}
+
+/// The last path segment of a `LezAccount<T>` data type, e.g. `State` for
+/// `my_core::State`.
+fn type_name(ty: &Type) -> String {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            return segment.ident.to_string();
+        }
+    }
+    quote::quote!(#ty).to_string().replace(' ', "")
+}
+
+fn idl_type_def(info: &IdlTypeInfo, consts: &[(String, usize)]) -> syn::Result<IdlAccountType> {
+    let fields = |fields: &[(String, Type)]| -> syn::Result<Vec<IdlField>> {
+        fields
+            .iter()
+            .map(|(name, ty)| {
+                Ok(IdlField { name: name.clone(), type_: rust_type_to_idl_type(ty, consts)? })
+            })
+            .collect()
+    };
+    let type_ = match &info.kind {
+        IdlTypeKind::Struct(struct_fields) => IdlTypeDef {
+            kind: "struct",
+            fields: fields(struct_fields)?,
+            variants: vec![],
+        },
+        IdlTypeKind::Enum(variants) => IdlTypeDef {
+            kind: "enum",
+            fields: vec![],
+            variants: variants
+                .iter()
+                .map(|(name, variant_fields)| {
+                    Ok(IdlEnumVariant { name: name.clone(), fields: fields(variant_fields)? })
+                })
+                .collect::<syn::Result<_>>()?,
+        },
+    };
+    Ok(IdlAccountType { name: info.name.clone(), type_ })
+}
+
+/// Map a Rust type to its IDL representation.
+///
+/// Paths are matched on their last segment, so `std::vec::Vec<T>` is a `vec`
+/// and `my_core::State` refers to the `State` type definition. Array lengths
+/// may be integer literals or `const`s collected alongside the IDL types.
+fn rust_type_to_idl_type(ty: &Type, consts: &[(String, usize)]) -> syn::Result<IdlType> {
+    let unsupported = |what: &str| {
+        syn::Error::new_spanned(ty, format!("{} cannot be described in the IDL", what))
+    };
+    match ty {
+        Type::Path(type_path) => {
+            if type_path.qself.is_some() {
+                return Err(unsupported("a qualified associated type"));
+            }
+            let segments = &type_path.path.segments;
+            let last = segments.last().unwrap();
+            if segments.iter().take(segments.len() - 1).any(|s| !s.arguments.is_none()) {
+                return Err(unsupported("a path with generic arguments before the type name"));
+            }
+            let path: Vec<String> = segments.iter().map(|s| s.ident.to_string()).collect();
+            // Qualified names are only understood for the standard and `nssa_core`
+            // types below; anything else would lose its path and could be mistaken
+            // for an unrelated IDL type of the same name
+            let ident = match path.as_slice() {
+                [name] => name.clone(),
+                _ => match well_known_type(&path) {
+                    Some(name) => name.to_string(),
+                    None => {
+                        return Err(unsupported(&format!(
+                            "the qualified path `{}` (import `{}` and name it directly)",
+                            path.join("::"),
+                            last.ident
+                        )))
+                    }
+                },
+            };
+            let generic_args: Vec<&Type> = match &last.arguments {
+                syn::PathArguments::None => vec![],
+                syn::PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Ok(ty),
+                        _ => Err(unsupported("a generic argument other than a type")),
+                    })
+                    .collect::<syn::Result<_>>()?,
+                syn::PathArguments::Parenthesized(_) => return Err(unsupported("a function trait")),
+            };
+            let single_arg = || match generic_args.as_slice() {
+                [inner] => rust_type_to_idl_type(inner, consts),
+                _ => Err(unsupported(&format!("`{}` without exactly one type argument", ident))),
+            };
+            match ident.as_str() {
+                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "bool" | "String" | "ProgramId" | "AccountId"
+                    if !generic_args.is_empty() =>
+                {
+                    Err(unsupported(&format!("`{}` with type arguments", ident)))
+                }
+                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64"
+                | "i128" | "bool" => Ok(IdlType::Primitive(ident)),
+                "String" => Ok(IdlType::Primitive("string".to_string())),
+                "ProgramId" => Ok(IdlType::Primitive("program_id".to_string())),
+                "AccountId" => Ok(IdlType::Primitive("account_id".to_string())),
+                "Vec" => Ok(IdlType::Vec { vec: Box::new(single_arg()?) }),
+                "Option" => Ok(IdlType::Option { option: Box::new(single_arg()?) }),
+                // Serialized exactly like the boxed value
+                "Box" => single_arg(),
+                "usize" | "isize" => Err(unsupported(&format!(
+                    "`{}` has a platform-dependent size and",
+                    ident
+                ))),
+                _ if !generic_args.is_empty() => {
+                    Err(unsupported(&format!("the generic type `{}`", ident)))
+                }
+                _ => Ok(IdlType::Defined { defined: ident }),
+            }
+        }
+        Type::Array(arr) => {
+            let elem = rust_type_to_idl_type(&arr.elem, consts)?;
+            Ok(IdlType::Array { array: (Box::new(elem), array_len(&arr.len, consts)?) })
+        }
+        Type::Tuple(tuple) if !tuple.elems.is_empty() => {
+            let elems = tuple
+                .elems
+                .iter()
+                .map(|elem| rust_type_to_idl_type(elem, consts))
+                .collect::<syn::Result<_>>()?;
+            Ok(IdlType::Tuple { tuple: elems })
+        }
+        Type::Tuple(_) => Err(unsupported("the unit type")),
+        Type::Paren(paren) => rust_type_to_idl_type(&paren.elem, consts),
+        Type::Group(group) => rust_type_to_idl_type(&group.elem, consts),
+        Type::Reference(_) => Err(unsupported("a reference")),
+        _ => Err(unsupported("this type")),
+    }
+}
+
+/// The bare name of a qualified standard or `nssa_core` type the IDL knows,
+/// e.g. `std::string::String`.
+fn well_known_type(path: &[String]) -> Option<&'static str> {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    Some(match path.as_slice() {
+        ["std" | "core", "primitive", name] => [
+            "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "bool", "usize", "isize",
+        ]
+        .into_iter()
+        .find(|p| p == name)?,
+        ["std" | "alloc", "string", "String"] => "String",
+        ["std" | "alloc", "vec", "Vec"] => "Vec",
+        ["std" | "alloc", "boxed", "Box"] => "Box",
+        ["std" | "core", "option", "Option"] => "Option",
+        ["nssa_core", "program", "ProgramId"] => "ProgramId",
+        ["nssa_core", "account", "AccountId"] => "AccountId",
+        _ => return None,
+    })
+}
+
+/// Resolve an array length: an integer literal or the name of a known `const`.
+fn array_len(len: &syn::Expr, consts: &[(String, usize)]) -> syn::Result<usize> {
+    match len {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(n), .. }) => n.base10_parse(),
+        syn::Expr::Path(path) if path.qself.is_none() => {
+            let name = path.path.segments.last().unwrap().ident.to_string();
+            consts
+                .iter()
+                .find(|(c, _)| *c == name)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        len,
+                        format!(
+                            "unknown array length `{}`: declare it as an integer `const` in the \
+                             program module or a `types_from` file",
+                            name
+                        ),
+                    )
+                })
+        }
+        syn::Expr::Group(group) => array_len(&group.expr, consts),
+        syn::Expr::Paren(paren) => array_len(&paren.expr, consts),
+        _ => Err(syn::Error::new_spanned(
+            len,
+            "array length must be an integer literal or a `const` name to be described in the IDL",
+        )),
+    }
+}
@@ -1,15 +1,16 @@
 //! # LEZ Framework Proc Macros
 //!
 //! This crate provides the `#[lez_program]` attribute macro that eliminates
-//! boilerplate in LEZ guest binaries, and the `generate_idl!` macro
-//! for extracting IDL from program source files.
+//! boilerplate in LEZ guest binaries, the `generate_idl!` macro
+//! for extracting IDL from program source files, and `declare_program!`
+//! for calling other programs through their IDL.
 //!
 //! ## Usage
 //!
 //! ```rust,ignore
 //! use lez_framework::prelude::*;
 //!
-//! #[lez_program]
+//! #[lez_program(program_id = "crate::PROGRAM_ID")]
 //! mod my_program {
 //!     #[instruction]
 //!     pub fn create(
@@ -22,15 +23,28 @@
 //! }
 //! ```
 //!
+//! `pda = ...` accounts without `pda_program` are PDAs of this program. A
+//! guest cannot know its own image ID when it is built, so by default the
+//! generated `main()` checks them against the program that owns them in the
+//! runtime's pre-state (see `lez_framework::pda::verify_own_pda`).
+//! `program_id = "<expr>"` pins the ID instead, for hosts and tests that know it.
+//!
 //! ## IDL Generation
 //!
+//! `#[lez_program]` writes `<program>.idl.json` to `idl/` in the target
+//! directory (or `idl_dir = "<dir>"`) whenever it is compiled, when that
+//! directory is writable, and exposes its path as `PROGRAM_IDL_PATH`. A
+//! standalone printer can still be generated from a source file:
+//!
 //! ```rust,ignore
 //! // generate_idl.rs — one-liner!
 //! lez_framework::generate_idl!("src/bin/treasury.rs");
 //! ```
 
+mod declare_program;
+mod idl;
+
 use proc_macro::TokenStream;
-use sha2::{Sha256, Digest};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
 use syn::{
@@ -41,28 +55,54 @@ use syn::{
 /// Main entry point: `#[lez_program]` on a module.
 ///
 /// This macro:
-/// 1. Finds all `#[instruction]` functions in the module
+/// 1. Finds all `#[instruction]` functions in the module and its instruction groups
 /// 2. Generates a serde-serializable `Instruction` enum
 /// 3. Generates the `fn main()` with read/dispatch/write boilerplate
 /// 4. Generates account validation code per instruction
 /// 5. Generates `PROGRAM_IDL_JSON` const with complete IDL (including PDA seeds)
+///    and, when it can, writes the same IDL to `PROGRAM_IDL_PATH`
 /// Program-level configuration parsed from `#[lez_program(...)]` attributes.
 struct ProgramConfig {
     /// External instruction enum path, e.g. `my_crate::Instruction`.
     /// If set, the macro will NOT generate its own `Instruction` enum.
     external_instruction: Option<syn::Path>,
+    /// Expression evaluating to this program's own `ProgramId`, e.g.
+    /// `crate::PROGRAM_ID`. Without it, this program's PDAs are checked
+    /// against the owner recorded in their pre-state.
+    program_id: Option<syn::Expr>,
+    /// Source files (relative to the program crate's manifest directory)
+    /// scanned for `#[lez_account]` / `#[lez_type]` items, e.g. a shared core crate.
+    types_from: Vec<String>,
+    /// How `Instruction` variants are tagged on the wire.
+    dispatch: Dispatch,
+    /// Directory (relative to the program crate's manifest directory) the
+    /// `<program>.idl.json` file is written to. Defaults to `idl/` in
+    /// `CARGO_TARGET_DIR`, or in `target/` of the outermost enclosing workspace.
+    idl_dir: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Dispatch {
+    /// serde's `u32` variant index (the default).
+    Index,
+    /// lssa-lang's 8-byte `SHA256("global:name")[..8]`, `dispatch = "discriminator"`.
+    Discriminator,
 }
 
 impl ProgramConfig {
-    fn parse(attr: TokenStream) -> syn::Result<Self> {
+    fn parse(attr: TokenStream2) -> syn::Result<Self> {
         let mut config = ProgramConfig {
             external_instruction: None,
+            program_id: None,
+            types_from: Vec::new(),
+            dispatch: Dispatch::Index,
+            idl_dir: None,
         };
         if attr.is_empty() {
             return Ok(config);
         }
         let parser = syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated;
-        let metas = parser.parse(attr)?;
+        let metas = parser.parse2(attr)?;
         for meta in metas {
             if let syn::Meta::NameValue(nv) = &meta {
                 if nv.path.is_ident("instruction") {
@@ -71,6 +111,39 @@ impl ProgramConfig {
                     } else {
                         return Err(syn::Error::new_spanned(&nv.value, "expected string literal"));
                     }
+                } else if nv.path.is_ident("program_id") {
+                    if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &nv.value {
+                        config.program_id = Some(s.parse()?);
+                    } else {
+                        return Err(syn::Error::new_spanned(&nv.value, "expected string literal"));
+                    }
+                } else if nv.path.is_ident("types_from") {
+                    if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &nv.value {
+                        config.types_from.push(s.value());
+                    } else {
+                        return Err(syn::Error::new_spanned(&nv.value, "expected string literal"));
+                    }
+                } else if nv.path.is_ident("idl_dir") {
+                    if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &nv.value {
+                        config.idl_dir = Some(s.value());
+                    } else {
+                        return Err(syn::Error::new_spanned(&nv.value, "expected string literal"));
+                    }
+                } else if nv.path.is_ident("dispatch") {
+                    if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &nv.value {
+                        config.dispatch = match s.value().as_str() {
+                            "index" => Dispatch::Index,
+                            "discriminator" => Dispatch::Discriminator,
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    s,
+                                    "expected \"index\" or \"discriminator\"",
+                                ))
+                            }
+                        };
+                    } else {
+                        return Err(syn::Error::new_spanned(&nv.value, "expected string literal"));
+                    }
                 } else {
                     return Err(syn::Error::new_spanned(&nv.path, "unknown attribute"));
                 }
@@ -84,7 +157,7 @@ impl ProgramConfig {
 
 #[proc_macro_attribute]
 pub fn lez_program(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let config = match ProgramConfig::parse(attr) {
+    let config = match ProgramConfig::parse(attr.into()) {
         Ok(c) => c,
         Err(err) => return err.to_compile_error().into(),
     };
@@ -96,12 +169,167 @@ pub fn lez_program(attr: TokenStream, item: TokenStream) -> TokenStream {
 }
 
 /// Marker attribute for instruction functions within an `#[lez_program]` module.
-/// Processed by `#[lez_program]`, not standalone.
+/// Processed by `#[lez_program]`; standalone (in an `instruction_groups!`
+/// file) it only strips the `#[account]` parameter attributes.
+///
+/// `#[instruction(index = N)]` pins the variant index the instruction is
+/// serialized with. Instructions without one continue from the previous
+/// index, starting at 0, so reordering pinned handlers keeps clients working.
+///
+/// `#[instruction(threshold(members, state.threshold, state.members))]`
+/// requires at least `state.threshold` distinct authorized accounts in the
+/// `members: Vec<AccountWithMetadata>` parameter, each listed in
+/// `state.members` (optional). Typed accounts named in the expressions are
+/// decoded for the check.
 #[proc_macro_attribute]
 pub fn instruction(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    match syn::parse::<ItemFn>(item.clone()) {
+        Ok(mut func) => {
+            strip_instruction_attrs(&mut func);
+            quote! { #func }.into()
+        }
+        Err(_) => item,
+    }
+}
+
+/// Marks an inline module inside an `#[lez_program]` module whose
+/// `#[instruction]` functions belong to the program:
+///
+/// ```rust,ignore
+/// #[lez_program]
+/// mod treasury {
+///     #[instruction_group]
+///     mod admin {
+///         use super::*;
+///
+///         #[instruction]
+///         pub fn freeze(#[account(mut, signer)] admin: AccountWithMetadata) -> LezResult { /* ... */ }
+///     }
+/// }
+/// ```
+///
+/// Groups may be nested, and are made `pub(crate)` so the generated `main()`
+/// can call their handlers. See [`macro@instruction_groups`] for groups kept
+/// in their own files.
+#[proc_macro_attribute]
+pub fn instruction_group(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemMod);
+    // Standalone, i.e. inside a group file: `#[lez_program]` has already
+    // collected its instructions, so only the module itself is rewritten
+    let dirs: Vec<std::path::PathBuf> = call_site_module_dirs()
+        .iter()
+        .map(|dir| dir.join(input.ident.to_string()))
+        .collect();
+    let mut unused = ProgramItems::default();
+    match expand_instruction_group(&input, &dirs, &[], &mut unused) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Declares instruction groups kept in their own files, the way `mod` would:
+///
+/// ```rust,ignore
+/// #[lez_program]
+/// mod treasury {
+///     instruction_groups! {
+///         mod admin;   // treasury/admin.rs next to the program's source file
+///         mod user;
+///     }
+/// }
+/// ```
+///
+/// (`mod admin;` itself is not allowed inside attribute macro input on stable
+/// Rust.) `#[lez_program]` reads the files for their instructions, types and
+/// errors, so the program still gets one `Instruction` enum and one IDL.
+/// Group files start with `use super::*;` and may declare further groups.
+#[proc_macro]
+pub fn instruction_groups(input: TokenStream) -> TokenStream {
+    let decls = parse_macro_input!(input as GroupDecls);
+    let mut unused = ProgramItems::default();
+    let mut tokens = TokenStream2::new();
+    for decl in &decls.0 {
+        match expand_group_file(decl, &call_site_module_dirs(), &[], &mut unused) {
+            Ok(group) => tokens.extend(group),
+            Err(err) => tokens.extend(err.to_compile_error()),
+        }
+    }
+    tokens.into()
+}
+
+/// Marks a struct or enum as account data, listed under `accounts` in the IDL
+/// so `lez-cli inspect --type` can decode it.
+///
+/// Picked up inside an `#[lez_program]` module, or in files named by
+/// `#[lez_program(types_from = "...")]`. Standalone it leaves the item unchanged.
+#[proc_macro_attribute]
+pub fn lez_account(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+
+/// Marks a struct or enum used inside account data or instruction args,
+/// listed under `types` in the IDL. See [`macro@lez_account`].
+#[proc_macro_attribute]
+pub fn lez_type(_attr: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
 
+/// A set of accounts shared by several instructions, with the same
+/// `#[account(...)]` constraints as instruction parameters:
+///
+/// ```rust,ignore
+/// #[derive(LezAccounts)]
+/// pub struct Transfer {
+///     #[account(mut, signer)]
+///     pub from: AccountWithMetadata,
+///     #[account(mut)]
+///     pub to: AccountWithMetadata,
+/// }
+///
+/// #[instruction]
+/// pub fn transfer(ctx: Transfer, amount: u128) -> LezResult { /* ... */ }
+/// ```
+///
+/// Declared in the `#[lez_program]` module or one of its instruction groups,
+/// the struct is flattened into the instruction's account list, validation
+/// and IDL exactly as if its fields were parameters. The derive itself only
+/// checks the struct's shape.
+#[proc_macro_derive(LezAccounts, attributes(account))]
+pub fn derive_lez_accounts(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::DeriveInput);
+    let checked = match &input.data {
+        syn::Data::Struct(s) => parse_account_set(&input.ident, &input.generics, &s.fields, &[]),
+        _ => Err(syn::Error::new_spanned(&input.ident, "LezAccounts can only be derived for structs")),
+    };
+    match checked {
+        Ok(_) => TokenStream::new(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Program-specific error enum with stable codes.
+///
+/// ```rust,ignore
+/// #[lez_error]
+/// pub enum TreasuryError {
+///     #[msg("Threshold must be at least 1")]
+///     InvalidThreshold,          // code 0
+///     #[msg("Treasury is frozen")]
+///     Frozen = 10,               // code 10
+/// }
+/// ```
+///
+/// Converts into `LezError::Custom`, and is listed under `errors` in the IDL
+/// when declared inside the `#[lez_program]` module or a `types_from` file.
+#[proc_macro_attribute]
+pub fn lez_error(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::ItemEnum);
+    match expand_lez_error(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
 /// Generate IDL from a program source file.
 ///
 /// Parses the given Rust source file, finds the `#[lez_program]` module,
@@ -121,17 +349,86 @@ pub fn generate_idl(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Generate `ChainedCall` builders for another LEZ program from its IDL.
+///
+/// The path is relative to `CARGO_MANIFEST_DIR`. The macro expands to a
+/// module named after the program with one function per instruction, taking
+/// the callee's program ID, its accounts and its typed arguments:
+///
+/// ```rust,ignore
+/// lez_framework::declare_program!("idl/token.idl.json");
+///
+/// let call = token::transfer(TOKEN_PROGRAM_ID, from, to, amount)?;
+/// Ok(LezOutput::with_chained_calls(post_states, vec![call]))
+/// ```
+///
+/// The instruction is serialized with the callee's dispatch (index or
+/// discriminator). Rest account lists are checked against their `min` / `max`,
+/// and PDA accounts are verified against their seeds before the call is built.
+/// A PDA derived under a `const` program of the callee's takes that program's
+/// ID from an extra `<account>_program: ProgramId` parameter after `program_id`.
+#[proc_macro]
+pub fn declare_program(input: TokenStream) -> TokenStream {
+    let lit = parse_macro_input!(input as syn::LitStr);
+    match declare_program::expand_declare_program(&lit) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
 // ─── Internal expansion logic ────────────────────────────────────────────
 
 /// Parsed info about one instruction function.
 struct InstructionInfo {
     fn_name: Ident,
+    /// Variant index in the serialized `Instruction` enum, set by `assign_instruction_indices`
+    index: u32,
+    /// Explicit `#[instruction(index = N)]`, if given
+    explicit_index: Option<syn::LitInt>,
+    /// `///` doc comments of the function
+    docs: Vec<String>,
     /// Account parameters (AccountWithMetadata type), in order
     accounts: Vec<AccountParam>,
     /// Non-account parameters (the instruction args)
     args: Vec<ArgParam>,
-    /// The original function item (with #[instruction] stripped)
-    func: ItemFn,
+    /// Instruction groups the function is declared in, below the program module
+    group: Vec<Ident>,
+    /// `#[instruction(threshold(...))]`
+    threshold: Option<ThresholdDef>,
+}
+
+/// `threshold(<signers>, <min>[, <members>])`: at least `min` distinct
+/// accounts of the rest account `signers` are authorized, all in `members`.
+struct ThresholdDef {
+    signers: Ident,
+    /// Integer expression over the accounts and arguments, by name; typed
+    /// accounts are decoded
+    min: syn::Expr,
+    /// Slice-like list of `AccountId`s or `[u8; 32]`s
+    members: Option<syn::Expr>,
+}
+
+impl ThresholdDef {
+    /// Names of the accounts `min` and `members` refer to.
+    fn referenced_accounts<'a>(&self, accounts: &'a [AccountParam]) -> Vec<&'a AccountParam> {
+        let mut idents = Vec::new();
+        let min = &self.min;
+        collect_idents(quote!(#min), &mut idents);
+        if let Some(members) = &self.members {
+            collect_idents(quote!(#members), &mut idents);
+        }
+        accounts.iter().filter(|a| idents.contains(&a.name)).collect()
+    }
+}
+
+fn collect_idents(tokens: TokenStream2, out: &mut Vec<Ident>) {
+    for token in tokens {
+        match token {
+            proc_macro2::TokenTree::Ident(ident) => out.push(ident),
+            proc_macro2::TokenTree::Group(group) => collect_idents(group.stream(), out),
+            _ => {}
+        }
+    }
 }
 
 struct AccountParam {
@@ -139,31 +436,158 @@ struct AccountParam {
     constraints: AccountConstraints,
     /// True if this is a Vec<AccountWithMetadata> (variable-length trailing accounts)
     is_rest: bool,
+    /// `T` of a `LezAccount<T>`, whose data is decoded before dispatch
+    data_type: Option<Type>,
+    docs: Vec<String>,
+    /// The `#[derive(LezAccounts)]` parameter this account is a field of
+    context: Option<AccountContext>,
+}
+
+/// A `#[derive(LezAccounts)]` struct, collected from the program module.
+struct AccountSet {
+    name: Ident,
+    /// Instruction groups the struct is declared in, below the program module
+    group: Vec<Ident>,
+    /// Named fields, in order
+    fields: Vec<syn::Field>,
+}
+
+/// Where a flattened account goes back to when the handler is called.
+#[derive(Clone)]
+struct AccountContext {
+    /// The handler parameter, e.g. `ctx`
+    param: Ident,
+    /// The struct's path below the program module, e.g. `admin::Transfer`
+    path: Vec<Ident>,
 }
 
 #[derive(Default)]
 struct AccountConstraints {
     mutable: bool,
     init: bool,
+    /// Like `init`, but an already initialized account is accepted as is
+    init_if_needed: bool,
+    /// `space = N`: exact data size after the handler
+    space: Option<usize>,
+    /// `max_space = N`: maximum data size after the handler
+    max_space: Option<usize>,
+    /// `min = N`: fewest accounts in a rest list
+    min: Option<usize>,
+    /// `max = N`: most accounts in a rest list
+    max: Option<usize>,
     owner: Option<syn::Expr>,
     signer: bool,
     pda_seeds: Vec<PdaSeedDef>,
+    /// `pda_program = ...`: the program the PDA is derived under, instead
+    /// of the executing one
+    pda_program: Option<PdaProgramDef>,
+    /// `has_one = <field>`: the sibling account named `field` must have the
+    /// ID stored in this account's data field of the same name
+    has_one: Vec<Ident>,
+    /// `close = <recipient>`: after the handler, the account's balance moves
+    /// to `recipient` and its data is reset
+    close: Option<Ident>,
+    checks: Vec<ConstraintCheck>,
+}
+
+/// A `constraint = <expr>` check, optionally `@ <error>`.
+struct ConstraintCheck {
+    /// `bool` expression over the accounts and arguments, by name
+    expr: syn::Expr,
+    /// Converted into `LezError` when the check fails; defaults to
+    /// `LezError::ConstraintViolated`
+    error: Option<syn::Expr>,
+}
+
+impl ConstraintCheck {
+    /// The check as written, recorded in the IDL.
+    fn to_idl_string(&self) -> String {
+        let expr = &self.expr;
+        let mut text = tokens_to_idl_string(quote!(#expr));
+        if let Some(error) = &self.error {
+            text.push_str(" @ ");
+            text.push_str(&tokens_to_idl_string(quote!(#error)));
+        }
+        text
+    }
 }
 
-/// A PDA seed definition from the `#[account(pda = ...)]` attribute.
+/// A PDA seed definition from the `#[account(pda = ...)]` attribute. The
+/// literal is kept for its span.
 #[derive(Clone)]
 enum PdaSeedDef {
     /// `const("some_string")` — a constant string seed
-    Const(String),
+    Const(syn::LitStr),
     /// `account("other_account_name")` — seed derived from another account's ID
-    Account(String),
+    Account(syn::LitStr),
     /// `arg("some_arg")` — seed derived from an instruction argument
-    Arg(String),
+    Arg(syn::LitStr),
+}
+
+/// The program a PDA is derived under, from `pda_program = ...`.
+#[derive(Clone)]
+enum PdaProgramDef {
+    /// `arg("name")` — a `ProgramId` instruction argument
+    Arg(syn::LitStr),
+    /// `const(EXPR)` — a `ProgramId` expression, such as a constant
+    Const(syn::Expr),
 }
 
 struct ArgParam {
     name: Ident,
     ty: Type,
+    docs: Vec<String>,
+}
+
+/// A `#[lez_account]` / `#[lez_type]` item to be described in the IDL.
+struct IdlTypeInfo {
+    name: String,
+    /// `#[lez_account]` (IDL `accounts`) rather than `#[lez_type]` (IDL `types`)
+    is_account: bool,
+    kind: IdlTypeKind,
+}
+
+/// A variant of a `#[lez_error]` enum.
+struct IdlErrorInfo {
+    name: String,
+    /// Program-local code; `LezError::error_code()` reports it as `6000 + code`
+    code: u32,
+    msg: Option<String>,
+}
+
+/// Types and errors collected for the IDL, alongside the instructions.
+#[derive(Default)]
+struct IdlItems {
+    types: Vec<IdlTypeInfo>,
+    errors: Vec<IdlErrorInfo>,
+    /// Integer `const`s, so array lengths like `[u8; KEY_LEN]` can be resolved
+    consts: Vec<(String, usize)>,
+}
+
+impl IdlItems {
+    fn extend(&mut self, other: IdlItems) {
+        self.types.extend(other.types);
+        self.errors.extend(other.errors);
+        self.consts.extend(other.consts);
+    }
+
+    /// Record `const NAME: T = <integer literal>;` items.
+    fn collect_const(&mut self, item: &syn::Item) {
+        if let syn::Item::Const(c) = item {
+            if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(n), .. }) = &*c.expr {
+                if let Ok(value) = n.base10_parse() {
+                    self.consts.push((c.ident.to_string(), value));
+                }
+            }
+        }
+    }
+}
+
+enum IdlTypeKind {
+    /// Field names and types; tuple fields are named by position
+    Struct(Vec<(String, Type)>),
+    /// Variant names and their fields
+    Enum(Vec<(String, Vec<(String, Type)>)>),
 }
 
 fn expand_lez_program(input: ItemMod, config: ProgramConfig) -> syn::Result<TokenStream2> {
@@ -174,23 +598,24 @@ fn expand_lez_program(input: ItemMod, config: ProgramConfig) -> syn::Result<Toke
         .as_ref()
         .ok_or_else(|| syn::Error::new_spanned(&input, "lez_program module must have a body"))?;
 
-    // Collect instruction functions and other items
-    let mut instructions: Vec<InstructionInfo> = Vec::new();
-    let mut other_items: Vec<TokenStream2> = Vec::new();
-
-    for item in items {
-        match item {
-            syn::Item::Fn(func) => {
-                if has_instruction_attr(&func.attrs) {
-                    instructions.push(parse_instruction(func.clone())?);
-                } else {
-                    other_items.push(quote! { #func });
-                }
-            }
-            other => {
-                other_items.push(quote! { #other });
-            }
-        }
+    // Collect instruction functions (including those in instruction groups),
+    // IDL types and errors; `items` is the module body to emit
+    let dirs: Vec<std::path::PathBuf> = call_site_module_dirs()
+        .iter()
+        .map(|dir| dir.join(mod_name.to_string()))
+        .collect();
+    let mut collected = ProgramItems::default();
+    let items = collect_program_items(items, &dirs, &[], &mut collected)?;
+    let mut instructions = collected.parse_instructions()?;
+    let mut idl_items = collected.idl_items;
+
+    // IDL types and errors from other source files, e.g. a shared core crate
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let mut types_from_paths: Vec<String> = Vec::new();
+    for path in &config.types_from {
+        let resolved = std::path::Path::new(&manifest_dir).join(path);
+        idl_items.extend(load_idl_items_from(&resolved, &input.ident)?);
+        types_from_paths.push(resolved.to_string_lossy().to_string());
     }
 
     if instructions.is_empty() {
@@ -199,16 +624,58 @@ fn expand_lez_program(input: ItemMod, config: ProgramConfig) -> syn::Result<Toke
             "lez_program must contain at least one #[instruction] function",
         ));
     }
+    check_instruction_names(&instructions)?;
+    assign_instruction_indices(&mut instructions)?;
 
     // Generate the Instruction enum (or use external one)
+    if config.dispatch == Dispatch::Discriminator {
+        if let Some(path) = &config.external_instruction {
+            return Err(syn::Error::new_spanned(
+                path,
+                "`dispatch = \"discriminator\"` requires the generated Instruction enum",
+            ));
+        }
+        if let Some(lit) = instructions.iter().find_map(|ix| ix.explicit_index.as_ref()) {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "`index` has no effect with `dispatch = \"discriminator\"`",
+            ));
+        }
+        check_discriminators(&instructions)?;
+    }
     let enum_def = if config.external_instruction.is_none() {
         let enum_variants = generate_enum_variants(&instructions);
-        quote! {
-            #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-            pub enum Instruction {
-                #(#enum_variants),*
+        if config.dispatch == Dispatch::Discriminator {
+            let serde_impls = generate_discriminator_serde(&instructions);
+            quote! {
+                #[derive(Debug, Clone)]
+                pub enum Instruction {
+                    #(#enum_variants),*
+                }
+                #serde_impls
+            }
+        } else if has_positional_indices(&instructions) {
+            quote! {
+                #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+                pub enum Instruction {
+                    #(#enum_variants),*
+                }
+            }
+        } else {
+            let serde_impls = generate_instruction_serde(&instructions);
+            quote! {
+                #[derive(Debug, Clone)]
+                pub enum Instruction {
+                    #(#enum_variants),*
+                }
+                #serde_impls
             }
         }
+    } else if let Some(lit) = instructions.iter().find_map(|ix| ix.explicit_index.as_ref()) {
+        return Err(syn::Error::new_spanned(
+            lit,
+            "`index` requires the generated Instruction enum; an external `instruction = \"...\"` enum defines its own encoding",
+        ));
     } else {
         // External instruction: import it as `Instruction` if it's not already named that
         let path = config.external_instruction.as_ref().unwrap();
@@ -218,13 +685,11 @@ fn expand_lez_program(input: ItemMod, config: ProgramConfig) -> syn::Result<Toke
     };
 
     // Generate match arms for dispatch
-    let match_arms = generate_match_arms(mod_name, &instructions);
-
-    // Generate the handler functions (with #[instruction] stripped, account attrs stripped)
-    let handler_fns = generate_handler_fns(&instructions);
+    let match_arms = generate_match_arms(mod_name, &instructions, config.program_id.as_ref());
 
     // Generate validation functions
     let validation_fns = generate_validation(&instructions);
+    let pda_validation_fns = generate_pda_validation(&instructions);
 
     // Generate main function
     let main_fn = quote! {
@@ -234,22 +699,15 @@ fn expand_lez_program(input: ItemMod, config: ProgramConfig) -> syn::Result<Toke
                 = nssa_core::program::read_nssa_inputs::<Instruction>();
             let pre_states_clone = pre_states.clone();
 
-            // Dispatch to instruction handler
-            let result: Result<
-                (Vec<nssa_core::program::AccountPostState>, Vec<nssa_core::program::ChainedCall>),
-                lez_framework::error::LezError
-            > = match instruction {
+            // Dispatch to instruction handler. Every failure aborts through
+            // `lez_framework::failure::abort` with an encoded `LezFailure`.
+            let (post_states, chained_calls): (
+                Vec<nssa_core::program::AccountPostState>,
+                Vec<nssa_core::program::ChainedCall>,
+            ) = match instruction {
                 #(#match_arms)*
             };
 
-            // Handle result
-            let (post_states, chained_calls) = match result {
-                Ok(output) => output,
-                Err(e) => {
-                    panic!("Program error [{}]: {}", e.error_code(), e);
-                }
-            };
-
             // Write outputs to zkVM host
             nssa_core::program::write_nssa_outputs_with_chained_call(
                 instruction_words,
@@ -265,8 +723,25 @@ fn expand_lez_program(input: ItemMod, config: ProgramConfig) -> syn::Result<Toke
         let segments: Vec<String> = p.segments.iter().map(|s| s.ident.to_string()).collect();
         segments.join("::")
     });
-    let idl_fn = generate_idl_fn(mod_name, &instructions, ext_instr_str.as_deref());
-    let idl_json = generate_idl_json(mod_name, &instructions, ext_instr_str.as_deref());
+    let idl = idl::build_idl(
+        &mod_name.to_string(),
+        &instructions,
+        &idl_items,
+        ext_instr_str.as_deref(),
+        config.dispatch,
+        &extract_docs(&input.attrs),
+    )?;
+    let idl_json = idl.to_json();
+
+    // Keep `<program>.idl.json` on disk in sync with the code being compiled.
+    // Best effort: a read-only or sandboxed target directory must not fail
+    // the build, and `PROGRAM_IDL_JSON` carries the same IDL anyway.
+    let idl_dir = match &config.idl_dir {
+        Some(dir) => std::path::Path::new(&manifest_dir).join(dir),
+        None => idl::default_idl_dir(std::path::Path::new(&manifest_dir)),
+    };
+    let idl_path = idl_dir.join(format!("{}.idl.json", mod_name)).display().to_string();
+    let _ = idl::write_idl_file(&idl_dir, &mod_name.to_string(), &idl.to_json_pretty());
 
     // Assemble everything
     let expanded = quote! {
@@ -276,19 +751,29 @@ fn expand_lez_program(input: ItemMod, config: ProgramConfig) -> syn::Result<Toke
         // Complete IDL as a const JSON string (accessible from any target)
         pub const PROGRAM_IDL_JSON: &str = #idl_json;
 
+        // Where the IDL file is written when the program compiles
+        pub const PROGRAM_IDL_PATH: &str = #idl_path;
+
+        // Help cargo track `types_from` sources
+        #(const _: &str = include_str!(#types_from_paths);)*
+
         // The program module with handler functions
         mod #mod_name {
             use super::*;
 
-            #(#other_items)*
-
-            #(#handler_fns)*
+            #(#items)*
 
             #(#validation_fns)*
+
+            #(#pda_validation_fns)*
         }
 
         // IDL generation (available at host-side for tooling)
-        #idl_fn
+        #[allow(dead_code)]
+        pub fn __program_idl() -> lez_framework::idl::LezIdl {
+            lez_framework::idl::LezIdl::from_json(PROGRAM_IDL_JSON)
+                .expect("PROGRAM_IDL_JSON is generated from the same model")
+        }
 
         // The guest binary entry point (cfg-gated so cargo test works on host)
         #[cfg(not(test))]
@@ -302,8 +787,9 @@ fn has_instruction_attr(attrs: &[Attribute]) -> bool {
     attrs.iter().any(|a| a.path().is_ident("instruction"))
 }
 
-fn parse_instruction(func: ItemFn) -> syn::Result<InstructionInfo> {
+fn parse_instruction(func: ItemFn, account_sets: &[AccountSet]) -> syn::Result<InstructionInfo> {
     let fn_name = func.sig.ident.clone();
+    let (explicit_index, threshold) = parse_instruction_options(&func.attrs)?;
     let mut accounts = Vec::new();
     let mut args = Vec::new();
 
@@ -313,12 +799,15 @@ fn parse_instruction(func: ItemFn) -> syn::Result<InstructionInfo> {
                 let param_name = extract_param_name(pat_type)?;
                 let ty = &*pat_type.ty;
 
-                if is_account_type(ty) {
+                if is_account_type(ty) || is_typed_account_type(ty) {
                     let constraints = parse_account_constraints(&pat_type.attrs)?;
                     accounts.push(AccountParam {
                         name: param_name,
                         constraints,
                         is_rest: false,
+                        data_type: typed_account_data_type(ty),
+                        docs: extract_docs(&pat_type.attrs),
+                        context: None,
                     });
                 } else if is_vec_account_type(ty) {
                     let constraints = parse_account_constraints(&pat_type.attrs)?;
@@ -326,11 +815,36 @@ fn parse_instruction(func: ItemFn) -> syn::Result<InstructionInfo> {
                         name: param_name,
                         constraints,
                         is_rest: true,
+                        data_type: None,
+                        docs: extract_docs(&pat_type.attrs),
+                        context: None,
                     });
+                } else if let Some(set) = find_account_set(ty, account_sets) {
+                    if let Some(attr) = pat_type.attrs.iter().find(|a| a.path().is_ident("account")) {
+                        return Err(syn::Error::new_spanned(
+                            attr,
+                            format!("put account constraints on the fields of `{}`", set.name),
+                        ));
+                    }
+                    let mut path = set.group.clone();
+                    path.push(set.name.clone());
+                    let context = AccountContext { param: param_name, path };
+                    for field in &set.fields {
+                        let field_ty = &field.ty;
+                        accounts.push(AccountParam {
+                            name: field.ident.clone().expect("LezAccounts fields are named"),
+                            constraints: parse_account_constraints(&field.attrs)?,
+                            is_rest: is_vec_account_type(field_ty),
+                            data_type: typed_account_data_type(field_ty),
+                            docs: extract_docs(&field.attrs),
+                            context: Some(context.clone()),
+                        });
+                    }
                 } else {
                     args.push(ArgParam {
                         name: param_name,
                         ty: ty.clone(),
+                        docs: extract_docs(&pat_type.attrs),
                     });
                 }
             }
@@ -343,14 +857,207 @@ fn parse_instruction(func: ItemFn) -> syn::Result<InstructionInfo> {
         }
     }
 
+    // Flattened fields share the handler's namespace in the generated `main()`
+    let names = accounts.iter().map(|a| &a.name).chain(args.iter().map(|a| &a.name));
+    for (i, name) in names.clone().enumerate() {
+        if names.clone().take(i).any(|other| other == name) {
+            return Err(syn::Error::new_spanned(
+                &fn_name,
+                format!("account or argument `{}` appears twice in `{}`", name, fn_name),
+            ));
+        }
+    }
+
+    check_account_references(&accounts)?;
+    check_pda_seeds(&accounts, &args)?;
+    if let Some(threshold) = &threshold {
+        if !accounts.iter().any(|a| a.is_rest && a.name == threshold.signers) {
+            return Err(syn::Error::new_spanned(
+                &threshold.signers,
+                format!("`threshold` signers must be the `Vec<AccountWithMetadata>` parameter, not `{}`", threshold.signers),
+            ));
+        }
+    }
+
     Ok(InstructionInfo {
         fn_name,
+        index: 0,
+        explicit_index,
+        docs: extract_docs(&func.attrs),
         accounts,
         args,
-        func,
+        group: Vec::new(),
+        threshold,
     })
 }
 
+/// Every `has_one = field` must be on a `LezAccount<T>` and name a fixed
+/// sibling account; every `close = recipient` must name another fixed,
+/// `mut` account. A single rest account may come last, and only it takes
+/// `min` / `max`; data sizes and PDAs only apply to fixed accounts.
+fn check_account_references(accounts: &[AccountParam]) -> syn::Result<()> {
+    for (i, acc) in accounts.iter().enumerate() {
+        if acc.is_rest {
+            if accounts[..i].iter().any(|a| a.is_rest) {
+                return Err(syn::Error::new_spanned(
+                    &acc.name,
+                    "an instruction can have only one `Vec<AccountWithMetadata>` parameter",
+                ));
+            }
+            if i + 1 != accounts.len() {
+                return Err(syn::Error::new_spanned(
+                    &acc.name,
+                    "`Vec<AccountWithMetadata>` must be the last account parameter",
+                ));
+            }
+            if acc.constraints.space.is_some() || acc.constraints.max_space.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &acc.name,
+                    "`space` and `max_space` cannot be used on a rest account",
+                ));
+            }
+            if !acc.constraints.pda_seeds.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    &acc.name,
+                    "`pda` cannot be used on a rest account: its accounts cannot share one address",
+                ));
+            }
+        } else if acc.constraints.min.is_some() || acc.constraints.max.is_some() {
+            return Err(syn::Error::new_spanned(
+                &acc.name,
+                "`min` and `max` only apply to a `Vec<AccountWithMetadata>` parameter",
+            ));
+        }
+        if let Some(recipient) = &acc.constraints.close {
+            if acc.is_rest {
+                return Err(syn::Error::new_spanned(recipient, "a rest account cannot be closed"));
+            }
+            match accounts.iter().find(|a| !a.is_rest && a.name == *recipient) {
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        recipient,
+                        format!("`close = {}`: no account named `{}` in this instruction", recipient, recipient),
+                    ))
+                }
+                Some(r) if r.name == acc.name => {
+                    return Err(syn::Error::new_spanned(recipient, "an account cannot be closed into itself"))
+                }
+                Some(r) if !r.constraints.mutable => {
+                    return Err(syn::Error::new_spanned(
+                        recipient,
+                        format!("`close = {}` requires `{}` to be `mut`", recipient, recipient),
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        for field in &acc.constraints.has_one {
+            if acc.data_type.is_none() {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    format!("`has_one` requires `{}` to be a `LezAccount<T>`", acc.name),
+                ));
+            }
+            if !accounts.iter().any(|a| !a.is_rest && a.name == *field) {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    format!("`has_one = {}`: no account named `{}` in this instruction", field, field),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collect `///` doc comments (`#[doc = "..."]` attributes), one entry per line.
+fn extract_docs(attrs: &[Attribute]) -> Vec<String> {
+    let mut docs = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("doc")) {
+        if let syn::Meta::NameValue(nv) = &attr.meta {
+            if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &nv.value {
+                for line in s.value().lines() {
+                    docs.push(line.strip_prefix(' ').unwrap_or(line).trim_end().to_string());
+                }
+            }
+        }
+    }
+    docs
+}
+
+/// Parse the optional `index = N` and `threshold(...)` from `#[instruction(...)]`.
+fn parse_instruction_options(
+    attrs: &[Attribute],
+) -> syn::Result<(Option<syn::LitInt>, Option<ThresholdDef>)> {
+    let mut index = None;
+    let mut threshold = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("instruction")) {
+        if let syn::Meta::List(_) = &attr.meta {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("index") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    lit.base10_parse::<u32>()?;
+                    index = Some(lit);
+                    Ok(())
+                } else if meta.path.is_ident("threshold") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let args = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated(&content)?;
+                    let mut args = args.into_iter();
+                    let signers = match args.next() {
+                        Some(syn::Expr::Path(path)) if path.path.get_ident().is_some() => {
+                            path.path.get_ident().unwrap().clone()
+                        }
+                        _ => return Err(meta.error("expected `threshold(<signers>, <min>)` or `threshold(<signers>, <min>, <members>)`")),
+                    };
+                    let min = args.next()
+                        .ok_or_else(|| meta.error("`threshold` needs the required number of signatures"))?;
+                    let members = args.next();
+                    if let Some(extra) = args.next() {
+                        return Err(syn::Error::new_spanned(extra, "unexpected `threshold` argument"));
+                    }
+                    threshold = Some(ThresholdDef { signers, min, members });
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown instruction option, expected `index = N` or `threshold(...)`"))
+                }
+            })?;
+        }
+    }
+    Ok((index, threshold))
+}
+
+/// Resolve every instruction's variant index, like enum discriminants:
+/// an explicit `index` wins, otherwise one more than the previous instruction.
+fn assign_instruction_indices(instructions: &mut [InstructionInfo]) -> syn::Result<()> {
+    let mut used: Vec<(u32, String)> = Vec::new();
+    let mut next: u64 = 0;
+    for ix in instructions.iter_mut() {
+        let index = match &ix.explicit_index {
+            Some(lit) => lit.base10_parse::<u32>()?,
+            None => u32::try_from(next).map_err(|_| {
+                syn::Error::new_spanned(&ix.fn_name, "instruction index overflows u32")
+            })?,
+        };
+        if let Some((_, other)) = used.iter().find(|(i, _)| *i == index) {
+            let message = format!("instruction index {} is already used by `{}`", index, other);
+            return Err(match &ix.explicit_index {
+                Some(lit) => syn::Error::new_spanned(lit, message),
+                None => syn::Error::new_spanned(&ix.fn_name, message),
+            });
+        }
+        used.push((index, ix.fn_name.to_string()));
+        ix.index = index;
+        next = u64::from(index) + 1;
+    }
+    Ok(())
+}
+
+/// True if every instruction's index is its position, i.e. serde's derived
+/// variant indices already match.
+fn has_positional_indices(instructions: &[InstructionInfo]) -> bool {
+    instructions.iter().enumerate().all(|(i, ix)| ix.index as usize == i)
+}
+
 fn extract_param_name(pat_type: &PatType) -> syn::Result<Ident> {
     match &*pat_type.pat {
         Pat::Ident(pat_ident) => Ok(pat_ident.ident.clone()),
@@ -370,6 +1077,25 @@ fn is_account_type(ty: &Type) -> bool {
     false
 }
 
+/// Check if a type is `LezAccount<T>` (account with borsh-decoded data).
+fn is_typed_account_type(ty: &Type) -> bool {
+    typed_account_data_type(ty).is_some()
+}
+
+/// `T` of a `LezAccount<T>` type.
+fn typed_account_data_type(ty: &Type) -> Option<Type> {
+    let Type::Path(type_path) = ty else { return None };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "LezAccount" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first() {
+        Some(syn::GenericArgument::Type(inner)) => Some(inner.clone()),
+        _ => None,
+    }
+}
+
 /// Check if a type is Vec<AccountWithMetadata> (variable-length account list).
 fn is_vec_account_type(ty: &Type) -> bool {
     if let Type::Path(type_path) = ty {
@@ -386,18 +1112,91 @@ fn is_vec_account_type(ty: &Type) -> bool {
     false
 }
 
-fn parse_account_constraints(attrs: &[Attribute]) -> syn::Result<AccountConstraints> {
-    let mut constraints = AccountConstraints::default();
+fn has_lez_accounts_derive(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|a| a.path().is_ident("derive")).any(|a| {
+        let mut found = false;
+        let _ = a.parse_nested_meta(|meta| {
+            found |= meta.path.segments.last().is_some_and(|s| s.ident == "LezAccounts");
+            Ok(())
+        });
+        found
+    })
+}
 
-    for attr in attrs {
-        if attr.path().is_ident("account") {
-            attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("mut") {
-                    constraints.mutable = true;
-                    Ok(())
-                } else if meta.path.is_ident("init") {
-                    constraints.init = true;
-                    constraints.mutable = true;
+/// Check a `#[derive(LezAccounts)]` struct: named, non-generic, and made
+/// only of account fields, with at most one trailing `Vec<AccountWithMetadata>`.
+fn parse_account_set(
+    name: &Ident,
+    generics: &syn::Generics,
+    fields: &syn::Fields,
+    group: &[Ident],
+) -> syn::Result<AccountSet> {
+    if !generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(generics, "LezAccounts structs cannot be generic"));
+    }
+    let syn::Fields::Named(named) = fields else {
+        return Err(syn::Error::new_spanned(name, "LezAccounts structs must have named fields"));
+    };
+    let fields: Vec<syn::Field> = named.named.iter().cloned().collect();
+    for (i, field) in fields.iter().enumerate() {
+        let ty = &field.ty;
+        if is_vec_account_type(ty) {
+            if i + 1 != fields.len() {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "`Vec<AccountWithMetadata>` must be the last field",
+                ));
+            }
+        } else if !is_account_type(ty) && !is_typed_account_type(ty) {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "LezAccounts fields must be `AccountWithMetadata`, `LezAccount<T>` or `Vec<AccountWithMetadata>`",
+            ));
+        }
+        parse_account_constraints(&field.attrs)?;
+    }
+    Ok(AccountSet { name: name.clone(), group: group.to_vec(), fields })
+}
+
+/// The account set a parameter of type `ty` refers to, by its last path segment.
+fn find_account_set<'a>(ty: &Type, account_sets: &'a [AccountSet]) -> Option<&'a AccountSet> {
+    let Type::Path(type_path) = ty else { return None };
+    let segment = type_path.path.segments.last()?;
+    account_sets.iter().find(|set| segment.ident == set.name)
+}
+
+fn parse_account_constraints(attrs: &[Attribute]) -> syn::Result<AccountConstraints> {
+    let mut constraints = AccountConstraints::default();
+
+    for attr in attrs {
+        if attr.path().is_ident("account") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("mut") {
+                    constraints.mutable = true;
+                    Ok(())
+                } else if meta.path.is_ident("init") {
+                    constraints.init = true;
+                    constraints.mutable = true;
+                    Ok(())
+                } else if meta.path.is_ident("init_if_needed") {
+                    constraints.init_if_needed = true;
+                    constraints.mutable = true;
+                    Ok(())
+                } else if meta.path.is_ident("space") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    constraints.space = Some(lit.base10_parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("max_space") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    constraints.max_space = Some(lit.base10_parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("min") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    constraints.min = Some(lit.base10_parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("max") {
+                    let lit: syn::LitInt = meta.value()?.parse()?;
+                    constraints.max = Some(lit.base10_parse()?);
                     Ok(())
                 } else if meta.path.is_ident("signer") {
                     constraints.signer = true;
@@ -413,10 +1212,64 @@ fn parse_account_constraints(attrs: &[Attribute]) -> syn::Result<AccountConstrai
                     let expr: syn::Expr = value.parse()?;
                     constraints.pda_seeds = parse_pda_expr(&expr)?;
                     Ok(())
+                } else if meta.path.is_ident("pda_program") {
+                    // pda_program = arg("name") or pda_program = const(EXPR)
+                    constraints.pda_program = Some(parse_pda_program(meta.value()?)?);
+                    Ok(())
+                } else if meta.path.is_ident("constraint") {
+                    let value = meta.value()?;
+                    let expr: syn::Expr = value.parse()?;
+                    let error = if value.peek(syn::Token![@]) {
+                        value.parse::<syn::Token![@]>()?;
+                        Some(value.parse()?)
+                    } else {
+                        None
+                    };
+                    constraints.checks.push(ConstraintCheck { expr, error });
+                    Ok(())
+                } else if meta.path.is_ident("has_one") {
+                    constraints.has_one.push(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("close") {
+                    constraints.close = Some(meta.value()?.parse()?);
+                    Ok(())
                 } else {
                     Err(meta.error("unknown account constraint"))
                 }
             })?;
+
+            if constraints.init && constraints.owner.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`owner` cannot be combined with `init`: a new account has no owner yet",
+                ));
+            }
+            if constraints.init_if_needed && constraints.owner.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`owner` cannot be combined with `init_if_needed`: a new account has no owner yet",
+                ));
+            }
+            if constraints.init && constraints.init_if_needed {
+                return Err(syn::Error::new_spanned(attr, "use either `init` or `init_if_needed`"));
+            }
+            if constraints.space.is_some() && constraints.max_space.is_some() {
+                return Err(syn::Error::new_spanned(attr, "use either `space` or `max_space`"));
+            }
+            if let (Some(min), Some(max)) = (constraints.min, constraints.max) {
+                if min > max {
+                    return Err(syn::Error::new_spanned(attr, "`min` cannot be greater than `max`"));
+                }
+            }
+            if constraints.init && !constraints.has_one.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`has_one` cannot be combined with `init`: a new account has no data yet",
+                ));
+            }
+            if constraints.close.is_some() && !constraints.mutable {
+                return Err(syn::Error::new_spanned(attr, "`close` requires `mut`"));
+            }
         }
     }
 
@@ -459,6 +1312,27 @@ fn parse_pda_expr(expr: &syn::Expr) -> syn::Result<Vec<PdaSeedDef>> {
     }
 }
 
+/// Parse `pda_program = arg("name")` or `pda_program = const(EXPR)`.
+/// `const` is a keyword, so its parentheses are parsed by hand.
+fn parse_pda_program(input: syn::parse::ParseStream) -> syn::Result<PdaProgramDef> {
+    if input.peek(syn::Token![const]) {
+        input.parse::<syn::Token![const]>()?;
+        let content;
+        syn::parenthesized!(content in input);
+        return Ok(PdaProgramDef::Const(content.parse()?));
+    }
+    let call: syn::ExprCall = input.parse()?;
+    if matches!(&*call.func, syn::Expr::Path(p) if p.path.is_ident("arg")) && call.args.len() == 1 {
+        if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(s), .. }) = &call.args[0] {
+            return Ok(PdaProgramDef::Arg(s.clone()));
+        }
+    }
+    Err(syn::Error::new_spanned(
+        call,
+        "`pda_program` must be arg(\"...\") or const(<ProgramId expression>)",
+    ))
+}
+
 fn parse_single_pda_seed(call: &syn::ExprCall) -> syn::Result<PdaSeedDef> {
     let func_name = if let syn::Expr::Path(path) = &*call.func {
         path.path
@@ -479,7 +1353,7 @@ fn parse_single_pda_seed(call: &syn::ExprCall) -> syn::Result<PdaSeedDef> {
     let arg = &call.args[0];
     let string_val = if let syn::Expr::Lit(lit) = arg {
         if let syn::Lit::Str(s) = &lit.lit {
-            s.value()
+            s.clone()
         } else {
             return Err(syn::Error::new_spanned(arg, "Expected string literal"));
         }
@@ -488,7 +1362,16 @@ fn parse_single_pda_seed(call: &syn::ExprCall) -> syn::Result<PdaSeedDef> {
     };
 
     match func_name.as_str() {
-        "const" | "r#const" | "seed_const" | "literal" => Ok(PdaSeedDef::Const(string_val)),
+        "const" | "r#const" | "seed_const" | "literal" => {
+            let len = string_val.value().len();
+            if len > 32 {
+                return Err(syn::Error::new_spanned(
+                    &string_val,
+                    format!("const PDA seed is {} bytes, at most 32 are allowed", len),
+                ));
+            }
+            Ok(PdaSeedDef::Const(string_val))
+        }
         "account" => Ok(PdaSeedDef::Account(string_val)),
         "arg" => Ok(PdaSeedDef::Arg(string_val)),
         _ => Err(syn::Error::new_spanned(
@@ -501,6 +1384,553 @@ fn parse_single_pda_seed(call: &syn::ExprCall) -> syn::Result<PdaSeedDef> {
     }
 }
 
+/// Every `account("...")` seed must name a fixed account of the instruction
+/// and every `arg("...")` seed one of its arguments. A seed account is
+/// declared before the PDA unless it is itself a PDA, and PDAs must not
+/// depend on each other in a cycle.
+fn check_pda_seeds(accounts: &[AccountParam], args: &[ArgParam]) -> syn::Result<()> {
+    for (i, acc) in accounts.iter().enumerate() {
+        match &acc.constraints.pda_program {
+            Some(_) if acc.constraints.pda_seeds.is_empty() => {
+                return Err(syn::Error::new_spanned(&acc.name, "`pda_program` requires `pda = ...` seeds"));
+            }
+            Some(PdaProgramDef::Arg(path)) if !args.iter().any(|a| a.name == path.value()) => {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    format!("`pda_program` references unknown argument `{}`", path.value()),
+                ));
+            }
+            _ => {}
+        }
+        for seed in &acc.constraints.pda_seeds {
+            match seed {
+                PdaSeedDef::Const(_) => {}
+                PdaSeedDef::Arg(path) => {
+                    if !args.iter().any(|a| a.name == path.value()) {
+                        return Err(syn::Error::new_spanned(
+                            path,
+                            format!("PDA seed references unknown argument `{}`", path.value()),
+                        ));
+                    }
+                }
+                PdaSeedDef::Account(path) => {
+                    let Some(j) = accounts.iter().position(|a| a.name == path.value()) else {
+                        return Err(syn::Error::new_spanned(
+                            path,
+                            format!("PDA seed references unknown account `{}`", path.value()),
+                        ));
+                    };
+                    let seed_acc = &accounts[j];
+                    if seed_acc.is_rest {
+                        return Err(syn::Error::new_spanned(
+                            path,
+                            format!("PDA seed cannot reference rest account `{}`", path.value()),
+                        ));
+                    }
+                    if j > i && seed_acc.constraints.pda_seeds.is_empty() {
+                        return Err(syn::Error::new_spanned(
+                            path,
+                            format!(
+                                "PDA seed references `{}`, which comes after `{}`; declare it first",
+                                path.value(), acc.name
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    // Follow `account` seeds between PDA accounts; reaching the start
+    // account again is a cycle, reported at the seed that leads into it
+    for (start, acc) in accounts.iter().enumerate() {
+        let mut stack: Vec<(usize, Vec<String>, Option<&syn::LitStr>)> =
+            vec![(start, vec![acc.name.to_string()], None)];
+        let mut visited = vec![false; accounts.len()];
+        while let Some((i, chain, origin)) = stack.pop() {
+            for seed in &accounts[i].constraints.pda_seeds {
+                let PdaSeedDef::Account(path) = seed else { continue };
+                let Some(j) = accounts.iter().position(|a| a.name == path.value()) else { continue };
+                if accounts[j].constraints.pda_seeds.is_empty() {
+                    continue;
+                }
+                let origin = origin.unwrap_or(path);
+                let mut next = chain.clone();
+                next.push(path.value());
+                if j == start {
+                    return Err(syn::Error::new_spanned(
+                        origin,
+                        format!("cyclic PDA seeds: {}", next.join(" → ")),
+                    ));
+                }
+                if !visited[j] {
+                    visited[j] = true;
+                    stack.push((j, next, Some(origin)));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// ─── IDL type parsing (#[lez_account] / #[lez_type]) ─────────────────────
+
+fn is_idl_type_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("lez_account") || attr.path().is_ident("lez_type")
+}
+
+/// Parse a struct or enum marked `#[lez_account]` or `#[lez_type]`.
+/// Returns `None` for unmarked items.
+fn parse_idl_type(item: &syn::Item) -> syn::Result<Option<IdlTypeInfo>> {
+    let (attrs, ident, generics) = match item {
+        syn::Item::Struct(s) => (&s.attrs, &s.ident, &s.generics),
+        syn::Item::Enum(e) => (&e.attrs, &e.ident, &e.generics),
+        _ => return Ok(None),
+    };
+    let Some(marker) = attrs.iter().find(|a| is_idl_type_attr(a)) else {
+        return Ok(None);
+    };
+    if !generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            generics,
+            "generic types cannot be described in the IDL",
+        ));
+    }
+
+    let kind = match item {
+        syn::Item::Struct(s) => IdlTypeKind::Struct(idl_fields(&s.fields)),
+        syn::Item::Enum(e) => IdlTypeKind::Enum(
+            e.variants
+                .iter()
+                .map(|v| (v.ident.to_string(), idl_fields(&v.fields)))
+                .collect(),
+        ),
+        _ => unreachable!(),
+    };
+
+    Ok(Some(IdlTypeInfo {
+        name: ident.to_string(),
+        is_account: marker.path().is_ident("lez_account"),
+        kind,
+    }))
+}
+
+fn idl_fields(fields: &syn::Fields) -> Vec<(String, Type)> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let name = f.ident.as_ref().map(|id| id.to_string()).unwrap_or_else(|| i.to_string());
+            (name, f.ty.clone())
+        })
+        .collect()
+}
+
+/// Remove the marker attributes so they need not be in scope inside the module.
+fn strip_idl_type_attrs(mut item: syn::Item) -> syn::Item {
+    match &mut item {
+        syn::Item::Struct(s) => s.attrs.retain(|a| !is_idl_type_attr(a)),
+        syn::Item::Enum(e) => e.attrs.retain(|a| !is_idl_type_attr(a)),
+        _ => {}
+    }
+    item
+}
+
+/// Collect marked items from a parsed file, descending into inline modules.
+fn collect_idl_items(items: &[syn::Item], out: &mut IdlItems) -> syn::Result<()> {
+    for item in items {
+        match item {
+            syn::Item::Mod(m) => {
+                if let Some((_, inner)) = &m.content {
+                    collect_idl_items(inner, out)?;
+                }
+            }
+            syn::Item::Enum(e) if has_lez_error_attr(&e.attrs) => {
+                out.errors.extend(parse_lez_error(e)?);
+            }
+            _ => {
+                out.collect_const(item);
+                if let Some(info) = parse_idl_type(item)? {
+                    out.types.push(info);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read a `types_from` source file and collect its IDL types and errors.
+fn load_idl_items_from(
+    path: &std::path::Path,
+    span: &impl quote::ToTokens,
+) -> syn::Result<IdlItems> {
+    let file = read_source_file(path, span)?;
+    let mut items = IdlItems::default();
+    collect_idl_items(&file.items, &mut items)?;
+    Ok(items)
+}
+
+fn read_source_file(path: &std::path::Path, span: &impl quote::ToTokens) -> syn::Result<syn::File> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        syn::Error::new_spanned(span, format!("Failed to read '{}': {}", path.display(), e))
+    })?;
+    syn::parse_file(&content).map_err(|e| {
+        syn::Error::new_spanned(span, format!("Failed to parse '{}': {}", path.display(), e))
+    })
+}
+
+// ─── Instruction groups ──────────────────────────────────────────────────
+
+/// Everything collected from a program module and its instruction groups.
+#[derive(Default)]
+struct ProgramItems {
+    /// `#[instruction]` functions and their groups, parsed once all
+    /// account sets are known
+    instruction_fns: Vec<(ItemFn, Vec<Ident>)>,
+    account_sets: Vec<AccountSet>,
+    idl_items: IdlItems,
+    /// Group source files read along the way
+    group_files: Vec<std::path::PathBuf>,
+}
+
+/// One `mod name;` inside `instruction_groups! { ... }`.
+struct GroupDecl {
+    attrs: Vec<Attribute>,
+    vis: syn::Visibility,
+    ident: Ident,
+}
+
+struct GroupDecls(Vec<GroupDecl>);
+
+impl syn::parse::Parse for GroupDecls {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut decls = Vec::new();
+        while !input.is_empty() {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let vis = input.parse()?;
+            input.parse::<syn::Token![mod]>()?;
+            let ident = input.parse()?;
+            input.parse::<syn::Token![;]>()?;
+            decls.push(GroupDecl { attrs, vis, ident });
+        }
+        Ok(GroupDecls(decls))
+    }
+}
+
+impl ProgramItems {
+    fn parse_instructions(&self) -> syn::Result<Vec<InstructionInfo>> {
+        self.instruction_fns
+            .iter()
+            .map(|(func, group)| {
+                let mut info = parse_instruction(func.clone(), &self.account_sets)?;
+                info.group = group.clone();
+                Ok(info)
+            })
+            .collect()
+    }
+}
+
+fn has_instruction_group_attr(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|a| a.path().is_ident("instruction_group"))
+}
+
+fn is_instruction_groups_macro(item: &syn::ItemMacro) -> bool {
+    item.mac.path.segments.last().is_some_and(|s| s.ident == "instruction_groups")
+}
+
+/// Walk a program module (or group) body: collect instructions and IDL items
+/// into `out` and return the items to emit in its place. `dirs` are where
+/// `mod name;` declared in this body would be looked up, and `group` is the
+/// module path of this body below the program module.
+fn collect_program_items(
+    items: &[syn::Item],
+    dirs: &[std::path::PathBuf],
+    group: &[Ident],
+    out: &mut ProgramItems,
+) -> syn::Result<Vec<TokenStream2>> {
+    let mut emitted = Vec::new();
+    for item in items {
+        match item {
+            syn::Item::Fn(func) if has_instruction_attr(&func.attrs) => {
+                let mut handler = func.clone();
+                strip_instruction_attrs(&mut handler);
+                emitted.push(quote! { #handler });
+                out.instruction_fns.push((func.clone(), group.to_vec()));
+            }
+            syn::Item::Struct(st) if has_lez_accounts_derive(&st.attrs) => {
+                out.account_sets.push(parse_account_set(&st.ident, &st.generics, &st.fields, group)?);
+                emitted.push(quote! { #st });
+            }
+            syn::Item::Enum(e) if has_lez_error_attr(&e.attrs) => {
+                out.idl_items.errors.extend(parse_lez_error(e)?);
+                emitted.push(expand_lez_error(e.clone())?);
+            }
+            syn::Item::Mod(m) if has_instruction_group_attr(&m.attrs) => {
+                let inner_dirs: Vec<std::path::PathBuf> =
+                    dirs.iter().map(|dir| dir.join(m.ident.to_string())).collect();
+                let mut path = group.to_vec();
+                path.push(m.ident.clone());
+                emitted.push(expand_instruction_group(m, &inner_dirs, &path, out)?);
+            }
+            syn::Item::Macro(m) if is_instruction_groups_macro(m) => {
+                let decls: GroupDecls = m.mac.parse_body()?;
+                for decl in &decls.0 {
+                    emitted.push(expand_group_file(decl, dirs, group, out)?);
+                }
+            }
+            other => {
+                out.idl_items.collect_const(other);
+                if let Some(info) = parse_idl_type(other)? {
+                    out.idl_items.types.push(info);
+                    let stripped = strip_idl_type_attrs(other.clone());
+                    emitted.push(quote! { #stripped });
+                } else {
+                    emitted.push(quote! { #other });
+                }
+            }
+        }
+    }
+    Ok(emitted)
+}
+
+/// An inline `#[instruction_group] mod name { ... }`, with its body collected.
+fn expand_instruction_group(
+    m: &ItemMod,
+    dirs: &[std::path::PathBuf],
+    group: &[Ident],
+    out: &mut ProgramItems,
+) -> syn::Result<TokenStream2> {
+    let (_, items) = m.content.as_ref().ok_or_else(|| {
+        syn::Error::new_spanned(
+            m,
+            "instruction group must have a body; declare groups kept in their own files with `instruction_groups! { mod name; }`",
+        )
+    })?;
+    let body = collect_program_items(items, dirs, group, out)?;
+    let (inner_attrs, outer_attrs): (Vec<&Attribute>, Vec<&Attribute>) = m
+        .attrs
+        .iter()
+        .filter(|a| !a.path().is_ident("instruction_group"))
+        .partition(|a| matches!(a.style, syn::AttrStyle::Inner(_)));
+    let vis = group_visibility(&m.vis);
+    let ident = &m.ident;
+    Ok(quote! {
+        #(#outer_attrs)*
+        #vis mod #ident {
+            #(#inner_attrs)*
+            #(#body)*
+        }
+    })
+}
+
+/// A `mod name;` from `instruction_groups!`: collect the file it names and
+/// have rustc load that same file.
+fn expand_group_file(
+    decl: &GroupDecl,
+    dirs: &[std::path::PathBuf],
+    group: &[Ident],
+    out: &mut ProgramItems,
+) -> syn::Result<TokenStream2> {
+    let path = find_group_file(&decl.ident, dirs)?;
+    let file = read_source_file(&path, &decl.ident)?;
+    let mut inner_group = group.to_vec();
+    inner_group.push(decl.ident.clone());
+    collect_program_items(&file.items, &module_dirs_of_file(&path), &inner_group, out)?;
+
+    let attrs = &decl.attrs;
+    let vis = group_visibility(&decl.vis);
+    let ident = &decl.ident;
+    let path_str = path.to_string_lossy().to_string();
+    out.group_files.push(path);
+    Ok(quote! {
+        #(#attrs)*
+        #[path = #path_str]
+        #vis mod #ident;
+    })
+}
+
+/// Groups are at least `pub(crate)`, since `main()` calls their handlers by path.
+fn group_visibility(vis: &syn::Visibility) -> TokenStream2 {
+    match vis {
+        syn::Visibility::Inherited => quote! { pub(crate) },
+        other => quote! { #other },
+    }
+}
+
+/// Find `name.rs` or `name/mod.rs` in the first directory that has one.
+fn find_group_file(ident: &Ident, dirs: &[std::path::PathBuf]) -> syn::Result<std::path::PathBuf> {
+    let candidates: Vec<std::path::PathBuf> = dirs
+        .iter()
+        .flat_map(|dir| [dir.join(format!("{}.rs", ident)), dir.join(ident.to_string()).join("mod.rs")])
+        .collect();
+    candidates.iter().find(|path| path.is_file()).cloned().ok_or_else(|| {
+        let tried: Vec<String> = candidates.iter().map(|p| format!("`{}`", p.display())).collect();
+        let message = if tried.is_empty() {
+            format!("cannot locate the source file declaring instruction group `{}`", ident)
+        } else {
+            format!("file not found for instruction group `{}`, tried {}", ident, tried.join(", "))
+        };
+        syn::Error::new_spanned(ident, message)
+    })
+}
+
+/// Directories `mod name;` declared at the top of `file` is looked up in,
+/// following rustc: next to `main.rs`/`lib.rs`/`mod.rs`, else in a directory
+/// named after the file. Other crate roots such as `src/bin/<name>.rs` can't
+/// be told apart from a plain module file, so both places are tried there.
+fn module_dirs_of_file(file: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let parent = file.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    match file.file_stem().and_then(|s| s.to_str()) {
+        Some("main") | Some("lib") | Some("mod") | None => vec![parent],
+        Some(stem) => vec![parent.join(stem), parent],
+    }
+}
+
+/// [`module_dirs_of_file`] for the file the macro is invoked in, if known.
+/// `Span::local_file` is why the crate's `rust-version` is 1.88.
+fn call_site_module_dirs() -> Vec<std::path::PathBuf> {
+    let Some(file) = proc_macro::Span::call_site().local_file() else {
+        return Vec::new();
+    };
+    let file = std::env::current_dir().map(|cwd| cwd.join(&file)).unwrap_or(file);
+    module_dirs_of_file(&file)
+}
+
+/// Every instruction becomes an `Instruction` variant, so names must be
+/// unique across the program module and its groups.
+fn check_instruction_names(instructions: &[InstructionInfo]) -> syn::Result<()> {
+    for (i, ix) in instructions.iter().enumerate() {
+        if let Some(other) = instructions[..i].iter().find(|o| o.fn_name == ix.fn_name) {
+            let location = |ix: &InstructionInfo| {
+                if ix.group.is_empty() {
+                    "the program module".to_string()
+                } else {
+                    let path: Vec<String> = ix.group.iter().map(|g| g.to_string()).collect();
+                    format!("group `{}`", path.join("::"))
+                }
+            };
+            return Err(syn::Error::new_spanned(
+                &ix.fn_name,
+                format!(
+                    "instruction `{}` is already declared in {}",
+                    ix.fn_name,
+                    location(other)
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ─── #[lez_error] ────────────────────────────────────────────────────────
+
+fn has_lez_error_attr(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|a| a.path().is_ident("lez_error"))
+}
+
+/// Largest program-local error code, so that `6000 + code` fits in a `u32`.
+const MAX_ERROR_CODE: u32 = u32::MAX - 6000;
+
+/// Assign codes to the variants of a `#[lez_error]` enum.
+///
+/// Codes follow Rust discriminant rules: an explicit `= N` sets the code,
+/// otherwise it is one more than the previous variant's (starting at 0),
+/// and may be at most `MAX_ERROR_CODE`.
+/// Messages come from `#[msg("...")]`.
+fn parse_lez_error(item: &syn::ItemEnum) -> syn::Result<Vec<IdlErrorInfo>> {
+    let mut errors: Vec<IdlErrorInfo> = Vec::new();
+    let mut next_code: u32 = 0;
+    for variant in &item.variants {
+        if !matches!(variant.fields, syn::Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "#[lez_error] variants cannot have fields",
+            ));
+        }
+        let code = match &variant.discriminant {
+            Some((_, syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(n), .. }))) => n.base10_parse::<u32>()?,
+            Some((_, expr)) => {
+                return Err(syn::Error::new_spanned(expr, "error code must be an integer literal"));
+            }
+            None => next_code,
+        };
+        if code > MAX_ERROR_CODE {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("error code {} is above {}: `6000 + code` must fit in a u32", code, MAX_ERROR_CODE),
+            ));
+        }
+        if let Some(dup) = errors.iter().find(|e| e.code == code) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("error code {} is already used by `{}`", code, dup.name),
+            ));
+        }
+        let mut msg = None;
+        for attr in variant.attrs.iter().filter(|a| a.path().is_ident("msg")) {
+            msg = Some(attr.parse_args::<syn::LitStr>()?.value());
+        }
+        errors.push(IdlErrorInfo {
+            name: variant.ident.to_string(),
+            code,
+            msg,
+        });
+        next_code = code.checked_add(1).ok_or_else(|| {
+            syn::Error::new_spanned(variant, "error code overflows u32")
+        })?;
+    }
+    Ok(errors)
+}
+
+/// Expand a `#[lez_error]` enum: strip the helper attributes and implement
+/// `code()`, `msg()`, `Display` and `From<_> for LezError`.
+fn expand_lez_error(mut item: syn::ItemEnum) -> syn::Result<TokenStream2> {
+    let errors = parse_lez_error(&item)?;
+    item.attrs.retain(|a| !a.path().is_ident("lez_error"));
+    for variant in &mut item.variants {
+        variant.attrs.retain(|a| !a.path().is_ident("msg"));
+    }
+
+    let name = &item.ident;
+    let variant_idents: Vec<&Ident> = item.variants.iter().map(|v| &v.ident).collect();
+    let codes: Vec<u32> = errors.iter().map(|e| e.code).collect();
+    let msgs: Vec<String> = errors
+        .iter()
+        .map(|e| e.msg.clone().unwrap_or_else(|| e.name.clone()))
+        .collect();
+
+    Ok(quote! {
+        #item
+
+        impl #name {
+            /// Program-local error code, reported as `6000 + code` by `LezError::error_code()`.
+            pub fn code(&self) -> u32 {
+                match self {
+                    #(Self::#variant_idents => #codes,)*
+                }
+            }
+
+            pub fn msg(&self) -> &'static str {
+                match self {
+                    #(Self::#variant_idents => #msgs,)*
+                }
+            }
+        }
+
+        impl ::core::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.msg())
+            }
+        }
+
+        impl ::core::convert::From<#name> for lez_framework::error::LezError {
+            fn from(err: #name) -> Self {
+                lez_framework::error::LezError::custom(err.code(), err.msg())
+            }
+        }
+    })
+}
+
 // ─── Code generation helpers ─────────────────────────────────────────────
 
 fn generate_enum_variants(instructions: &[InstructionInfo]) -> Vec<TokenStream2> {
@@ -527,12 +1957,234 @@ fn generate_enum_variants(instructions: &[InstructionInfo]) -> Vec<TokenStream2>
         .collect()
 }
 
-fn generate_match_arms(mod_name: &Ident, instructions: &[InstructionInfo]) -> Vec<TokenStream2> {
+/// Serde impls for an `Instruction` enum with explicit indices. The wire format
+/// matches the derived one, except that the variant index is `ix.index`.
+fn generate_instruction_serde(instructions: &[InstructionInfo]) -> TokenStream2 {
+    let variant_names: Vec<Ident> = instructions.iter().map(|ix| to_pascal_case(&ix.fn_name)).collect();
+    let variant_strs: Vec<String> = variant_names.iter().map(|v| v.to_string()).collect();
+    let indices: Vec<u32> = instructions.iter().map(|ix| ix.index).collect();
+
+    let serialize_arms: Vec<TokenStream2> = instructions
+        .iter()
+        .zip(&variant_names)
+        .map(|(ix, variant_name)| {
+            let variant_str = variant_name.to_string();
+            let index = ix.index;
+            let field_names: Vec<&Ident> = ix.args.iter().map(|a| &a.name).collect();
+            let field_strs: Vec<String> = field_names.iter().map(|f| f.to_string()).collect();
+            let num_fields = field_names.len();
+            if field_names.is_empty() {
+                quote! {
+                    Instruction::#variant_name => {
+                        serializer.serialize_unit_variant("Instruction", #index, #variant_str)
+                    }
+                }
+            } else {
+                quote! {
+                    Instruction::#variant_name { #(#field_names),* } => {
+                        let mut state = serializer.serialize_struct_variant(
+                            "Instruction", #index, #variant_str, #num_fields,
+                        )?;
+                        #(state.serialize_field(#field_strs, #field_names)?;)*
+                        state.end()
+                    }
+                }
+            }
+        })
+        .collect();
+
+    // Struct variants are read as a newtype around a derived struct with the
+    // same fields, which has the same encoding in binary formats and in JSON.
+    let deserialize_arms: Vec<TokenStream2> = instructions
+        .iter()
+        .zip(&variant_names)
+        .map(|(ix, variant_name)| {
+            let index = ix.index;
+            let field_names: Vec<&Ident> = ix.args.iter().map(|a| &a.name).collect();
+            if field_names.is_empty() {
+                quote! {
+                    #index => {
+                        variant.unit_variant()?;
+                        Ok(Instruction::#variant_name)
+                    }
+                }
+            } else {
+                let fields_struct = format_ident!("__{}Fields", variant_name);
+                let fields: Vec<TokenStream2> = ix.args.iter().map(|a| {
+                    let name = &a.name;
+                    let ty = &a.ty;
+                    quote! { #name: #ty }
+                }).collect();
+                quote! {
+                    #index => {
+                        #[derive(serde::Deserialize)]
+                        struct #fields_struct { #(#fields),* }
+                        let #fields_struct { #(#field_names),* } = variant.newtype_variant()?;
+                        Ok(Instruction::#variant_name { #(#field_names),* })
+                    }
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        impl serde::Serialize for Instruction {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                #[allow(unused_imports)]
+                use serde::ser::SerializeStructVariant;
+                match self {
+                    #(#serialize_arms)*
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for Instruction {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                const VARIANTS: &[&str] = &[#(#variant_strs),*];
+                const TAG: lez_framework::dispatch::VariantTag = lez_framework::dispatch::VariantTag {
+                    enum_name: "Instruction",
+                    variants: &[#((#indices, #variant_strs)),*],
+                };
+
+                struct InstructionVisitor;
+                impl<'de> serde::de::Visitor<'de> for InstructionVisitor {
+                    type Value = Instruction;
+
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.write_str("enum Instruction")
+                    }
+
+                    fn visit_enum<A: serde::de::EnumAccess<'de>>(self, data: A) -> Result<Instruction, A::Error> {
+                        use serde::de::VariantAccess;
+                        let (index, variant) = data.variant_seed(TAG)?;
+                        match index {
+                            #(#deserialize_arms)*
+                            _ => unreachable!("VariantTag only yields known indices"),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_enum("Instruction", VARIANTS, InstructionVisitor)
+            }
+        }
+    }
+}
+
+/// The discriminator as the little-endian `u64` it is serialized as.
+fn discriminator_u64(ix: &InstructionInfo) -> u64 {
+    let disc = idl::compute_discriminator(&ix.fn_name.to_string());
+    u64::from_le_bytes(disc[..8].try_into().unwrap())
+}
+
+fn check_discriminators(instructions: &[InstructionInfo]) -> syn::Result<()> {
+    for (i, ix) in instructions.iter().enumerate() {
+        if let Some(other) = instructions[..i].iter().find(|o| discriminator_u64(o) == discriminator_u64(ix)) {
+            return Err(syn::Error::new_spanned(
+                &ix.fn_name,
+                format!("discriminator of `{}` collides with `{}`", ix.fn_name, other.fn_name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Serde impls for `dispatch = "discriminator"`. An instruction is a tuple of
+/// the discriminator (a `u64`, so the first 8 bytes of the data are the
+/// discriminator bytes in order) followed by the arguments.
+fn generate_discriminator_serde(instructions: &[InstructionInfo]) -> TokenStream2 {
+    let max_len = 1 + instructions.iter().map(|ix| ix.args.len()).max().unwrap_or(0);
+
+    let serialize_arms: Vec<TokenStream2> = instructions
+        .iter()
+        .map(|ix| {
+            let variant_name = to_pascal_case(&ix.fn_name);
+            let disc = discriminator_u64(ix);
+            let field_names: Vec<&Ident> = ix.args.iter().map(|a| &a.name).collect();
+            let len = 1 + field_names.len();
+            quote! {
+                Instruction::#variant_name { #(#field_names),* } => {
+                    let mut tuple = serializer.serialize_tuple(#len)?;
+                    tuple.serialize_element(&#disc)?;
+                    #(tuple.serialize_element(#field_names)?;)*
+                    tuple.end()
+                }
+            }
+        })
+        .collect();
+
+    let deserialize_arms: Vec<TokenStream2> = instructions
+        .iter()
+        .map(|ix| {
+            let variant_name = to_pascal_case(&ix.fn_name);
+            let disc = discriminator_u64(ix);
+            let field_names: Vec<&Ident> = ix.args.iter().map(|a| &a.name).collect();
+            let positions: Vec<usize> = (1..=field_names.len()).collect();
+            quote! {
+                #disc => {
+                    #(
+                        let #field_names = seq
+                            .next_element()?
+                            .ok_or_else(|| serde::de::Error::invalid_length(#positions, &self))?;
+                    )*
+                    Ok(Instruction::#variant_name { #(#field_names),* })
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        impl serde::Serialize for Instruction {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeTuple;
+                match self {
+                    #(#serialize_arms)*
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for Instruction {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct InstructionVisitor;
+                impl<'de> serde::de::Visitor<'de> for InstructionVisitor {
+                    type Value = Instruction;
+
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.write_str("an instruction discriminator followed by its arguments")
+                    }
+
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Instruction, A::Error> {
+                        let discriminator: u64 = seq
+                            .next_element()?
+                            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                        match discriminator {
+                            #(#deserialize_arms)*
+                            other => Err(serde::de::Error::invalid_value(
+                                serde::de::Unexpected::Unsigned(other),
+                                &self,
+                            )),
+                        }
+                    }
+                }
+
+                // The longest variant bounds the tuple; shorter ones stop early
+                deserializer.deserialize_tuple(#max_len, InstructionVisitor)
+            }
+        }
+    }
+}
+
+fn generate_match_arms(
+    mod_name: &Ident,
+    instructions: &[InstructionInfo],
+    program_id: Option<&syn::Expr>,
+) -> Vec<TokenStream2> {
     instructions
         .iter()
         .map(|ix| {
             let variant_name = to_pascal_case(&ix.fn_name);
             let fn_name = &ix.fn_name;
+            let group = &ix.group;
+            let ix_name = ix.fn_name.to_string();
             let num_accounts = ix.accounts.len();
 
             let field_names: Vec<&Ident> = ix.args.iter().map(|a| &a.name).collect();
@@ -553,16 +2205,22 @@ fn generate_match_arms(mod_name: &Ident, instructions: &[InstructionInfo]) -> Ve
                 
                 quote! {
                     if pre_states.len() < #num_fixed {
-                        panic!(
-                            "Account count mismatch: expected at least {}, got {}",
-                            #num_fixed, pre_states.len()
+                        lez_framework::failure::abort(
+                            #ix_name,
+                            lez_framework::error::LezError::AccountCountMismatch {
+                                expected: #num_fixed,
+                                actual: pre_states.len(),
+                            },
                         );
                     }
                     let (fixed_accounts, rest_accounts) = pre_states.split_at(#num_fixed);
                     let [#(#fixed_names),*] = <[_; #num_fixed]>::try_from(fixed_accounts.to_vec())
-                        .unwrap_or_else(|v: Vec<_>| panic!(
-                            "Account count mismatch: expected {}, got {}",
-                            #num_fixed, v.len()
+                        .unwrap_or_else(|v: Vec<_>| lez_framework::failure::abort(
+                            #ix_name,
+                            lez_framework::error::LezError::AccountCountMismatch {
+                                expected: #num_fixed,
+                                actual: v.len(),
+                            },
                         ));
                     let #rest_name: Vec<nssa_core::account::AccountWithMetadata> = rest_accounts.to_vec();
                 }
@@ -570,23 +2228,39 @@ fn generate_match_arms(mod_name: &Ident, instructions: &[InstructionInfo]) -> Ve
                 let account_names: Vec<&Ident> = ix.accounts.iter().map(|a| &a.name).collect();
                 quote! {
                     let [#(#account_names),*] = <[_; #num_accounts]>::try_from(pre_states)
-                        .unwrap_or_else(|v: Vec<_>| panic!(
-                            "Account count mismatch: expected {}, got {}",
-                            #num_accounts, v.len()
+                        .unwrap_or_else(|v: Vec<_>| lez_framework::failure::abort(
+                            #ix_name,
+                            lez_framework::error::LezError::AccountCountMismatch {
+                                expected: #num_accounts,
+                                actual: v.len(),
+                            },
                         ));
                 }
             };
 
-            // Check if this instruction has any validation (signer/init checks)
-            let has_validation = ix.accounts.iter().any(|a| a.constraints.signer || a.constraints.init);
+            // Check if this instruction has any validation (signer/init/owner checks)
+            let has_validation = needs_validation(ix);
             let validate_fn_name = format_ident!("__validate_{}", ix.fn_name);
 
+            // Fields of a `#[derive(LezAccounts)]` parameter are put back together
             let call_args: Vec<TokenStream2> = ix
                 .accounts
                 .iter()
-                .map(|a| {
-                    let name = &a.name;
-                    quote! { #name }
+                .enumerate()
+                .filter_map(|(i, a)| match &a.context {
+                    None => {
+                        let name = &a.name;
+                        Some(quote! { #name })
+                    }
+                    Some(ctx) if i > 0 && ix.accounts[i - 1].context.as_ref().map(|c| &c.param) == Some(&ctx.param) => None,
+                    Some(ctx) => {
+                        let path = &ctx.path;
+                        let fields = ix.accounts[i..]
+                            .iter()
+                            .take_while(|f| f.context.as_ref().map(|c| &c.param) == Some(&ctx.param))
+                            .map(|f| &f.name);
+                        Some(quote! { #mod_name #(::#path)* { #(#fields),* } })
+                    }
                 })
                 .chain(ix.args.iter().map(|a| {
                     let name = &a.name;
@@ -594,6 +2268,9 @@ fn generate_match_arms(mod_name: &Ident, instructions: &[InstructionInfo]) -> Ve
                 }))
                 .collect();
 
+            let validation_args: Vec<TokenStream2> = ix.args.iter()
+                .map(|a| { let name = &a.name; quote! { &#name } })
+                .collect();
             let validation_call = if has_validation {
                 if has_rest {
                     // For instructions with Vec accounts, build the slice dynamically
@@ -605,7 +2282,8 @@ fn generate_match_arms(mod_name: &Ident, instructions: &[InstructionInfo]) -> Ve
                     quote! {
                         let mut __all_accounts = vec![#(#fixed_refs),*];
                         __all_accounts.extend(#rest_ref.clone());
-                        #mod_name::#validate_fn_name(&__all_accounts).expect("account validation failed");
+                        #mod_name::#validate_fn_name(&__all_accounts, #(#validation_args),*)
+                            .unwrap_or_else(|e| lez_framework::failure::abort(#ix_name, e));
                     }
                 } else {
                     let account_refs: Vec<TokenStream2> = ix
@@ -617,39 +2295,189 @@ fn generate_match_arms(mod_name: &Ident, instructions: &[InstructionInfo]) -> Ve
                         })
                         .collect();
                     quote! {
-                        #mod_name::#validate_fn_name(&[#(#account_refs.clone()),*]).expect("account validation failed");
+                        #mod_name::#validate_fn_name(&[#(#account_refs.clone()),*], #(#validation_args),*)
+                            .unwrap_or_else(|e| lez_framework::failure::abort(#ix_name, e));
                     }
                 }
             } else {
                 quote! {}
             };
 
+            let pda_validation_call = if needs_pda_validation(ix) {
+                let program_id = match program_id {
+                    Some(expr) => quote! { Some(&(#expr)) },
+                    None => quote! { None },
+                };
+                let validate_pdas_fn_name = format_ident!("__validate_pdas_{}", ix.fn_name);
+                let account_refs: Vec<TokenStream2> = ix.accounts.iter()
+                    .filter(|a| !a.is_rest)
+                    .map(|a| { let name = &a.name; quote! { #name.clone() } })
+                    .collect();
+                let arg_refs: Vec<TokenStream2> = ix.args.iter()
+                    .map(|a| { let name = &a.name; quote! { &#name } })
+                    .collect();
+                quote! {
+                    #mod_name::#validate_pdas_fn_name(
+                        &[#(#account_refs),*],
+                        #program_id,
+                        #(#arg_refs),*
+                    ).unwrap_or_else(|e| lez_framework::failure::abort(#ix_name, e));
+                }
+            } else {
+                quote! {}
+            };
+
+            // Accounts without `mut` must come back from the handler unchanged
+            let num_fixed = ix.accounts.iter().filter(|a| !a.is_rest).count();
+            let read_only_checks: Vec<TokenStream2> = ix.accounts.iter()
+                .enumerate()
+                .filter(|(_, a)| !a.constraints.mutable)
+                .map(|(idx, a)| {
+                    let acc_name = a.name.to_string();
+                    if a.is_rest {
+                        quote! {
+                            for __idx in #num_fixed..pre_states_clone.len() {
+                                lez_framework::validation::verify_read_only(
+                                    &pre_states_clone, &post_states, __idx, #acc_name,
+                                )?;
+                            }
+                        }
+                    } else {
+                        quote! {
+                            lez_framework::validation::verify_read_only(
+                                &pre_states_clone, &post_states, #idx, #acc_name,
+                            )?;
+                        }
+                    }
+                })
+                .collect();
+            // `init_if_needed` accounts that were fresh are claimed, and
+            // `space` / `max_space` bound the data the handler left behind
+            let post_state_checks: Vec<TokenStream2> = ix.accounts.iter()
+                .enumerate()
+                .filter(|(_, a)| !a.is_rest)
+                .flat_map(|(idx, a)| {
+                    let claim = a.constraints.init_if_needed.then(|| quote! {
+                        lez_framework::validation::claim_if_new(&pre_states_clone, &mut post_states, #idx);
+                    });
+                    let bounds = match (a.constraints.space, a.constraints.max_space) {
+                        (Some(space), _) => Some((space, space)),
+                        (None, Some(max)) => Some((0, max)),
+                        (None, None) => None,
+                    };
+                    let size = bounds.map(|(min, max)| quote! {
+                        lez_framework::validation::verify_data_size(&post_states, #idx, #min, #max)?;
+                    });
+                    claim.into_iter().chain(size)
+                })
+                .collect();
+            let claims = ix.accounts.iter().any(|a| !a.is_rest && a.constraints.init_if_needed);
+            let post_states_binding = if claims { quote! { mut post_states } } else { quote! { post_states } };
+            let post_state_call = if post_state_checks.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    .and_then(|(#post_states_binding, chained_calls)| {
+                        #(#post_state_checks)*
+                        Ok((post_states, chained_calls))
+                    })
+                }
+            };
+
+            // `close = recipient` accounts hand their balance over once the
+            // handler is done
+            let close_calls: Vec<TokenStream2> = ix.accounts.iter()
+                .enumerate()
+                .filter_map(|(idx, a)| a.constraints.close.as_ref().map(|recipient| (idx, recipient)))
+                .map(|(idx, recipient)| {
+                    let recipient_idx = ix.accounts.iter().position(|a| a.name == *recipient)
+                        .expect("checked by check_account_references");
+                    quote! {
+                        lez_framework::validation::close_account(&mut post_states, #idx, #recipient_idx)?;
+                    }
+                })
+                .collect();
+            let close_call = if close_calls.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    .and_then(|(mut post_states, chained_calls)| {
+                        #(#close_calls)*
+                        Ok((post_states, chained_calls))
+                    })
+                }
+            };
+
+            let read_only_call = if read_only_checks.is_empty() {
+                quote! {}
+            } else {
+                quote! {
+                    .and_then(|(post_states, chained_calls)| {
+                        #(#read_only_checks)*
+                        Ok((post_states, chained_calls))
+                    })
+                }
+            };
+
+            // Decode `LezAccount<T>` parameters after validation has seen the raw
+            // accounts. `init` accounts start from `T::default()`; the rest are
+            // decoded in a chain so a failure surfaces as the arm's error.
+            let typed_inits: Vec<TokenStream2> = ix.accounts.iter()
+                .filter(|a| a.data_type.is_some() && a.constraints.init)
+                .map(|a| {
+                    let name = &a.name;
+                    quote! { let #name = lez_framework::account::LezAccount::init(#name); }
+                })
+                .collect();
+            let handler_call = ix.accounts.iter()
+                .enumerate()
+                .filter(|(_, a)| a.data_type.is_some() && !a.constraints.init)
+                .rev()
+                .fold(
+                    quote! {
+                        #mod_name::#(#group::)*#fn_name(#(#call_args),*)
+                    },
+                    |inner, (idx, a)| {
+                        let name = &a.name;
+                        let load = if a.constraints.init_if_needed {
+                            quote! { load_or_init }
+                        } else {
+                            quote! { load }
+                        };
+                        quote! {
+                            lez_framework::account::LezAccount::#load(#name, #idx)
+                                .and_then(|#name| #inner)
+                        }
+                    },
+                );
+
             quote! {
                 #pattern => {
                     #account_destructure
                     #validation_call
-                    #mod_name::#fn_name(#(#call_args),*)
+                    #pda_validation_call
+                    #(#typed_inits)*
+                    #handler_call
                         .map(|output| (output.post_states, output.chained_calls))
+                        #post_state_call
+                        #close_call
+                        #read_only_call
+                        .unwrap_or_else(|e| lez_framework::failure::abort(#ix_name, e))
                 }
             }
         })
         .collect()
 }
 
-fn generate_handler_fns(instructions: &[InstructionInfo]) -> Vec<TokenStream2> {
-    instructions
-        .iter()
-        .map(|ix| {
-            let mut func = ix.func.clone();
-            func.attrs.retain(|a| !a.path().is_ident("instruction"));
-            for input in &mut func.sig.inputs {
-                if let FnArg::Typed(pat_type) = input {
-                    pat_type.attrs.retain(|a| !a.path().is_ident("account"));
-                }
-            }
-            quote! { #func }
-        })
-        .collect()
+/// Turn an `#[instruction]` function into its plain handler.
+fn strip_instruction_attrs(func: &mut ItemFn) {
+    func.attrs.retain(|a| !a.path().is_ident("instruction"));
+    // Doc comments are not allowed on parameters; they only feed the IDL
+    for input in &mut func.sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            pat_type.attrs.retain(|a| !a.path().is_ident("account") && !a.path().is_ident("doc"));
+        }
+    }
 }
 
 fn generate_validation(instructions: &[InstructionInfo]) -> Vec<TokenStream2> {
@@ -657,385 +2485,481 @@ fn generate_validation(instructions: &[InstructionInfo]) -> Vec<TokenStream2> {
         .iter()
         .map(|ix| {
             let fn_name = format_ident!("__validate_{}", ix.fn_name);
-            
+            let num_fixed = ix.accounts.iter().filter(|a| !a.is_rest).count();
+
+            // Checks on a rest account apply to each of its elements
+            let per_account = |acc: &AccountParam, idx: usize, check: TokenStream2| {
+                if acc.is_rest {
+                    quote! {
+                        for __idx in #num_fixed..__lez_accounts.len() {
+                            #check
+                        }
+                    }
+                } else {
+                    quote! {
+                        {
+                            let __idx = #idx;
+                            #check
+                        }
+                    }
+                }
+            };
+
+            // Generate count checks for rest accounts with #[account(min = ..., max = ...)]
+            let count_checks: Vec<TokenStream2> = ix
+                .accounts
+                .iter()
+                .filter(|acc| acc.is_rest && (acc.constraints.min.is_some() || acc.constraints.max.is_some()))
+                .map(|acc| {
+                    let acc_name = acc.name.to_string();
+                    let min = acc.constraints.min.unwrap_or(0);
+                    let max = match acc.constraints.max {
+                        Some(max) => quote! { Some(#max) },
+                        None => quote! { None },
+                    };
+                    quote! {
+                        lez_framework::validation::verify_account_list_len(
+                            __lez_accounts.len().saturating_sub(#num_fixed),
+                            #min,
+                            #max,
+                            #acc_name,
+                        )?;
+                    }
+                })
+                .collect();
+
             // Generate signer checks for accounts with #[account(signer)]
             let signer_checks: Vec<TokenStream2> = ix
                 .accounts
                 .iter()
                 .enumerate()
                 .filter(|(_, acc)| acc.constraints.signer)
-                .map(|(i, acc)| {
+                .map(|(idx, acc)| {
                     let acc_name = acc.name.to_string();
-                    let idx = i;
-                    quote! {
-                        if !accounts[#idx].is_authorized {
+                    per_account(acc, idx, quote! {
+                        if !__lez_accounts[__idx].is_authorized {
                             return Err(lez_framework::error::LezError::Unauthorized {
-                                message: format!("Account '{}' (index {}) must be a signer", #acc_name, #idx),
+                                message: format!("Account '{}' (index {}) must be a signer", #acc_name, __idx),
                             });
                         }
-                    }
+                    })
                 })
                 .collect();
-            
+
             // Generate init checks for accounts with #[account(init)]
             let init_checks: Vec<TokenStream2> = ix
                 .accounts
                 .iter()
                 .enumerate()
                 .filter(|(_, acc)| acc.constraints.init)
-                .map(|(i, acc)| {
-                    let acc_name = acc.name.to_string();
-                    let idx = i;
-                    quote! {
-                        if accounts[#idx].account != nssa_core::account::Account::default() {
+                .map(|(idx, acc)| {
+                    per_account(acc, idx, quote! {
+                        if __lez_accounts[__idx].account != nssa_core::account::Account::default() {
                             return Err(lez_framework::error::LezError::AccountAlreadyInitialized {
-                                account_index: #idx,
+                                account_index: __idx,
                             });
                         }
-                    }
+                    })
                 })
                 .collect();
 
-            if signer_checks.is_empty() && init_checks.is_empty() {
-                return quote! {};
-            }
-
-            quote! {
-                #[allow(dead_code)]
-                pub fn #fn_name(accounts: &[nssa_core::account::AccountWithMetadata]) -> Result<(), lez_framework::error::LezError> {
-                    #(#signer_checks)*
-                    #(#init_checks)*
-                    Ok(())
-                }
-            }
-        })
-        .collect()
-}
-
-fn to_pascal_case(ident: &Ident) -> Ident {
-    let s = ident.to_string();
-    let pascal: String = s
-        .split('_')
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
-            }
-        })
-        .collect();
-    format_ident!("{}", pascal)
-}
-
-// ─── IDL type conversion ─────────────────────────────────────────────────
-
-fn rust_type_to_idl_string(ty: &Type) -> String {
-    match ty {
-        Type::Path(type_path) => {
-            let segment = type_path.path.segments.last().unwrap();
-            let ident = segment.ident.to_string();
-            match ident.as_str() {
-                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64"
-                | "i128" | "bool" | "String" => ident.to_lowercase(),
-                "Vec" => {
-                    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                        if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
-                            format!("vec<{}>", rust_type_to_idl_string(inner))
-                        } else {
-                            "vec<unknown>".to_string()
-                        }
-                    } else {
-                        "vec<unknown>".to_string()
-                    }
-                }
-                "ProgramId" => "program_id".to_string(),
-                "AccountId" => "account_id".to_string(),
-                other => other.to_string(),
-            }
-        }
-        Type::Array(arr) => {
-            let elem = rust_type_to_idl_string(&arr.elem);
-            if let syn::Expr::Lit(lit) = &arr.len {
-                if let syn::Lit::Int(n) = &lit.lit {
-                    return format!("[{}; {}]", elem, n);
-                }
-            }
-            format!("[{}; ?]", elem)
-        }
-        _ => "unknown".to_string(),
-    }
-}
-
-/// Convert a Rust IDL type string to the JSON representation.
-/// This produces a JSON value string for embedding in const IDL JSON.
-fn rust_type_to_idl_json(ty: &Type) -> String {
-    match ty {
-        Type::Path(type_path) => {
-            let segment = type_path.path.segments.last().unwrap();
-            let ident = segment.ident.to_string();
-            match ident.as_str() {
-                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64"
-                | "i128" | "bool" | "String" => {
-                    format!("\"{}\"", ident.to_lowercase())
-                }
-                "Vec" => {
-                    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                        if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
-                            format!("{{\"vec\":{}}}", rust_type_to_idl_json(inner))
-                        } else {
-                            "\"vec<unknown>\"".to_string()
-                        }
-                    } else {
-                        "\"vec<unknown>\"".to_string()
-                    }
-                }
-                "ProgramId" => "\"program_id\"".to_string(),
-                "AccountId" => "\"account_id\"".to_string(),
-                other => format!("{{\"defined\":\"{}\"}}", other),
-            }
-        }
-        Type::Array(arr) => {
-            let elem = rust_type_to_idl_json(&arr.elem);
-            if let syn::Expr::Lit(lit) = &arr.len {
-                if let syn::Lit::Int(n) = &lit.lit {
-                    return format!("{{\"array\":[{},{}]}}", elem, n);
-                }
-            }
-            format!("{{\"array\":[{},0]}}", elem)
-        }
-        _ => "\"unknown\"".to_string(),
-    }
-}
-
-// ─── IDL generation (code-based, for __program_idl()) ────────────────────
-
-/// Compute SHA256("global:{name}")[..8] discriminator at macro expansion time.
-fn compute_discriminator(name: &str) -> Vec<u8> {
-    let mut hasher = Sha256::new();
-    hasher.update(format!("global:{}", name).as_bytes());
-    let result = hasher.finalize();
-    result[..8].to_vec()
-}
-
-fn generate_idl_fn(mod_name: &Ident, instructions: &[InstructionInfo], external_instruction: Option<&str>) -> TokenStream2 {
-    let program_name = mod_name.to_string();
-
-    let instruction_literals: Vec<TokenStream2> = instructions
-        .iter()
-        .map(|ix| {
-            let ix_name = ix.fn_name.to_string();
-
-            let account_literals: Vec<TokenStream2> = ix
+            // Generate owner checks for accounts with #[account(owner = ...)]
+            let owner_checks: Vec<TokenStream2> = ix
                 .accounts
                 .iter()
-                .map(|acc| {
-                    let acc_name = acc.name.to_string().trim_start_matches('_').to_string();
-                    let writable = acc.constraints.mutable;
-                    let signer = acc.constraints.signer;
-                    let init = acc.constraints.init;
-
-                    let pda_expr = if acc.constraints.pda_seeds.is_empty() {
-                        quote! { None }
-                    } else {
-                        let seed_literals: Vec<TokenStream2> = acc
-                            .constraints
-                            .pda_seeds
-                            .iter()
-                            .map(|seed| match seed {
-                                PdaSeedDef::Const(val) => quote! {
-                                    lez_framework::idl::IdlSeed::Const { value: #val.to_string() }
-                                },
-                                PdaSeedDef::Account(name) => quote! {
-                                    lez_framework::idl::IdlSeed::Account { path: #name.to_string() }
-                                },
-                                PdaSeedDef::Arg(name) => quote! {
-                                    lez_framework::idl::IdlSeed::Arg { path: #name.to_string() }
-                                },
-                            })
-                            .collect();
+                .enumerate()
+                .filter_map(|(idx, acc)| acc.constraints.owner.as_ref().map(|owner| (idx, acc, owner)))
+                .map(|(idx, acc, owner)| {
+                    per_account(acc, idx, quote! {
+                        lez_framework::validation::verify_owner(
+                            &__lez_accounts[__idx].account.program_owner,
+                            &(#owner),
+                            __idx,
+                        )?;
+                    })
+                })
+                .collect();
 
+            // Generate `has_one = field` checks: decode the account's data and
+            // compare the field with the sibling account's ID
+            let has_one_checks: Vec<TokenStream2> = ix
+                .accounts
+                .iter()
+                .enumerate()
+                .filter(|(_, acc)| !acc.constraints.has_one.is_empty())
+                .map(|(idx, acc)| {
+                    let data_type = acc.data_type.as_ref().expect("checked by check_account_references");
+                    let comparisons = acc.constraints.has_one.iter().map(|field| {
+                        let field_idx = ix.accounts.iter().position(|a| a.name == *field)
+                            .expect("checked by check_account_references");
+                        let message = format!(
+                            "Account '{}' (index {}) does not match '{}.{}'",
+                            field, field_idx, acc.name, field
+                        );
                         quote! {
-                            Some(lez_framework::idl::IdlPda {
-                                seeds: vec![#(#seed_literals),*],
-                            })
+                            if __data.#field != __lez_accounts[#field_idx].account_id {
+                                return Err(lez_framework::error::LezError::Unauthorized {
+                                    message: #message.to_string(),
+                                });
+                            }
                         }
-                    };
-
-                    let is_rest = acc.is_rest;
+                    });
                     quote! {
-                        lez_framework::idl::IdlAccountItem {
-                            name: #acc_name.to_string(),
-                            writable: #writable,
-                            signer: #signer,
-                            init: #init,
-                            owner: None,
-                            pda: #pda_expr,
-                            rest: #is_rest,
-                            visibility: vec!["public".to_string()],
+                        {
+                            let __data = lez_framework::account::LezAccount::<#data_type>::load(
+                                __lez_accounts[#idx].clone(),
+                                #idx,
+                            )?;
+                            #(#comparisons)*
                         }
                     }
                 })
                 .collect();
 
-            let arg_literals: Vec<TokenStream2> = ix
-                .args
+            // Generate `constraint = ...` checks, with every account and
+            // argument in scope by name
+            let constraint_checks: Vec<TokenStream2> = ix
+                .accounts
                 .iter()
-                .map(|arg| {
-                    let arg_name = arg.name.to_string().trim_start_matches('_').to_string();
-                    let type_str = rust_type_to_idl_string(&arg.ty);
+                .enumerate()
+                .flat_map(|(idx, acc)| acc.constraints.checks.iter().map(move |check| (idx, check)))
+                .map(|(idx, check)| {
+                    let expr = &check.expr;
+                    let error = match &check.error {
+                        Some(error) => quote! { (#error).into() },
+                        None => {
+                            let text = check.to_idl_string();
+                            quote! {
+                                lez_framework::error::LezError::ConstraintViolated {
+                                    account_index: #idx,
+                                    constraint: #text.to_string(),
+                                }
+                            }
+                        }
+                    };
                     quote! {
-                        lez_framework::idl::IdlArg {
-                            name: #arg_name.to_string(),
-                            type_: lez_framework::idl::IdlType::Primitive(#type_str.to_string()),
+                        if !(#expr) {
+                            return Err(#error);
                         }
                     }
                 })
                 .collect();
-
-            let discriminator_bytes = compute_discriminator(&ix_name);
-            let disc_bytes_lit: Vec<proc_macro2::TokenStream> = discriminator_bytes.iter()
-                .map(|b| { let val = proc_macro2::Literal::u8_unsuffixed(*b); quote! { #val } })
-                .collect();
-            let variant_name_str = {
-                let s = &ix_name;
-                s.split('_')
-                    .map(|w| {
-                        let mut c = w.chars();
-                        match c.next() {
-                            None => String::new(),
-                            Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+            let bindings: Vec<TokenStream2> = if constraint_checks.is_empty() {
+                Vec::new()
+            } else {
+                ix.accounts
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, acc)| {
+                        let name = &acc.name;
+                        if acc.is_rest {
+                            quote! { let #name = &__lez_accounts[#num_fixed..]; }
+                        } else {
+                            quote! { let #name = &__lez_accounts[#idx]; }
                         }
                     })
-                    .collect::<String>()
+                    .collect()
             };
+            // Generate the `threshold(...)` check, with the accounts it refers
+            // to in scope by name (typed accounts decoded)
+            let threshold_check = ix.threshold.as_ref().map(|threshold| {
+                let bindings = threshold.referenced_accounts(&ix.accounts).into_iter().map(|acc| {
+                    let name = &acc.name;
+                    let idx = ix.accounts.iter().position(|a| a.name == acc.name).unwrap();
+                    match &acc.data_type {
+                        _ if acc.is_rest => quote! { let #name = &__lez_accounts[#num_fixed..]; },
+                        Some(data_type) => quote! {
+                            let #name = lez_framework::account::LezAccount::<#data_type>::load(
+                                __lez_accounts[#idx].clone(),
+                                #idx,
+                            )?;
+                        },
+                        None => quote! { let #name = &__lez_accounts[#idx]; },
+                    }
+                });
+                let min = &threshold.min;
+                let members = match &threshold.members {
+                    Some(members) => quote! { Some(&(#members)[..]) },
+                    None => quote! { None::<&[nssa_core::account::AccountId]> },
+                };
+                quote! {
+                    {
+                        #(#bindings)*
+                        let __required = usize::try_from(#min).unwrap_or(usize::MAX);
+                        lez_framework::validation::verify_threshold(
+                            &__lez_accounts[#num_fixed..],
+                            #num_fixed,
+                            __required,
+                            #members,
+                        )?;
+                    }
+                }
+            });
+
+            let arg_params: Vec<TokenStream2> = ix.args.iter()
+                .map(|a| {
+                    let name = &a.name;
+                    let ty = &a.ty;
+                    quote! { #name: &#ty }
+                })
+                .collect();
+
+            if !needs_validation(ix) {
+                return quote! {};
+            }
 
             quote! {
-                lez_framework::idl::IdlInstruction {
-                    name: #ix_name.to_string(),
-                    accounts: vec![#(#account_literals),*],
-                    args: vec![#(#arg_literals),*],
-                    discriminator: Some(vec![#(#disc_bytes_lit),*]),
-                    execution: Some(lez_framework::idl::IdlExecution {
-                        public: true,
-                        private_owned: false,
-                    }),
-                    variant: Some(#variant_name_str.to_string()),
+                #[allow(dead_code, unused_variables)]
+                pub fn #fn_name(
+                    __lez_accounts: &[nssa_core::account::AccountWithMetadata],
+                    #(#arg_params),*
+                ) -> Result<(), lez_framework::error::LezError> {
+                    #(#count_checks)*
+                    #(#signer_checks)*
+                    #(#init_checks)*
+                    #(#owner_checks)*
+                    #(#has_one_checks)*
+                    #(#bindings)*
+                    #(#constraint_checks)*
+                    #threshold_check
+                    Ok(())
                 }
             }
         })
-        .collect();
-
-    // Compute instruction_type at proc-macro expansion time
-    let instruction_type_expr = if let Some(ext) = external_instruction {
-        quote! { Some(#ext.to_string()) }
-    } else {
-        quote! { None }
-    };
-
-    quote! {
-        #[allow(dead_code)]
-        pub fn __program_idl() -> lez_framework::idl::LezIdl {
-            lez_framework::idl::LezIdl {
-                version: "0.1.0".to_string(),
-                name: #program_name.to_string(),
-                instructions: vec![#(#instruction_literals),*],
-                accounts: vec![],
-                types: vec![],
-                errors: vec![],
-                spec: Some("0.1.0".to_string()),
-                instruction_type: #instruction_type_expr,
-                metadata: Some(lez_framework::idl::IdlMetadata {
-                    name: #program_name.to_string(),
-                    version: "0.1.0".to_string(),
-                }),
-            }
-        }
-    }
+        .collect()
 }
 
-// ─── IDL generation (JSON string, for PROGRAM_IDL_JSON const) ────────────
-
-fn generate_idl_json(mod_name: &Ident, instructions: &[InstructionInfo], external_instruction: Option<&str>) -> String {
-    let program_name = mod_name.to_string();
-
-    let instructions_json: Vec<String> = instructions
+/// Generate `__validate_pdas_<fn>(accounts, program_id, args...)` for instructions
+/// with `#[account(pda = ...)]` accounts. Each PDA account's ID is recomputed
+/// from its seeds, under its `pda_program` if it has one, and compared against
+/// the supplied one. Without `pda_program` the account is checked with
+/// `verify_own_pda`, which falls back to the account's owner when
+/// `program_id` is `None`.
+fn generate_pda_validation(instructions: &[InstructionInfo]) -> Vec<TokenStream2> {
+    instructions
         .iter()
+        .filter(|ix| needs_pda_validation(ix))
         .map(|ix| {
-            let ix_name = &ix.fn_name.to_string();
+            let fn_name = format_ident!("__validate_pdas_{}", ix.fn_name);
+            let arg_params: Vec<TokenStream2> = ix.args.iter()
+                .map(|a| {
+                    let name = &a.name;
+                    let ty = &a.ty;
+                    quote! { #name: &#ty }
+                })
+                .collect();
 
-            let accounts_json: Vec<String> = ix
+            let pda_checks: Vec<TokenStream2> = ix
                 .accounts
                 .iter()
-                .map(|acc| {
-                    let name = acc.name.to_string();
-                    let writable = acc.constraints.mutable;
-                    let signer = acc.constraints.signer;
-                    let init = acc.constraints.init;
-
-                    let pda_json = if acc.constraints.pda_seeds.is_empty() {
-                        String::new()
-                    } else {
-                        let seeds: Vec<String> = acc
-                            .constraints
-                            .pda_seeds
-                            .iter()
-                            .map(|seed| match seed {
-                                PdaSeedDef::Const(val) => {
-                                    format!("{{\"kind\":\"const\",\"value\":\"{}\"}}", val)
-                                }
-                                PdaSeedDef::Account(name) => {
-                                    format!("{{\"kind\":\"account\",\"path\":\"{}\"}}", name)
-                                }
-                                PdaSeedDef::Arg(name) => {
-                                    format!("{{\"kind\":\"arg\",\"path\":\"{}\"}}", name)
-                                }
-                            })
-                            .collect();
-                        format!(",\"pda\":{{\"seeds\":[{}]}}", seeds.join(","))
+                .enumerate()
+                .filter(|(_, acc)| !acc.is_rest && !acc.constraints.pda_seeds.is_empty())
+                .map(|(idx, acc)| {
+                    let seed_exprs: Vec<TokenStream2> = acc.constraints.pda_seeds.iter()
+                        .map(|seed| match seed {
+                            PdaSeedDef::Const(value) => quote! {
+                                lez_framework::pda::seed_from_str(#value)
+                            },
+                            PdaSeedDef::Account(path) => {
+                                let seed_idx = ix.accounts.iter()
+                                    .position(|a| !a.is_rest && a.name == path.value())
+                                    .expect("seed accounts are checked by check_pda_seeds");
+                                quote! { *__lez_accounts[#seed_idx].account_id.value() }
+                            }
+                            PdaSeedDef::Arg(path) => {
+                                let arg = format_ident!("{}", path.value());
+                                quote! { lez_framework::pda::PdaSeedArg::to_seed_bytes(#arg) }
+                            }
+                        })
+                        .collect();
+                    let program = match &acc.constraints.pda_program {
+                        None => {
+                            return quote! {
+                                lez_framework::pda::verify_own_pda(
+                                    &__lez_accounts[#idx],
+                                    __lez_program_id,
+                                    &[#(&#seed_exprs),*],
+                                    #idx,
+                                )?;
+                            };
+                        }
+                        Some(PdaProgramDef::Arg(path)) => {
+                            let arg = format_ident!("{}", path.value());
+                            quote! { #arg }
+                        }
+                        Some(PdaProgramDef::Const(expr)) => quote! { &(#expr) },
                     };
-
-                    let rest_json = if acc.is_rest { ",\"rest\":true".to_string() } else { String::new() };
-                    format!(
-                        "{{\"name\":\"{}\",\"writable\":{},\"signer\":{},\"init\":{}{}{}}}",
-                        name, writable, signer, init, pda_json, rest_json
-                    )
+                    quote! {
+                        lez_framework::pda::verify_pda(
+                            &__lez_accounts[#idx].account_id,
+                            #program,
+                            &[#(&#seed_exprs),*],
+                            #idx,
+                        )?;
+                    }
                 })
                 .collect();
 
-            let args_json: Vec<String> = ix
-                .args
-                .iter()
-                .map(|arg| {
-                    let name = arg.name.to_string();
-                    let type_json = rust_type_to_idl_json(&arg.ty);
-                    format!("{{\"name\":\"{}\",\"type\":{}}}", name, type_json)
-                })
-                .collect();
+            quote! {
+                #[allow(dead_code, unused_variables)]
+                pub fn #fn_name(
+                    __lez_accounts: &[nssa_core::account::AccountWithMetadata],
+                    __lez_program_id: Option<&nssa_core::program::ProgramId>,
+                    #(#arg_params),*
+                ) -> Result<(), lez_framework::error::LezError> {
+                    #(#pda_checks)*
+                    Ok(())
+                }
+            }
+        })
+        .collect()
+}
 
-            format!(
-                "{{\"name\":\"{}\",\"accounts\":[{}],\"args\":[{}]}}",
-                ix_name,
-                accounts_json.join(","),
-                args_json.join(",")
-            )
+/// Whether an instruction has fixed accounts with PDA seeds to verify.
+fn needs_pda_validation(ix: &InstructionInfo) -> bool {
+    ix.accounts.iter().any(|a| !a.is_rest && !a.constraints.pda_seeds.is_empty())
+}
+
+/// Whether an instruction needs a generated `__validate_*` function.
+fn needs_validation(ix: &InstructionInfo) -> bool {
+    ix.threshold.is_some() || ix.accounts.iter().any(|a| {
+        a.constraints.signer
+            || a.constraints.init
+            || a.constraints.owner.is_some()
+            || a.constraints.min.is_some()
+            || a.constraints.max.is_some()
+            || !a.constraints.has_one.is_empty()
+            || !a.constraints.checks.is_empty()
+    })
+}
+
+/// Render an `owner = <expr>` constraint as the string recorded in the IDL.
+fn owner_to_idl_string(owner: &syn::Expr) -> String {
+    quote!(#owner).to_string().replace(' ', "")
+}
+
+/// Render tokens close to how they are usually written, e.g.
+/// `vault.balance >= *amount` rather than `vault . balance >= * amount`.
+fn tokens_to_idl_string(tokens: TokenStream2) -> String {
+    use proc_macro2::{Delimiter, Spacing, TokenTree};
+
+    let mut out = String::new();
+    // Whether the last token ends an operand, so that `*`, `&`, `-` and `!`
+    // after it are binary operators (or a macro's `!`) rather than unary
+    let mut after_operand = false;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if after_operand {
+                    out.push(' ');
+                }
+                out.push_str(&token.to_string());
+                after_operand = true;
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => (" { ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                out.push_str(open);
+                out.push_str(&tokens_to_idl_string(group.stream()));
+                out.push_str(close);
+                after_operand = true;
+            }
+            TokenTree::Punct(punct) => {
+                let mut op = punct.as_char().to_string();
+                let mut spacing = punct.spacing();
+                while spacing == Spacing::Joint {
+                    match tokens.peek() {
+                        Some(TokenTree::Punct(next)) => {
+                            op.push(next.as_char());
+                            spacing = next.spacing();
+                            tokens.next();
+                        }
+                        _ => break,
+                    }
+                }
+                match op.as_str() {
+                    "." | "::" => out.push_str(&op),
+                    "," | ";" => {
+                        out.push_str(&op);
+                        out.push(' ');
+                    }
+                    "!" if after_operand => out.push('!'),
+                    "*" | "&" | "-" | "!" if !after_operand => out.push_str(&op),
+                    _ => {
+                        out.push(' ');
+                        out.push_str(&op);
+                        out.push(' ');
+                    }
+                }
+                after_operand = false;
+            }
+        }
+    }
+    out
+}
+
+fn to_pascal_case(ident: &Ident) -> Ident {
+    let s = ident.to_string();
+    let pascal: String = s
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
+            }
         })
         .collect();
-
-    let instruction_type_suffix = if let Some(ext) = external_instruction {
-        format!(",\"instruction_type\":\"{}\"", ext)
-    } else {
-        String::new()
-    };
-    format!(
-        "{{\"version\":\"0.1.0\",\"name\":\"{}\",\"instructions\":[{}],\"accounts\":[],\"types\":[],\"errors\":[]{}}}"    ,
-        program_name,
-        instructions_json.join(","),
-        instruction_type_suffix
-    )
+    format_ident!("{}", pascal)
 }
 
 // ─── generate_idl! macro implementation ──────────────────────────────────
 
+/// A `#[lez_program]` module found by [`find_program_mod`], with the
+/// directories its own `mod name;` declarations resolve against and the
+/// module files read to get there.
+type FoundProgramMod = (ItemMod, Vec<std::path::PathBuf>, Vec<String>);
+
+/// Find the `#[lez_program]` module among `items`, descending into inline and
+/// `mod name;` modules.
+fn find_program_mod(
+    items: &[syn::Item],
+    dirs: &[std::path::PathBuf],
+    span: &syn::LitStr,
+) -> syn::Result<Option<FoundProgramMod>> {
+    for item in items {
+        let syn::Item::Mod(m) = item else { continue };
+        let inner_dirs: Vec<std::path::PathBuf> =
+            dirs.iter().map(|dir| dir.join(m.ident.to_string())).collect();
+        if m.attrs.iter().any(|a| a.path().is_ident("lez_program")) {
+            return Ok(Some((m.clone(), inner_dirs, Vec::new())));
+        }
+        let found = match &m.content {
+            Some((_, inner)) => find_program_mod(inner, &inner_dirs, span)?,
+            None => {
+                // Modules that can't be found are not our concern here
+                let Ok(path) = find_group_file(&m.ident, dirs) else { continue };
+                let file = read_source_file(&path, span)?;
+                find_program_mod(&file.items, &module_dirs_of_file(&path), span)?.map(
+                    |(program_mod, dirs, mut files)| {
+                        files.push(path.to_string_lossy().to_string());
+                        (program_mod, dirs, files)
+                    },
+                )
+            }
+        };
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+    Ok(None)
+}
+
 fn expand_generate_idl(file_path: &str, span_token: &syn::LitStr) -> syn::Result<TokenStream2> {
     // Try the path as-is first, then relative to CARGO_MANIFEST_DIR
     let resolved_path = if std::path::Path::new(file_path).exists() {
@@ -1063,18 +2987,12 @@ fn expand_generate_idl(file_path: &str, span_token: &syn::LitStr) -> syn::Result
         )
     })?;
 
-    // Find the #[lez_program] module
-    let mut program_mod: Option<&ItemMod> = None;
-    for item in &file.items {
-        if let syn::Item::Mod(m) = item {
-            if m.attrs.iter().any(|a| a.path().is_ident("lez_program")) {
-                program_mod = Some(m);
-                break;
-            }
-        }
-    }
-
-    let program_mod = program_mod.ok_or_else(|| {
+    // Find the #[lez_program] module, which may sit in a nested module
+    let resolved_file = std::env::current_dir()
+        .map(|cwd| cwd.join(&resolved_path))
+        .unwrap_or_else(|_| resolved_path.clone().into());
+    let found = find_program_mod(&file.items, &module_dirs_of_file(&resolved_file), span_token)?;
+    let (program_mod, dirs, mut source_files) = found.ok_or_else(|| {
         syn::Error::new_spanned(
             span_token,
             format!(
@@ -1090,15 +3008,12 @@ fn expand_generate_idl(file_path: &str, span_token: &syn::LitStr) -> syn::Result
         syn::Error::new_spanned(span_token, "lez_program module has no body")
     })?;
 
-    // Parse instructions
-    let mut instructions: Vec<InstructionInfo> = Vec::new();
-    for item in items {
-        if let syn::Item::Fn(func) = item {
-            if has_instruction_attr(&func.attrs) {
-                instructions.push(parse_instruction(func.clone())?);
-            }
-        }
-    }
+    // Parse instructions, IDL types and errors, following instruction groups
+    let mut collected = ProgramItems::default();
+    collect_program_items(items, &dirs, &[], &mut collected)?;
+    let mut instructions = collected.parse_instructions()?;
+    let mut idl_items = collected.idl_items;
+    source_files.extend(collected.group_files.iter().map(|p| p.to_string_lossy().to_string()));
 
     if instructions.is_empty() {
         return Err(syn::Error::new_spanned(
@@ -1106,28 +3021,47 @@ fn expand_generate_idl(file_path: &str, span_token: &syn::LitStr) -> syn::Result
             "No #[instruction] functions found in the program module",
         ));
     }
+    check_instruction_names(&instructions)?;
+    assign_instruction_indices(&mut instructions)?;
+
+    // Read the #[lez_program(...)] options
+    let config = match program_mod.attrs.iter().find(|a| a.path().is_ident("lez_program")) {
+        Some(attr) => match &attr.meta {
+            syn::Meta::List(list) => ProgramConfig::parse(list.tokens.clone())?,
+            _ => ProgramConfig::parse(TokenStream2::new())?,
+        },
+        None => ProgramConfig::parse(TokenStream2::new())?,
+    };
+    let external_instruction_str: Option<String> = config.external_instruction.as_ref().map(|p| {
+        let segments: Vec<String> = p.segments.iter().map(|s| s.ident.to_string()).collect();
+        segments.join("::")
+    });
 
-    // Detect external instruction type from the #[lez_program(...)] attr
-    let external_instruction_str: Option<String> = program_mod.attrs.iter()
-        .find(|a| a.path().is_ident("lez_program"))
-        .and_then(|attr| {
-            // Try to parse as lez_program(instruction = "some::Path")
-            let mut ext: Option<String> = None;
-            let _ = attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("instruction") {
-                    if let Ok(value) = meta.value() {
-                        if let Ok(lit) = value.parse::<syn::LitStr>() {
-                            ext = Some(lit.value());
-                        }
-                    }
-                }
-                Ok(())
-            });
-            ext
-        });
+    // `types_from` paths are relative to the program crate, so resolve them
+    // against the nearest Cargo.toml above the program source file
+    let program_crate_dir = std::path::Path::new(&resolved_path)
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").exists())
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default();
+    let mut types_from_paths: Vec<String> = Vec::new();
+    for path in &config.types_from {
+        let resolved = program_crate_dir.join(path);
+        idl_items.extend(load_idl_items_from(&resolved, span_token)?);
+        types_from_paths.push(resolved.to_string_lossy().to_string());
+    }
 
     // Generate the IDL JSON
-    let idl_json = generate_idl_json(mod_name, &instructions, external_instruction_str.as_deref());
+    let idl_json = idl::build_idl(
+        &mod_name.to_string(),
+        &instructions,
+        &idl_items,
+        external_instruction_str.as_deref(),
+        config.dispatch,
+        &extract_docs(&program_mod.attrs),
+    )?
+    .to_json_pretty();
 
     // Embed the resolved path for cargo tracking
     let resolved = resolved_path.clone();
@@ -1137,9 +3071,9 @@ fn expand_generate_idl(file_path: &str, span_token: &syn::LitStr) -> syn::Result
         fn main() {
             // Help cargo track source changes
             const _SOURCE: &str = include_str!(#resolved);
-            let json: serde_json::Value = serde_json::from_str(#idl_json)
-                .expect("Generated IDL JSON is invalid");
-            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+            #(const _: &str = include_str!(#source_files);)*
+            #(const _: &str = include_str!(#types_from_paths);)*
+            println!("{}", #idl_json);
         }
     })
 }
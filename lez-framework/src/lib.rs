@@ -4,14 +4,18 @@
 //! similar to Anchor for Solana.
 
 // Re-export the proc macros
-pub use lez_framework_macros::{lez_program, instruction, generate_idl};
+pub use lez_framework_macros::{
+    lez_program, instruction, instruction_group, instruction_groups, generate_idl, declare_program,
+    lez_account, lez_type, lez_error, LezAccounts,
+};
 
 // Re-export core types
 pub use lez_framework_core::*;
 
 pub mod prelude {
     pub use crate::lez_program;
-    pub use crate::instruction;
+    pub use crate::{instruction, instruction_group, instruction_groups};
+    pub use crate::{lez_account, lez_type, lez_error, LezAccounts};
     pub use lez_framework_core::prelude::*;
     pub use lez_framework_core::types::LezOutput;
     pub use lez_framework_core::error::{LezError, LezResult};
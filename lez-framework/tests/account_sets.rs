@@ -0,0 +1,108 @@
+//! `#[derive(LezAccounts)]` structs are flattened into the instruction's
+//! accounts exactly as if their fields were parameters.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use nssa_core::program::ProgramId;
+use serde_json::json;
+
+const PROGRAM: ProgramId = [7u32; 8];
+
+#[lez_program(program_id = "crate::PROGRAM")]
+mod ledger {
+    #[allow(unused_imports)]
+    use super::*;
+
+    /// Accounts shared by every transfer.
+    #[derive(LezAccounts)]
+    pub struct Transfer {
+        /// Debited
+        #[account(mut, signer)]
+        pub from: AccountWithMetadata,
+        #[account(mut)]
+        pub to: AccountWithMetadata,
+        #[account(pda = account("from"))]
+        pub limits: AccountWithMetadata,
+    }
+
+    #[instruction]
+    pub fn transfer(ctx: Transfer, amount: u128) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(ctx.from.account),
+            AccountPostState::new(ctx.to.account),
+            AccountPostState::new(ctx.limits.account),
+        ]))
+    }
+
+    #[instruction]
+    pub fn transfer_with_fee(
+        ctx: Transfer,
+        #[account(mut)]
+        fee_sink: AccountWithMetadata,
+        amount: u128,
+        fee: u128,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(ctx.from.account),
+            AccountPostState::new(ctx.to.account),
+            AccountPostState::new(ctx.limits.account),
+            AccountPostState::new(fee_sink.account),
+        ]))
+    }
+
+    #[instruction]
+    pub fn transfer_inline(
+        /// Debited
+        #[account(mut, signer)]
+        from: AccountWithMetadata,
+        #[account(mut)]
+        to: AccountWithMetadata,
+        #[account(pda = account("from"))]
+        limits: AccountWithMetadata,
+        amount: u128,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(from.account),
+            AccountPostState::new(to.account),
+            AccountPostState::new(limits.account),
+        ]))
+    }
+}
+
+fn instruction(name: &str) -> serde_json::Value {
+    let idl: serde_json::Value = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+    idl["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|ix| ix["name"] == name)
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn flattened_like_inline_parameters() {
+    assert_eq!(instruction("transfer")["accounts"], instruction("transfer_inline")["accounts"]);
+    assert_eq!(instruction("transfer")["args"], instruction("transfer_inline")["args"]);
+}
+
+#[test]
+fn account_set_followed_by_more_accounts() {
+    let ix = instruction("transfer_with_fee");
+    let names: Vec<&str> = ix["accounts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["from", "to", "limits", "fee_sink"]);
+    assert_eq!(ix["accounts"][0]["docs"], json!(["Debited"]));
+    assert_eq!(ix["args"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn instruction_carries_only_args() {
+    let ix = Instruction::TransferWithFee { amount: 10, fee: 1 };
+    assert!(matches!(ix, Instruction::TransferWithFee { amount: 10, fee: 1 }));
+}
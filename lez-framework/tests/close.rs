@@ -0,0 +1,33 @@
+//! `#[account(mut, close = recipient)]` is recorded in the IDL; the balance
+//! transfer itself is covered by `close_account` in lez-framework-core.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+
+#[lez_program]
+mod escrow {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[instruction]
+    pub fn cancel(
+        #[account(mut, close = maker)]
+        escrow: AccountWithMetadata,
+        #[account(mut, signer)]
+        maker: AccountWithMetadata,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(escrow.account),
+            AccountPostState::new(maker.account),
+        ]))
+    }
+}
+
+#[test]
+fn close_recorded_in_idl() {
+    let idl = __program_idl();
+    let accounts = &idl.instructions[0].accounts;
+    assert_eq!(accounts[0].close.as_deref(), Some("maker"));
+    assert_eq!(accounts[1].close, None);
+}
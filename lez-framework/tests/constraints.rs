@@ -0,0 +1,99 @@
+//! `#[account(constraint = ...)]` checks run in `__validate_*` and are
+//! recorded in the IDL as written.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use nssa_core::account::{Account, AccountId};
+
+#[lez_program]
+mod vault {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[lez_error]
+    pub enum VaultError {
+        #[msg("Not enough funds in the vault")]
+        InsufficientFunds,
+    }
+
+    #[instruction]
+    pub fn withdraw(
+        #[account(mut, constraint = vault.account.balance >= *amount @ VaultError::InsufficientFunds)]
+        vault: AccountWithMetadata,
+        #[account(mut, signer, constraint = recipient.account_id != vault.account_id)]
+        recipient: AccountWithMetadata,
+        amount: u128,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(vault.account),
+            AccountPostState::new(recipient.account),
+        ]))
+    }
+
+    /// An account may be called `accounts` without hiding the others.
+    #[instruction]
+    pub fn sweep(
+        #[account(mut, signer, constraint = accounts.account.balance > 0)]
+        accounts: AccountWithMetadata,
+        #[account(mut, constraint = vault.account_id != accounts.account_id)]
+        vault: AccountWithMetadata,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(accounts.account),
+            AccountPostState::new(vault.account),
+        ]))
+    }
+}
+
+fn make_account(id: u8, balance: u128) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new([id; 32]),
+        account: Account { balance, ..Account::default() },
+        is_authorized: true,
+    }
+}
+
+#[test]
+fn passing_constraints() {
+    let accounts = vec![make_account(1, 100), make_account(2, 0)];
+    assert!(vault::__validate_withdraw(&accounts, &100).is_ok());
+}
+
+#[test]
+fn failing_constraint_returns_its_error() {
+    let accounts = vec![make_account(1, 100), make_account(2, 0)];
+    let err = vault::__validate_withdraw(&accounts, &101).unwrap_err();
+    assert_eq!(err, vault::VaultError::InsufficientFunds.into());
+}
+
+#[test]
+fn failing_constraint_without_error() {
+    let accounts = vec![make_account(1, 100), make_account(1, 0)];
+    match vault::__validate_withdraw(&accounts, &1).unwrap_err() {
+        LezError::ConstraintViolated { account_index, constraint } => {
+            assert_eq!(account_index, 1);
+            assert_eq!(constraint, "recipient.account_id != vault.account_id");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn constraints_recorded_in_idl() {
+    let idl = __program_idl();
+    let accounts = &idl.instructions[0].accounts;
+    assert_eq!(
+        accounts[0].constraints,
+        vec!["vault.account.balance >= *amount @ VaultError::InsufficientFunds"]
+    );
+    assert_eq!(accounts[1].constraints, vec!["recipient.account_id != vault.account_id"]);
+}
+
+#[test]
+fn account_named_accounts() {
+    let accounts = vec![make_account(1, 100), make_account(2, 0)];
+    assert!(vault::__validate_sweep(&accounts).is_ok());
+    let same = vec![make_account(1, 100), make_account(1, 0)];
+    assert!(vault::__validate_sweep(&same).is_err());
+}
@@ -0,0 +1,79 @@
+//! `pda_program` derives a PDA under another program's ID, taken from an
+//! instruction argument or a constant, and records it in the IDL.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::idl::IdlPdaProgram;
+use lez_framework::pda::{compute_pda, seed_from_str};
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+use nssa_core::program::ProgramId;
+
+const TOKEN_PROGRAM: ProgramId = [9u32; 8];
+
+#[lez_program(program_id = "crate::PROGRAM")]
+mod vaults {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[instruction]
+    pub fn deposit(
+        owner: AccountWithMetadata,
+        #[account(pda = [literal("holding"), account("owner")], pda_program = arg("token_program_id"))]
+        holding: AccountWithMetadata,
+        token_program_id: ProgramId,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(owner.account),
+            AccountPostState::new(holding.account),
+        ]))
+    }
+
+    #[instruction]
+    pub fn withdraw(
+        owner: AccountWithMetadata,
+        #[account(pda = [literal("holding"), account("owner")], pda_program = const(TOKEN_PROGRAM))]
+        holding: AccountWithMetadata,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(owner.account),
+            AccountPostState::new(holding.account),
+        ]))
+    }
+}
+
+const PROGRAM: ProgramId = [7u32; 8];
+
+fn account(id: AccountId) -> AccountWithMetadata {
+    AccountWithMetadata { account_id: id, account: Account::default(), is_authorized: false }
+}
+
+fn accounts(program: &ProgramId) -> Vec<AccountWithMetadata> {
+    let owner = AccountId::new([1u8; 32]);
+    let holding = compute_pda(program, &[&seed_from_str("holding"), owner.value()]);
+    vec![account(owner), account(holding)]
+}
+
+#[test]
+fn arg_program_pda_verifies() {
+    let accounts = accounts(&TOKEN_PROGRAM);
+    assert!(vaults::__validate_pdas_deposit(&accounts, Some(&PROGRAM), &TOKEN_PROGRAM).is_ok());
+    assert!(vaults::__validate_pdas_deposit(&accounts, Some(&PROGRAM), &PROGRAM).is_err());
+}
+
+#[test]
+fn const_program_pda_verifies() {
+    assert!(vaults::__validate_pdas_withdraw(&accounts(&TOKEN_PROGRAM), Some(&PROGRAM)).is_ok());
+    match vaults::__validate_pdas_withdraw(&accounts(&PROGRAM), Some(&PROGRAM)).unwrap_err() {
+        LezError::PdaMismatch { account_index } => assert_eq!(account_index, 1),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn pda_program_recorded_in_idl() {
+    let idl = __program_idl();
+    let program = |ix: usize| idl.instructions[ix].accounts[1].pda.as_ref().unwrap().program.clone();
+    assert!(matches!(program(0), Some(IdlPdaProgram::Arg { path }) if path == "token_program_id"));
+    assert!(matches!(program(1), Some(IdlPdaProgram::Const { value }) if value == "TOKEN_PROGRAM"));
+}
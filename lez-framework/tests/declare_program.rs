@@ -0,0 +1,167 @@
+//! `declare_program!` builds `ChainedCall`s from a callee's IDL. The fixture
+//! is the IDL of the `callee::escrow` program below (checked by
+//! `fixture_is_the_callee_idl`), whose `Instruction` must read back what the
+//! builders serialize.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::pda::{compute_pda, seed_from_str, PdaSeedArg};
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+
+mod callee {
+    use lez_framework::prelude::*;
+
+    // The generated `Instruction` enum lives outside the module
+    pub use escrow::Split;
+
+    /// Program the escrow's receipts are derived under.
+    pub const RECEIPTS: ProgramId = [8u32; 8];
+
+    #[lez_program(program_id = "crate::ESCROW")]
+    mod escrow {
+        #[allow(unused_imports)]
+        use super::*;
+
+        #[lez_type]
+        #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+        pub struct Split {
+            pub share: u32,
+            pub memo: Option<String>,
+        }
+
+        #[instruction]
+        pub fn deposit(
+            #[account(signer)]
+            owner: AccountWithMetadata,
+            #[account(mut, pda = [literal("vault"), account("owner")])]
+            vault: AccountWithMetadata,
+            amount: u64,
+            memo: String,
+        ) -> LezResult {
+            Ok(LezOutput::states_only(vec![
+                AccountPostState::new(owner.account),
+                AccountPostState::new(vault.account),
+            ]))
+        }
+
+        #[instruction]
+        pub fn claim(
+            #[account(signer)]
+            owner: AccountWithMetadata,
+            #[account(mut, pda = [literal("receipt"), account("owner")], pda_program = const(RECEIPTS))]
+            receipt: AccountWithMetadata,
+        ) -> LezResult {
+            Ok(LezOutput::states_only(vec![
+                AccountPostState::new(owner.account),
+                AccountPostState::new(receipt.account),
+            ]))
+        }
+
+        #[instruction]
+        pub fn release(
+            #[account(mut)]
+            vault: AccountWithMetadata,
+            #[account(min = 1, max = 2)]
+            recipients: Vec<AccountWithMetadata>,
+            split: Split,
+        ) -> LezResult {
+            Ok(LezOutput::states_only(vec![AccountPostState::new(vault.account)]))
+        }
+    }
+}
+
+lez_framework::declare_program!("tests/fixtures/escrow.idl.json");
+// Instruction, account and argument names that are Rust keywords
+lez_framework::declare_program!("tests/fixtures/keywords.idl.json");
+
+const ESCROW: ProgramId = [3u32; 8];
+
+fn account(id: AccountId) -> AccountWithMetadata {
+    AccountWithMetadata { account_id: id, account: Account::default(), is_authorized: false }
+}
+
+fn decode(call: &ChainedCall) -> callee::Instruction {
+    risc0_zkvm::serde::from_slice(&call.instruction_data).unwrap()
+}
+
+#[test]
+fn fixture_is_the_callee_idl() {
+    let fixture: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/escrow.idl.json")).unwrap();
+    let generated: serde_json::Value = serde_json::from_str(callee::PROGRAM_IDL_JSON).unwrap();
+    assert_eq!(fixture, generated, "regenerate tests/fixtures/escrow.idl.json from the callee");
+}
+
+#[test]
+fn builds_deposit_call() {
+    let owner = AccountId::new([1u8; 32]);
+    let vault = compute_pda(&ESCROW, &[&seed_from_str("vault"), owner.value()]);
+    let call = escrow::deposit(ESCROW, account(owner), account(vault), 7, "rent".to_string()).unwrap();
+
+    assert_eq!(call.program_id, ESCROW);
+    let ids: Vec<AccountId> = call.pre_states.iter().map(|a| a.account_id).collect();
+    assert_eq!(ids, vec![owner, vault]);
+    match decode(&call) {
+        callee::Instruction::Deposit { amount, memo } => assert_eq!((amount, memo.as_str()), (7, "rent")),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn wrong_pda_is_rejected() {
+    let owner = AccountId::new([1u8; 32]);
+    let err = escrow::deposit(ESCROW, account(owner), account(AccountId::new([2u8; 32])), 7, String::new())
+        .unwrap_err();
+    assert!(matches!(err, LezError::PdaMismatch { account_index: 1 }));
+}
+
+#[test]
+fn const_pda_program_is_passed_in() {
+    let owner = AccountId::new([1u8; 32]);
+    let receipt = compute_pda(&callee::RECEIPTS, &[&seed_from_str("receipt"), owner.value()]);
+    let call = escrow::claim(ESCROW, callee::RECEIPTS, account(owner), account(receipt)).unwrap();
+    assert!(matches!(decode(&call), callee::Instruction::Claim));
+
+    let err = escrow::claim(ESCROW, ESCROW, account(owner), account(receipt)).unwrap_err();
+    assert!(matches!(err, LezError::PdaMismatch { account_index: 1 }));
+}
+
+#[test]
+fn builds_release_call_with_defined_type() {
+    let vault = account(AccountId::new([4u8; 32]));
+    let recipients = vec![account(AccountId::new([5u8; 32])), account(AccountId::new([6u8; 32]))];
+    let split = escrow::Split { share: 50, memo: Some("half".to_string()) };
+    let call = escrow::release(ESCROW, vault, recipients, split).unwrap();
+
+    assert_eq!(call.pre_states.len(), 3);
+    match decode(&call) {
+        callee::Instruction::Release { split } => {
+            assert_eq!(split, callee::Split { share: 50, memo: Some("half".to_string()) })
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn rest_account_bounds_are_checked() {
+    let vault = || account(AccountId::new([4u8; 32]));
+    let split = || escrow::Split { share: 1, memo: None };
+    match escrow::release(ESCROW, vault(), vec![], split()).unwrap_err() {
+        LezError::AccountListLength { account_name, actual, min, max } => {
+            assert_eq!((account_name.as_str(), actual, min, max), ("recipients", 0, 1, Some(2)));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    let three = vec![account(AccountId::new([5u8; 32])); 3];
+    assert!(escrow::release(ESCROW, vault(), three, split()).is_err());
+}
+
+#[test]
+fn keyword_names_are_raw_identifiers() {
+    let entry = compute_pda(&ESCROW, &[&seed_from_str("entry"), &4u8.to_seed_bytes()]);
+    let call = registry::r#match(ESCROW, account(entry), 4).unwrap();
+    let (tag, r#type): (u32, u8) = risc0_zkvm::serde::from_slice(&call.instruction_data).unwrap();
+    assert_eq!((tag, r#type), (0, 4));
+    assert!(registry::r#match(ESCROW, account(entry), 5).is_err());
+}
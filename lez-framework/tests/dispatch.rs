@@ -0,0 +1,133 @@
+//! `#[lez_program(dispatch = "discriminator")]`: instructions are tagged with
+//! their lssa-lang discriminator instead of serde's variant index.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_cli::parse::parse_value;
+use lez_cli::serialize::{instruction_tag_risc0, serialize_to_risc0};
+use lez_framework::idl::{compute_discriminator, IdlDispatch, LezIdl};
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+
+#[lez_program(dispatch = "discriminator")]
+mod vault {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[instruction]
+    pub fn deposit(
+        #[account(mut)]
+        vault: AccountWithMetadata,
+        amount: u128,
+        memo: String,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(vault.account)]))
+    }
+
+    #[instruction]
+    pub fn close(#[account(mut)] vault: AccountWithMetadata) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(vault.account)]))
+    }
+
+    #[instruction]
+    pub fn adjust(
+        #[account(mut)]
+        vault: AccountWithMetadata,
+        port: u16,
+        tick: i8,
+        step: i16,
+        delta: i64,
+        total: i128,
+        to: AccountId,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(vault.account)]))
+    }
+}
+
+fn discriminator(name: &str) -> u64 {
+    u64::from_le_bytes(compute_discriminator(name).try_into().unwrap())
+}
+
+#[test]
+fn idl_records_dispatch_mode() {
+    assert_eq!(__program_idl().dispatch, IdlDispatch::Discriminator);
+    let idl: LezIdl = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+    assert_eq!(idl.dispatch, IdlDispatch::Discriminator);
+}
+
+#[test]
+fn instruction_is_prefixed_with_discriminator() {
+    let ix = Instruction::Deposit { amount: 7, memo: "rent".to_string() };
+    let json = serde_json::to_value(&ix).unwrap();
+    assert_eq!(json, serde_json::json!([discriminator("deposit"), 7, "rent"]));
+
+    match serde_json::from_value::<Instruction>(json).unwrap() {
+        Instruction::Deposit { amount, memo } => assert_eq!((amount, memo.as_str()), (7, "rent")),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let close = serde_json::to_value(Instruction::Close).unwrap();
+    assert_eq!(close, serde_json::json!([discriminator("close")]));
+    assert!(matches!(serde_json::from_value(close).unwrap(), Instruction::Close));
+}
+
+#[test]
+fn unknown_discriminator_is_rejected() {
+    let json = serde_json::json!([discriminator("withdraw"), 7]);
+    assert!(serde_json::from_value::<Instruction>(json).is_err());
+}
+
+#[test]
+fn risc0_round_trip() {
+    let ix = Instruction::Deposit { amount: u128::MAX - 1, memo: "rent".to_string() };
+    let words = risc0_zkvm::serde::to_vec(&ix).unwrap();
+    let disc = discriminator("deposit");
+    assert_eq!(&words[..2], &[disc as u32, (disc >> 32) as u32]);
+
+    match risc0_zkvm::serde::from_slice::<Instruction, u32>(&words).unwrap() {
+        Instruction::Deposit { amount, memo } => assert_eq!((amount, memo.as_str()), (u128::MAX - 1, "rent")),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let close = risc0_zkvm::serde::to_vec(&Instruction::Close).unwrap();
+    assert!(matches!(risc0_zkvm::serde::from_slice::<Instruction, u32>(&close).unwrap(), Instruction::Close));
+}
+
+#[test]
+fn guest_decodes_cli_instruction_data() {
+    let idl = LezIdl::from_json(PROGRAM_IDL_JSON).unwrap();
+    let deposit = idl.instructions.iter().find(|ix| ix.name == "deposit").unwrap();
+    let raw = ["340282366920938463463374607431768211454", "rent"];
+    let values: Vec<_> = deposit.args.iter().zip(raw)
+        .map(|(arg, raw)| parse_value(raw, &arg.type_).unwrap())
+        .collect();
+    let args: Vec<_> = deposit.args.iter().map(|arg| &arg.type_).zip(&values).collect();
+
+    let words = serialize_to_risc0(instruction_tag_risc0(&idl, "deposit").unwrap(), &args).unwrap();
+    let ix = Instruction::Deposit { amount: u128::MAX - 1, memo: "rent".to_string() };
+    assert_eq!(words, risc0_zkvm::serde::to_vec(&ix).unwrap());
+    match risc0_zkvm::serde::from_slice::<Instruction, u32>(&words).unwrap() {
+        Instruction::Deposit { amount, memo } => assert_eq!((amount, memo.as_str()), (u128::MAX - 1, "rent")),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let words = serialize_to_risc0(instruction_tag_risc0(&idl, "close").unwrap(), &[]).unwrap();
+    assert!(matches!(risc0_zkvm::serde::from_slice::<Instruction, u32>(&words).unwrap(), Instruction::Close));
+}
+
+#[test]
+fn cli_encodes_every_primitive_like_the_guest() {
+    let idl = LezIdl::from_json(PROGRAM_IDL_JSON).unwrap();
+    let adjust = idl.instructions.iter().find(|ix| ix.name == "adjust").unwrap();
+    let to = AccountId::new([7u8; 32]);
+    let to_hex = "07".repeat(32);
+    let raw = ["65535", "-1", "-300", "-5", "-170141183460469231731687303715884105728", to_hex.as_str()];
+    let values: Vec<_> = adjust.args.iter().zip(raw)
+        .map(|(arg, raw)| parse_value(raw, &arg.type_).unwrap())
+        .collect();
+    let args: Vec<_> = adjust.args.iter().map(|arg| &arg.type_).zip(&values).collect();
+
+    let words = serialize_to_risc0(instruction_tag_risc0(&idl, "adjust").unwrap(), &args).unwrap();
+    let ix = Instruction::Adjust { port: u16::MAX, tick: -1, step: -300, delta: -5, total: i128::MIN, to };
+    assert_eq!(words, risc0_zkvm::serde::to_vec(&ix).unwrap());
+}
@@ -63,6 +63,7 @@ fn e2e_idl_generation() {
     // initialize instruction
     let init = &idl.instructions[0];
     assert_eq!(init.name, "initialize");
+    assert_eq!(init.docs, vec!["Initialize the treasury state."]);
     assert_eq!(init.accounts.len(), 2);
     assert!(init.accounts[0].init, "state should be init");
     assert!(init.accounts[0].writable, "init implies writable");
@@ -76,13 +77,29 @@ fn e2e_idl_generation() {
     // transfer instruction
     let transfer = &idl.instructions[1];
     assert_eq!(transfer.name, "transfer");
+    assert_eq!(transfer.index, Some(5), "transfer pins its variant index");
     assert_eq!(transfer.accounts.len(), 3);
     assert!(transfer.accounts[0].writable, "from should be writable");
+    assert_eq!(
+        transfer.accounts[0].owner.as_deref(),
+        Some("TOKEN_PROGRAM_ID"),
+        "from should carry its owner constraint"
+    );
     assert!(transfer.accounts[1].writable, "to should be writable");
     assert!(transfer.accounts[2].signer, "signer should be signer");
     assert_eq!(transfer.args.len(), 2);
     assert_eq!(transfer.args[0].name, "amount");
     assert_eq!(transfer.args[1].name, "memo");
+
+    // Account data types
+    assert_eq!(idl.accounts.len(), 1);
+    assert_eq!(idl.accounts[0].name, "TreasuryState");
+    assert_eq!(idl.types.len(), 1);
+    assert_eq!(idl.types[0].name, "TreasuryStatus");
+
+    // Program errors
+    assert_eq!(idl.errors.len(), 3);
+    assert_eq!(idl.find_error(6010).map(|e| e.name.as_str()), Some("Frozen"));
 }
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,82 @@
+//! `#[account(has_one = field)]` compares a typed account's data field with
+//! the ID of the sibling account of the same name.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+
+#[lez_program]
+mod vault {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[lez_account]
+    #[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+    pub struct VaultState {
+        pub authority: AccountId,
+        pub total: u128,
+    }
+
+    #[instruction]
+    pub fn withdraw(
+        #[account(mut, has_one = authority)]
+        state: LezAccount<VaultState>,
+        #[account(signer)]
+        authority: AccountWithMetadata,
+        amount: u128,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            state.into_post_state()?,
+            AccountPostState::new(authority.account),
+        ]))
+    }
+}
+
+fn make_account(id: u8, data: Vec<u8>) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new([id; 32]),
+        account: Account { data: data.try_into().unwrap(), ..Account::default() },
+        is_authorized: true,
+    }
+}
+
+fn state_of(authority: u8) -> AccountWithMetadata {
+    let state = vault::VaultState { authority: AccountId::new([authority; 32]), total: 0 };
+    make_account(1, borsh::to_vec(&state).unwrap())
+}
+
+#[test]
+fn matching_account_passes() {
+    let accounts = vec![state_of(2), make_account(2, vec![])];
+    assert!(vault::__validate_withdraw(&accounts, &0).is_ok());
+}
+
+#[test]
+fn other_account_is_unauthorized() {
+    let accounts = vec![state_of(2), make_account(3, vec![])];
+    match vault::__validate_withdraw(&accounts, &0).unwrap_err() {
+        LezError::Unauthorized { message } => {
+            assert_eq!(message, "Account 'authority' (index 1) does not match 'state.authority'");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn undecodable_data_is_reported() {
+    let accounts = vec![make_account(1, vec![1, 2, 3]), make_account(2, vec![])];
+    match vault::__validate_withdraw(&accounts, &0).unwrap_err() {
+        LezError::DeserializationError { account_index, .. } => assert_eq!(account_index, 0),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn relation_recorded_in_idl() {
+    let idl = __program_idl();
+    let accounts = &idl.instructions[0].accounts;
+    assert_eq!(accounts[0].account_type.as_deref(), Some("VaultState"));
+    assert_eq!(accounts[0].has_one, vec!["authority"]);
+    assert!(accounts[1].has_one.is_empty());
+}
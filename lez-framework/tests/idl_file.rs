@@ -0,0 +1,25 @@
+//! Compiling a `#[lez_program]` writes its IDL to `PROGRAM_IDL_PATH`.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+
+#[lez_program]
+mod counter {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[instruction]
+    pub fn increment(#[account(mut)] counter: AccountWithMetadata, by: u64) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(counter.account)]))
+    }
+}
+
+#[test]
+fn idl_file_written_at_build_time() {
+    assert!(PROGRAM_IDL_PATH.ends_with("counter.idl.json"));
+    let on_disk: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(PROGRAM_IDL_PATH).unwrap()).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+    assert_eq!(on_disk, expected);
+}
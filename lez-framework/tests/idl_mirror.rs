@@ -0,0 +1,135 @@
+//! The macros crate writes the IDL from its own copy of the IDL structs.
+//! `LezIdl` must read that JSON back and write it out unchanged, so a field
+//! added, renamed or dropped on one side only fails here.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::idl::LezIdl;
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+use nssa_core::program::ProgramId;
+
+const PROGRAM: ProgramId = [7u32; 8];
+const TOKEN_PROGRAM: ProgramId = [9u32; 8];
+
+/// Every part of the IDL, at least once.
+#[lez_program(program_id = "crate::PROGRAM", dispatch = "discriminator")]
+mod ledger {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[lez_type]
+    #[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+    pub enum Status {
+        #[default]
+        Open,
+        Frozen { until: u64 },
+    }
+
+    #[lez_account]
+    #[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+    pub struct Book {
+        pub authority: AccountId,
+        pub threshold: u8,
+        pub members: Vec<[u8; 32]>,
+        pub status: Status,
+    }
+
+    #[lez_error]
+    pub enum LedgerError {
+        #[msg("Not enough funds in the escrow")]
+        InsufficientFunds,
+        Frozen = 10,
+    }
+
+    /// Open a book for `authority`.
+    #[instruction]
+    pub fn open(
+        #[account(signer)]
+        authority: AccountWithMetadata,
+        /// The new book.
+        #[account(init, space = 8, pda = [literal("book"), account("authority"), arg("id")])]
+        book: AccountWithMetadata,
+        #[account(init_if_needed, max_space = 64)]
+        cache: AccountWithMetadata,
+        /// Caller-chosen book number.
+        id: u64,
+        label: Option<String>,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(authority.account),
+            AccountPostState::new_claimed(book.account),
+            AccountPostState::new(cache.account),
+        ]))
+    }
+
+    #[instruction(threshold(signers, book.threshold, book.members))]
+    pub fn settle(
+        #[account(mut, has_one = authority)]
+        book: LezAccount<Book>,
+        authority: AccountWithMetadata,
+        #[account(mut, close = recipient)]
+        escrow: AccountWithMetadata,
+        #[account(mut, constraint = recipient.account_id != escrow.account_id)]
+        recipient: AccountWithMetadata,
+        #[account(pda = [literal("holding"), account("recipient")], pda_program = const(TOKEN_PROGRAM))]
+        holding: AccountWithMetadata,
+        #[account(owner = TOKEN_PROGRAM, min = 1, max = 3)]
+        signers: Vec<AccountWithMetadata>,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            book.into_post_state()?,
+            AccountPostState::new(authority.account),
+            AccountPostState::new(escrow.account),
+            AccountPostState::new(recipient.account),
+            AccountPostState::new(holding.account),
+        ]))
+    }
+
+    #[instruction]
+    pub fn withdraw(
+        #[account(mut, constraint = escrow.account.balance >= *amount @ LedgerError::InsufficientFunds)]
+        escrow: AccountWithMetadata,
+        owner: AccountWithMetadata,
+        #[account(pda = [literal("holding"), account("owner")], pda_program = arg("token_program_id"))]
+        holding: AccountWithMetadata,
+        amount: u128,
+        split: (u64, [u8; 4]),
+        token_program_id: ProgramId,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(escrow.account),
+            AccountPostState::new(owner.account),
+            AccountPostState::new(holding.account),
+        ]))
+    }
+}
+
+#[test]
+fn core_idl_round_trips_generated_json() {
+    let generated: serde_json::Value = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+    let idl = LezIdl::from_json(PROGRAM_IDL_JSON).unwrap();
+    let rewritten: serde_json::Value = serde_json::to_value(&idl).unwrap();
+    assert_eq!(rewritten, generated);
+}
+
+#[test]
+fn fixture_covers_every_optional_field() {
+    let idl: serde_json::Value = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+    let accounts: Vec<&serde_json::Value> = idl["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|ix| ix["accounts"].as_array().unwrap())
+        .collect();
+    for field in [
+        "docs", "init_if_needed", "space", "max_space", "owner", "pda", "account_type",
+        "has_one", "close", "constraints", "rest", "min_accounts", "max_accounts",
+    ] {
+        assert!(accounts.iter().any(|a| a.get(field).is_some()), "no account has `{}`", field);
+    }
+    for field in ["docs", "accounts", "types", "errors", "dispatch"] {
+        assert!(idl.get(field).is_some(), "IDL has no `{}`", field);
+    }
+    assert!(idl["instructions"].as_array().unwrap().iter().any(|ix| ix.get("threshold").is_some()));
+}
@@ -0,0 +1,85 @@
+//! Argument and field types beyond the primitives are described in full:
+//! `Option`, tuples, nested generics and `const`-length arrays.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use serde_json::json;
+
+// The generated `Instruction` enum lives outside the module
+use registry::KEY_LEN;
+
+#[lez_program]
+mod registry {
+    #[allow(unused_imports)]
+    use super::*;
+
+    pub const KEY_LEN: usize = 32;
+
+    #[lez_type]
+    #[derive(BorshSerialize, BorshDeserialize)]
+    pub struct Entry {
+        pub key: [u8; KEY_LEN],
+        pub range: (u16, i64),
+    }
+
+    #[instruction]
+    #[allow(clippy::boxed_local)]
+    pub fn register(
+        #[account(mut)]
+        registry: AccountWithMetadata,
+        label: std::string::String,
+        owner: Option<[u8; KEY_LEN]>,
+        limits: (u64, Option<i16>),
+        members: Vec<Option<[u8; 32]>>,
+        weight: Box<u32>,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(registry.account)]))
+    }
+}
+
+fn arg_types() -> Vec<serde_json::Value> {
+    let idl: serde_json::Value = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+    idl["instructions"][0]["args"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|arg| arg["type"].clone())
+        .collect()
+}
+
+#[test]
+fn composite_argument_types() {
+    assert_eq!(
+        arg_types(),
+        vec![
+            json!("string"),
+            json!({"option": {"array": ["u8", 32]}}),
+            json!({"tuple": ["u64", {"option": "i16"}]}),
+            json!({"vec": {"option": {"array": ["u8", 32]}}}),
+            json!("u32"),
+        ]
+    );
+}
+
+#[test]
+fn composite_field_types() {
+    let idl = __program_idl();
+    let fields = serde_json::to_value(&idl.types[0].type_.fields).unwrap();
+    assert_eq!(
+        fields,
+        json!([
+            {"name": "key", "type": {"array": ["u8", 32]}},
+            {"name": "range", "type": {"tuple": ["u16", "i64"]}},
+        ])
+    );
+}
+
+#[test]
+fn core_types_read_tuples() {
+    let idl = __program_idl();
+    assert!(matches!(
+        &idl.instructions[0].args[2].type_,
+        lez_framework::idl::IdlType::Tuple { tuple } if tuple.len() == 2
+    ));
+}
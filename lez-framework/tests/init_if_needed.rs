@@ -0,0 +1,61 @@
+//! `#[account(init_if_needed)]` accepts both fresh and existing accounts;
+//! `space` / `max_space` are recorded in the IDL.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+
+#[lez_program]
+mod registry {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[instruction]
+    pub fn upsert(
+        #[account(init_if_needed, max_space = 64)]
+        entry: AccountWithMetadata,
+        #[account(init, space = 8)]
+        counter: AccountWithMetadata,
+        #[account(signer)]
+        owner: AccountWithMetadata,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(entry.account),
+            AccountPostState::new_claimed(counter.account),
+            AccountPostState::new(owner.account),
+        ]))
+    }
+}
+
+fn make_account(balance: u128) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new([1u8; 32]),
+        account: Account { balance, ..Account::default() },
+        is_authorized: true,
+    }
+}
+
+#[test]
+fn existing_account_passes_init_if_needed() {
+    let accounts = vec![make_account(10), make_account(0), make_account(0)];
+    assert!(registry::__validate_upsert(&accounts).is_ok());
+}
+
+#[test]
+fn existing_account_still_fails_init() {
+    let accounts = vec![make_account(0), make_account(10), make_account(0)];
+    assert_eq!(
+        registry::__validate_upsert(&accounts).unwrap_err(),
+        LezError::AccountAlreadyInitialized { account_index: 1 }
+    );
+}
+
+#[test]
+fn sizes_recorded_in_idl() {
+    let idl = __program_idl();
+    let accounts = &idl.instructions[0].accounts;
+    assert!(accounts[0].init_if_needed && !accounts[0].init && accounts[0].writable);
+    assert_eq!((accounts[0].space, accounts[0].max_space), (None, Some(64)));
+    assert_eq!((accounts[1].space, accounts[1].max_space), (Some(8), None));
+}
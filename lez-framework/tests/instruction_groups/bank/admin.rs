@@ -0,0 +1,30 @@
+use super::*;
+
+#[lez_type]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct FeeSchedule {
+    pub bps: u16,
+}
+
+/// Change the fee schedule.
+#[instruction]
+pub fn set_fee(
+    #[account(mut, signer)]
+    bank: AccountWithMetadata,
+    schedule: FeeSchedule,
+) -> LezResult {
+    Ok(LezOutput::states_only(vec![AccountPostState::new(bank.account)]))
+}
+
+#[instruction_group]
+mod risk {
+    use super::*;
+
+    #[instruction]
+    pub fn pause(
+        #[account(mut)]
+        bank: AccountWithMetadata,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(bank.account)]))
+    }
+}
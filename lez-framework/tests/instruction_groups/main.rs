@@ -0,0 +1,82 @@
+//! Instructions spread over `#[instruction_group]` modules and
+//! `instruction_groups!` files still make up one `Instruction` enum and one IDL.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use nssa_core::program::ProgramId;
+
+// The generated `Instruction` enum lives outside the module
+use bank::admin::FeeSchedule;
+
+const PROGRAM: ProgramId = [7u32; 8];
+
+#[lez_program(program_id = "crate::PROGRAM")]
+mod bank {
+    #[allow(unused_imports)]
+    use super::*;
+
+    instruction_groups! {
+        mod admin;
+    }
+
+    #[instruction]
+    pub fn open(
+        #[account(init, pda = literal("bank"))]
+        bank: AccountWithMetadata,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(bank.account)]))
+    }
+
+    #[instruction_group]
+    mod user {
+        use super::*;
+
+        #[instruction]
+        pub fn deposit(
+            #[account(mut)]
+            bank: AccountWithMetadata,
+            #[account(signer)]
+            owner: AccountWithMetadata,
+            amount: u64,
+        ) -> LezResult {
+            Ok(LezOutput::states_only(vec![
+                AccountPostState::new(bank.account),
+                AccountPostState::new(owner.account),
+            ]))
+        }
+    }
+}
+
+fn instruction_names() -> Vec<String> {
+    __program_idl().instructions.iter().map(|ix| ix.name.clone()).collect()
+}
+
+#[test]
+fn one_idl_for_all_groups() {
+    assert_eq!(instruction_names(), vec!["set_fee", "pause", "open", "deposit"]);
+    let indices: Vec<Option<u32>> = __program_idl().instructions.iter().map(|ix| ix.index).collect();
+    assert_eq!(indices, vec![Some(0), Some(1), Some(2), Some(3)]);
+}
+
+#[test]
+fn group_files_contribute_types() {
+    let idl = __program_idl();
+    assert_eq!(idl.types.len(), 1);
+    assert_eq!(idl.types[0].name, "FeeSchedule");
+    assert_eq!(idl.instructions[0].args[0].name, "schedule");
+}
+
+#[test]
+fn one_instruction_enum() {
+    let ix = Instruction::Deposit { amount: 5 };
+    assert!(matches!(ix, Instruction::Deposit { amount: 5 }));
+    let ix = Instruction::Pause;
+    assert!(matches!(ix, Instruction::Pause));
+}
+
+#[test]
+fn handlers_keep_their_module_paths() {
+    let _: fn(AccountWithMetadata) -> LezResult = bank::admin::risk::pause;
+    let _: fn(AccountWithMetadata, AccountWithMetadata, u64) -> LezResult = bank::user::deposit;
+}
@@ -0,0 +1,119 @@
+//! A PDA seed may reference a PDA account declared after it; PDAs are
+//! computed in seed order rather than declaration order. `arg` seeds use
+//! the canonical encoding of their type, recorded in the IDL.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::idl::{IdlSeed, IdlType};
+use lez_framework::pda::{compute_pda, seed_from_str, PdaSeedArg};
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+use nssa_core::program::ProgramId;
+
+#[lez_program(program_id = "crate::PROGRAM")]
+mod pools {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[instruction]
+    pub fn open_position(
+        mint: AccountWithMetadata,
+        #[account(init, pda = [literal("position"), account("pool")])]
+        position: AccountWithMetadata,
+        #[account(pda = [literal("pool"), account("mint")])]
+        pool: AccountWithMetadata,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![
+            AccountPostState::new(mint.account),
+            AccountPostState::new_claimed(position.account),
+            AccountPostState::new(pool.account),
+        ]))
+    }
+
+    #[instruction]
+    pub fn open_round(
+        #[account(init, pda = [literal("round"), arg("index")])]
+        round: AccountWithMetadata,
+        index: u64,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new_claimed(round.account)]))
+    }
+}
+
+const PROGRAM: ProgramId = [7u32; 8];
+
+fn account(id: AccountId) -> AccountWithMetadata {
+    AccountWithMetadata { account_id: id, account: Account::default(), is_authorized: false }
+}
+
+fn accounts() -> Vec<AccountWithMetadata> {
+    let mint = AccountId::new([1u8; 32]);
+    let pool = compute_pda(&PROGRAM, &[&seed_from_str("pool"), mint.value()]);
+    let position = compute_pda(&PROGRAM, &[&seed_from_str("position"), pool.value()]);
+    vec![account(mint), account(position), account(pool)]
+}
+
+#[test]
+fn forward_pda_reference_verifies() {
+    assert!(pools::__validate_pdas_open_position(&accounts(), Some(&PROGRAM)).is_ok());
+}
+
+#[test]
+fn wrong_dependency_is_a_mismatch() {
+    let mut accounts = accounts();
+    accounts[2] = account(AccountId::new([2u8; 32]));
+    match pools::__validate_pdas_open_position(&accounts, Some(&PROGRAM)).unwrap_err() {
+        LezError::PdaMismatch { account_index } => assert_eq!(account_index, 1),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn without_program_id_owner_is_checked() {
+    let index = 5u64;
+    let round = compute_pda(&PROGRAM, &[&seed_from_str("round"), &index.to_seed_bytes()]);
+    let mut accounts = [account(round)];
+    // Unclaimed: checked once this program owns it
+    assert!(pools::__validate_pdas_open_round(&accounts, None, &index).is_ok());
+    accounts[0].account.program_owner = PROGRAM;
+    assert!(pools::__validate_pdas_open_round(&accounts, None, &index).is_ok());
+    assert!(pools::__validate_pdas_open_round(&accounts, None, &6).is_err());
+    accounts[0].account.program_owner = [8u32; 8];
+    assert!(pools::__validate_pdas_open_round(&accounts, None, &index).is_err());
+}
+
+#[test]
+fn idl_orders_pdas_by_seeds() {
+    let idl = __program_idl();
+    let order: Vec<&str> = idl.instructions[0]
+        .pda_accounts_in_seed_order()
+        .iter()
+        .map(|a| a.name.as_str())
+        .collect();
+    assert_eq!(order, vec!["pool", "position"]);
+}
+
+#[test]
+fn integer_arg_seed_is_little_endian() {
+    let mut seed = [0u8; 32];
+    seed[..8].copy_from_slice(&5u64.to_le_bytes());
+    assert_eq!(5u64.to_seed_bytes(), seed);
+    let round = compute_pda(&PROGRAM, &[&seed_from_str("round"), &seed]);
+    assert!(pools::__validate_pdas_open_round(&[account(round)], Some(&PROGRAM), &5).is_ok());
+    assert!(pools::__validate_pdas_open_round(&[account(round)], Some(&PROGRAM), &6).is_err());
+}
+
+#[test]
+fn arg_seed_type_recorded_in_idl() {
+    let idl = __program_idl();
+    let ix = &idl.instructions[1];
+    let seed = &ix.accounts[0].pda.as_ref().unwrap().seeds[1];
+    match seed {
+        IdlSeed::Arg { path, type_: Some(IdlType::Primitive(ty)) } => {
+            assert_eq!(path, "index");
+            assert_eq!(ty, "u64");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(matches!(ix.seed_arg_type(seed), Some(IdlType::Primitive(ty)) if ty == "u64"));
+}
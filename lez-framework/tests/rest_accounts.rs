@@ -0,0 +1,80 @@
+//! Constraints on a `Vec<AccountWithMetadata>` parameter apply to every
+//! element, and `min` / `max` bound its length.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+
+pub const MEMBER_PROGRAM_ID: ProgramId = [5u32; 8];
+
+#[lez_program]
+mod council {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[instruction]
+    pub fn approve(
+        #[account(mut)]
+        proposal: AccountWithMetadata,
+        #[account(signer, owner = MEMBER_PROGRAM_ID, min = 1, max = 3)]
+        members: Vec<AccountWithMetadata>,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![AccountPostState::new(proposal.account)]))
+    }
+}
+
+fn make_account(id: u8, authorized: bool) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new([id; 32]),
+        account: Account { program_owner: MEMBER_PROGRAM_ID, ..Account::default() },
+        is_authorized: authorized,
+    }
+}
+
+#[test]
+fn every_member_is_checked() {
+    let ok = vec![make_account(0, false), make_account(1, true), make_account(2, true)];
+    assert!(council::__validate_approve(&ok).is_ok());
+
+    let unsigned = vec![make_account(0, false), make_account(1, true), make_account(2, false)];
+    match council::__validate_approve(&unsigned).unwrap_err() {
+        LezError::Unauthorized { message } => {
+            assert_eq!(message, "Account 'members' (index 2) must be a signer");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let mut foreign = make_account(3, true);
+    foreign.account.program_owner = [9u32; 8];
+    let wrong_owner = vec![make_account(0, false), make_account(1, true), foreign];
+    match council::__validate_approve(&wrong_owner).unwrap_err() {
+        LezError::InvalidAccountOwner { account_index, .. } => assert_eq!(account_index, 2),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn member_count_is_bounded() {
+    let none = vec![make_account(0, false)];
+    let err = council::__validate_approve(&none).unwrap_err();
+    assert_eq!(
+        err,
+        LezError::AccountListLength { account_name: "members".to_string(), actual: 0, min: 1, max: Some(3) }
+    );
+    assert_eq!(err.to_string(), "Account list 'members' has 0 accounts, expected 1 to 3");
+
+    let too_many: Vec<_> = (0..5).map(|i| make_account(i, true)).collect();
+    assert!(matches!(
+        council::__validate_approve(&too_many).unwrap_err(),
+        LezError::AccountListLength { actual: 4, .. }
+    ));
+}
+
+#[test]
+fn bounds_recorded_in_idl() {
+    let idl = __program_idl();
+    let members = &idl.instructions[0].accounts[1];
+    assert!(members.rest);
+    assert_eq!((members.min_accounts, members.max_accounts), (Some(1), Some(3)));
+}
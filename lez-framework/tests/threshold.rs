@@ -0,0 +1,103 @@
+//! `#[instruction(threshold(...))]` counts distinct authorized members of a
+//! rest account against a stored threshold and member list.
+
+#![allow(dead_code, unused_variables)]
+
+use lez_framework::prelude::*;
+use nssa_core::account::AccountId;
+
+#[lez_program]
+mod multisig {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[lez_account]
+    #[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+    pub struct MultisigState {
+        pub threshold: u8,
+        pub members: Vec<[u8; 32]>,
+    }
+
+    #[instruction(threshold(signers, state.threshold, state.members))]
+    pub fn execute(
+        state: LezAccount<MultisigState>,
+        signers: Vec<AccountWithMetadata>,
+    ) -> LezResult {
+        Ok(LezOutput::states_only(vec![state.into_post_state()?]))
+    }
+
+    #[instruction(threshold(signers, 1))]
+    pub fn ping(signers: Vec<AccountWithMetadata>) -> LezResult {
+        Ok(LezOutput::empty())
+    }
+}
+
+fn state(threshold: u8, members: &[u8]) -> AccountWithMetadata {
+    let data = multisig::MultisigState {
+        threshold,
+        members: members.iter().map(|m| [*m; 32]).collect(),
+    };
+    AccountWithMetadata {
+        account_id: AccountId::new([0u8; 32]),
+        account: Account { data: borsh::to_vec(&data).unwrap().try_into().unwrap(), ..Account::default() },
+        is_authorized: false,
+    }
+}
+
+fn signer(id: u8, authorized: bool) -> AccountWithMetadata {
+    AccountWithMetadata {
+        account_id: AccountId::new([id; 32]),
+        account: Account::default(),
+        is_authorized: authorized,
+    }
+}
+
+fn unauthorized_message(err: LezError) -> String {
+    match err {
+        LezError::Unauthorized { message } => message,
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn threshold_met() {
+    let accounts = vec![state(2, &[1, 2, 3]), signer(1, true), signer(3, true), signer(2, false)];
+    assert!(multisig::__validate_execute(&accounts).is_ok());
+}
+
+#[test]
+fn threshold_not_met() {
+    let accounts = vec![state(2, &[1, 2, 3]), signer(1, true), signer(2, false)];
+    let message = unauthorized_message(multisig::__validate_execute(&accounts).unwrap_err());
+    assert_eq!(message, "1 of 2 required signatures");
+}
+
+#[test]
+fn duplicate_signer_rejected() {
+    let accounts = vec![state(2, &[1, 2]), signer(1, true), signer(1, true)];
+    let message = unauthorized_message(multisig::__validate_execute(&accounts).unwrap_err());
+    assert!(message.contains("(index 2) is listed more than once"), "{}", message);
+}
+
+#[test]
+fn non_member_rejected() {
+    let accounts = vec![state(1, &[1, 2]), signer(9, true)];
+    let message = unauthorized_message(multisig::__validate_execute(&accounts).unwrap_err());
+    assert!(message.contains("(index 1) is not a member"), "{}", message);
+}
+
+#[test]
+fn literal_threshold_without_members() {
+    assert!(multisig::__validate_ping(&[signer(4, true)]).is_ok());
+    assert!(multisig::__validate_ping(&[signer(4, false)]).is_err());
+}
+
+#[test]
+fn threshold_recorded_in_idl() {
+    let idl = __program_idl();
+    let threshold = idl.instructions[0].threshold.as_ref().unwrap();
+    assert_eq!(threshold.signers, "signers");
+    assert_eq!(threshold.min, "state.threshold");
+    assert_eq!(threshold.members.as_deref(), Some("state.members"));
+    assert_eq!(idl.instructions[1].threshold.as_ref().unwrap().min, "1");
+}
@@ -7,27 +7,65 @@
 
 use lez_framework::prelude::*;
 
-#[lez_program]
+mod state;
+pub use state::TreasuryStatus;
+
+/// Program that must own the `from` account in `transfer`.
+pub const TOKEN_PROGRAM_ID: ProgramId = [7u32; 8];
+
+/// This program's own id, used to verify PDA accounts.
+pub const TREASURY_PROGRAM_ID: ProgramId = [3u32; 8];
+
+/// Multi-admin treasury.
+#[lez_program(program_id = "TREASURY_PROGRAM_ID", types_from = "src/state.rs")]
 mod treasury {
     #[allow(unused_imports)]
     use super::*;
 
+    /// Decoded data of the treasury state account.
+    #[lez_account]
+    #[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+    pub struct TreasuryState {
+        pub threshold: u64,
+        pub status: TreasuryStatus,
+        pub admins: Vec<[u8; 32]>,
+    }
+
+    #[lez_error]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TreasuryError {
+        #[msg("Threshold must be at least 1")]
+        InvalidThreshold,
+        #[msg("Treasury is frozen")]
+        Frozen = 10,
+        Unknown,
+    }
+
     /// Initialize the treasury state.
     #[instruction]
     pub fn initialize(
+        /// The treasury state account, created here.
         #[account(init, pda = literal("treasury_state"))]
-        state: AccountWithMetadata,
+        mut state: LezAccount<TreasuryState>,
         #[account(signer)]
         authority: AccountWithMetadata,
+        /// Number of admin approvals required.
         threshold: u64,
     ) -> LezResult {
-        Ok(LezOutput::states_only(vec![]))
+        if threshold == 0 {
+            return Err(TreasuryError::InvalidThreshold.into());
+        }
+        state.threshold = threshold;
+        Ok(LezOutput::states_only(vec![
+            state.into_post_state_claimed()?,
+            AccountPostState::new(authority.account),
+        ]))
     }
 
     /// Transfer funds.
-    #[instruction]
+    #[instruction(index = 5)]
     pub fn transfer(
-        #[account(mut)]
+        #[account(mut, owner = TOKEN_PROGRAM_ID)]
         from: AccountWithMetadata,
         #[account(mut)]
         to: AccountWithMetadata,
@@ -52,6 +90,12 @@ mod tests {
         }
     }
 
+    fn make_owned_account(owner: ProgramId) -> AccountWithMetadata {
+        let mut acc = make_account(false);
+        acc.account.program_owner = owner;
+        acc
+    }
+
     #[test]
     fn idl_has_expected_instructions() {
         let idl = __program_idl();
@@ -62,6 +106,45 @@ mod tests {
         assert_eq!(idl.instructions[1].name, "transfer");
     }
 
+    #[test]
+    fn explicit_instruction_index() {
+        let idl = __program_idl();
+        assert_eq!(idl.instructions[0].index, Some(0));
+        assert_eq!(idl.instructions[1].index, Some(5));
+        assert_eq!(idl.instruction_index("transfer"), Some(5));
+
+        let json_idl: lez_framework::idl::LezIdl = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+        assert_eq!(json_idl.instruction_index("transfer"), Some(5));
+
+        // The hand-written serde impls still round-trip through a
+        // self-describing format
+        let ix = Instruction::Transfer { amount: 7, memo: "rent".to_string() };
+        let json = serde_json::to_string(&ix).unwrap();
+        assert_eq!(json, r#"{"Transfer":{"amount":7,"memo":"rent"}}"#);
+        match serde_json::from_str::<Instruction>(&json).unwrap() {
+            Instruction::Transfer { amount, memo } => assert_eq!((amount, memo.as_str()), (7, "rent")),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(serde_json::from_str::<Instruction>(r#"{"Close":{}}"#).is_err());
+    }
+
+    #[test]
+    fn idl_docs() {
+        let idl = __program_idl();
+        assert_eq!(idl.docs, vec!["Multi-admin treasury."]);
+        let init = &idl.instructions[0];
+        assert_eq!(init.docs, vec!["Initialize the treasury state."]);
+        assert_eq!(init.accounts[0].docs, vec!["The treasury state account, created here."]);
+        assert!(init.accounts[1].docs.is_empty());
+        assert_eq!(init.args[0].docs, vec!["Number of admin approvals required."]);
+
+        let json_idl: lez_framework::idl::LezIdl = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+        assert_eq!(json_idl.docs, idl.docs);
+        assert_eq!(json_idl.instructions[0].docs, init.docs);
+        assert_eq!(json_idl.instructions[0].accounts[0].docs, init.accounts[0].docs);
+        assert_eq!(json_idl.instructions[0].args[0].docs, init.args[0].docs);
+    }
+
     #[test]
     fn idl_json_round_trip() {
         let idl: lez_framework::idl::LezIdl =
@@ -70,6 +153,26 @@ mod tests {
         assert_eq!(idl.instructions.len(), 2);
     }
 
+    #[test]
+    fn idl_json_is_fully_structured() {
+        let json: serde_json::Value = serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+        assert_eq!(serde_json::to_value(__program_idl()).unwrap(), json);
+
+        assert_eq!(json["spec"], "0.1.0");
+        assert_eq!(json["metadata"]["name"], "treasury");
+        let transfer = &json["instructions"][1];
+        assert_eq!(transfer["variant"], "Transfer");
+        assert_eq!(transfer["execution"]["public"], true);
+        assert_eq!(transfer["discriminator"].as_array().unwrap().len(), 8);
+        assert_eq!(transfer["accounts"][0]["visibility"], serde_json::json!(["public"]));
+        assert_eq!(transfer["args"][0]["type"], "u64");
+        assert_eq!(transfer["args"][1]["type"], "string");
+        assert_eq!(
+            json["accounts"][0]["type"]["fields"][2]["type"],
+            serde_json::json!({"vec": {"array": ["u8", 32]}})
+        );
+    }
+
     #[test]
     fn initialize_instruction_metadata() {
         let idl = __program_idl();
@@ -101,13 +204,121 @@ mod tests {
         assert_eq!(ix.args[1].name, "memo");
     }
 
+    #[test]
+    fn idl_types_registered() {
+        let idl = __program_idl();
+        assert_eq!(idl.accounts.len(), 1);
+        let state = &idl.accounts[0];
+        assert_eq!(state.name, "TreasuryState");
+        assert_eq!(state.type_.kind, "struct");
+        let field_names: Vec<&str> = state.type_.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(field_names, ["threshold", "status", "admins"]);
+        assert!(matches!(&state.type_.fields[1].type_,
+            lez_framework::idl::IdlType::Defined { defined } if defined == "TreasuryStatus"));
+
+        assert_eq!(idl.types.len(), 1);
+        let status = &idl.types[0];
+        assert_eq!(status.name, "TreasuryStatus");
+        assert_eq!(status.type_.kind, "enum");
+        assert_eq!(status.type_.variants.len(), 2);
+        assert_eq!(status.type_.variants[1].fields[0].name, "until");
+
+        // The const JSON must describe the same types
+        let json_idl: lez_framework::idl::LezIdl =
+            serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+        assert_eq!(
+            serde_json::to_value(&json_idl.accounts).unwrap(),
+            serde_json::to_value(&idl.accounts).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&json_idl.types).unwrap(),
+            serde_json::to_value(&idl.types).unwrap()
+        );
+    }
+
+    #[test]
+    fn idl_errors_registered() {
+        let idl = __program_idl();
+        let codes: Vec<(u32, &str)> = idl.errors.iter().map(|e| (e.code, e.name.as_str())).collect();
+        assert_eq!(codes, [(6000, "InvalidThreshold"), (6010, "Frozen"), (6011, "Unknown")]);
+        assert_eq!(idl.errors[0].msg.as_deref(), Some("Threshold must be at least 1"));
+        assert!(idl.errors[2].msg.is_none());
+
+        let json_idl: lez_framework::idl::LezIdl =
+            serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+        assert_eq!(
+            serde_json::to_value(&json_idl.errors).unwrap(),
+            serde_json::to_value(&idl.errors).unwrap()
+        );
+    }
+
+    #[test]
+    fn program_error_converts_to_lez_error() {
+        let err = treasury::initialize(LezAccount::init(make_account(false)), make_account(true), 0)
+            .unwrap_err();
+        assert_eq!(err.error_code(), 6000);
+        assert_eq!(__program_idl().find_error(err.error_code()).unwrap().name, "InvalidThreshold");
+
+        let frozen: LezError = treasury::TreasuryError::Frozen.into();
+        assert_eq!(frozen.error_code(), 6010);
+        assert_eq!(frozen.to_string(), "Program error 10: Treasury is frozen");
+    }
+
+    #[test]
+    fn owner_constraint_in_idl() {
+        let idl = __program_idl();
+        let ix = &idl.instructions[1];
+        assert_eq!(ix.accounts[0].owner.as_deref(), Some("TOKEN_PROGRAM_ID"));
+        assert!(ix.accounts[1].owner.is_none());
+
+        let json_idl: lez_framework::idl::LezIdl =
+            serde_json::from_str(PROGRAM_IDL_JSON).unwrap();
+        assert_eq!(json_idl.instructions[1].accounts[0].owner.as_deref(), Some("TOKEN_PROGRAM_ID"));
+    }
+
+    #[test]
+    fn owner_constraint_enforced() {
+        let ok = vec![make_owned_account(TOKEN_PROGRAM_ID), make_account(false), make_account(true)];
+        assert!(treasury::__validate_transfer(&ok, &0, &String::new()).is_ok());
+
+        let wrong = vec![make_owned_account([9u32; 8]), make_account(false), make_account(true)];
+        let err = treasury::__validate_transfer(&wrong, &0, &String::new()).unwrap_err();
+        match err {
+            LezError::InvalidAccountOwner { account_index, .. } => assert_eq!(account_index, 0),
+            _ => panic!("Expected InvalidAccountOwner, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn pda_constraint_enforced() {
+        let state_id = lez_framework::pda::compute_pda(
+            &TREASURY_PROGRAM_ID,
+            &[&lez_framework::pda::seed_from_str("treasury_state")],
+        );
+        let mut state = make_account(false);
+        state.account_id = state_id;
+        let ok = vec![state, make_account(true)];
+        assert!(treasury::__validate_pdas_initialize(&ok, Some(&TREASURY_PROGRAM_ID), &2).is_ok());
+
+        // Same seeds under another program id must not match
+        let err = treasury::__validate_pdas_initialize(&ok, Some(&TOKEN_PROGRAM_ID), &2).unwrap_err();
+        assert!(matches!(err, LezError::PdaMismatch { account_index: 0 }));
+
+        let wrong = vec![make_account(false), make_account(true)];
+        let err = treasury::__validate_pdas_initialize(&wrong, Some(&TREASURY_PROGRAM_ID), &2).unwrap_err();
+        assert!(matches!(err, LezError::PdaMismatch { account_index: 0 }));
+    }
+
     /// Validates the cfg-gate fix: handler functions are directly callable
     /// from host-side tests without triggering zkVM syscalls.
     #[test]
     fn handler_initialize_callable() {
         let acc = make_account(true);
-        let result = treasury::initialize(acc.clone(), acc.clone(), 5);
-        assert!(result.is_ok());
+        let result = treasury::initialize(LezAccount::init(acc.clone()), acc.clone(), 5);
+        let output = result.expect("initialize should succeed");
+        let state_data = &output.post_states[0].account().data;
+        let state = treasury::TreasuryState::try_from_slice(state_data).unwrap();
+        assert_eq!(state.threshold, 5);
     }
 
     #[test]
@@ -0,0 +1,13 @@
+//! Types shared outside the program module, registered in the IDL
+//! through `#[lez_program(types_from = "src/state.rs")]`.
+
+use lez_framework::prelude::*;
+
+/// Lifecycle of a treasury.
+#[lez_type]
+#[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
+pub enum TreasuryStatus {
+    #[default]
+    Active,
+    Frozen { until: u64 },
+}